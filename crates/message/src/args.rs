@@ -1,5 +1,9 @@
-use clap::{Parser, Subcommand};
+use carrier::lsb::ChannelBits;
+use carrier::{watermark, zero_width};
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use payload::passphrase::Cipher;
 use png_spec::chunk_type::ChunkType;
+use png_spec::strip::Rule;
 use std::path::PathBuf;
 
 #[derive(Debug, Parser)]
@@ -17,13 +21,43 @@ pub enum Commands {
     Decode(DecodeArgs),
     Remove(RemoveArgs),
     Print(PrintArgs),
+    Xmp(XmpArgs),
+    Strip(StripArgs),
+    Idat(IdatArgs),
+    Optimize(OptimizeArgs),
+    Hash(HashArgs),
+    Keygen(KeygenArgs),
+    Verify(VerifyArgs),
+    Embed(EmbedArgs),
+    Extract(ExtractArgs),
+    Split(SplitArgs),
+    Combine(CombineArgs),
+    Lsb(LsbArgs),
+    Watermark(WatermarkArgs),
+    /// Hides a file in the order of the palette entries of an indexed image, which leaves the
+    /// pixels looking the same
+    Palette(CarrierArgs),
+    /// Hides a file in the filter type of every scanline, filtering the pixels again to match
+    Filters(CarrierArgs),
+    /// Hides a file in empty stored blocks of the compressed image data, which still inflates to
+    /// the same bytes
+    Zlib(CarrierArgs),
+    /// Hides a file in the color of fully transparent pixels, which never shows
+    Transparent(CarrierArgs),
+    Text(TextArgs),
+    Capacity(CapacityArgs),
+    Detect(DetectArgs),
 }
 
+/// Hides a message in a chunk of a PNG file
 #[derive(Parser, Debug)]
 pub struct EncodeArgs {
     #[clap(value_parser)]
     pub path: PathBuf,
 
+    /// Chunk type to store the payload in. zTXt and iCCP disguise it inside a comment or a
+    /// private tag of the color profile, so the chunk stays valid, and need the payload
+    /// encrypted
     #[clap(value_parser)] //= ChunkType::from_str)]
     pub chunk_type: ChunkType,
 
@@ -32,8 +66,27 @@ pub struct EncodeArgs {
 
     #[clap(value_parser)]
     pub output_file: Option<PathBuf>,
+
+    /// Split the payload over several chunks holding at most this many bytes of it each.
+    /// Payloads too large for a single chunk are always split
+    #[clap(long, value_parser)]
+    pub part_size: Option<usize>,
+
+    #[clap(flatten)]
+    pub seal: SealArgs,
+
+    #[clap(flatten)]
+    pub decoys: DecoyArgs,
+
+    #[clap(flatten)]
+    pub sign: SignArgs,
+
+    /// Fails before writing the result if the decoded pixels would change
+    #[clap(long, value_parser)]
+    pub verify_pixels: bool,
 }
 
+/// Prints a message hidden with `encode`
 #[derive(Parser, Debug)]
 pub struct DecodeArgs {
     #[clap(value_parser)]
@@ -41,6 +94,251 @@ pub struct DecodeArgs {
 
     #[clap(value_parser)] //= ChunkType::from_str)]
     pub chunk_type: ChunkType,
+
+    #[clap(flatten)]
+    pub open: OpenArgs,
+
+    /// How to print the message
+    #[clap(long, value_enum, default_value_t = OutputFormat::Utf8)]
+    pub format: OutputFormat,
+
+    /// Print the content type, timestamp and label of the message to standard error
+    #[clap(long, value_parser)]
+    pub metadata: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    /// UTF-8 text, failing if the message is not valid UTF-8
+    Utf8,
+    /// UTF-8 text, replacing invalid sequences
+    Lossy,
+    /// The bytes as they are
+    Raw,
+    Hex,
+    Base64,
+}
+
+/// Embeds a file, or standard input, into a PNG file
+#[derive(Debug, Parser)]
+pub struct EmbedArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    /// Chunk type to store the payload in. zTXt and iCCP disguise it inside a comment or a
+    /// private tag of the color profile, so the chunk stays valid, and need the payload
+    /// encrypted
+    #[clap(value_parser)]
+    pub chunk_type: ChunkType,
+
+    /// File to embed, or `-` for standard input
+    #[clap(value_parser)]
+    pub file: PathBuf,
+
+    #[clap(value_parser)]
+    pub output_file: Option<PathBuf>,
+
+    /// Name to store instead of the file name
+    #[clap(long, value_parser)]
+    pub name: Option<String>,
+
+    /// MIME type to store instead of guessing it
+    #[clap(long, value_parser)]
+    pub mime: Option<String>,
+
+    /// Split the payload over several chunks holding at most this many bytes of it each.
+    /// Payloads too large for a single chunk are always split
+    #[clap(long, value_parser)]
+    pub part_size: Option<usize>,
+
+    #[clap(flatten)]
+    pub seal: SealArgs,
+
+    #[clap(flatten)]
+    pub sign: SignArgs,
+}
+
+/// Extracts a file embedded with `embed`
+#[derive(Debug, Parser)]
+pub struct ExtractArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    #[clap(value_parser)]
+    pub chunk_type: ChunkType,
+
+    /// Where to write the file, or `-` for standard output. Defaults to the stored file name in
+    /// the current directory
+    #[clap(short, long, value_parser)]
+    pub output: Option<PathBuf>,
+
+    /// Overwrite an existing file
+    #[clap(long, value_parser)]
+    pub force: bool,
+
+    #[clap(flatten)]
+    pub open: OpenArgs,
+}
+
+/// Splits a file into Shamir shares and embeds one in each image, so that any `--threshold` of
+/// the images rebuild it with `combine` and fewer tell nothing about it
+#[derive(Debug, Parser)]
+pub struct SplitArgs {
+    #[clap(value_parser)]
+    pub chunk_type: ChunkType,
+
+    /// File to split, or `-` for standard input
+    #[clap(value_parser)]
+    pub file: PathBuf,
+
+    /// Images to embed the shares in, one share each
+    #[clap(value_parser, required = true)]
+    pub images: Vec<PathBuf>,
+
+    /// Number of shares needed to rebuild the file
+    #[clap(short = 'k', long, value_parser)]
+    pub threshold: usize,
+
+    /// Number of shares, which must match the number of images [default: the number of images]
+    #[clap(short = 'n', long, value_parser)]
+    pub shares: Option<usize>,
+
+    /// Write the images to this directory instead of changing them in place
+    #[clap(long, value_parser)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Name to store instead of the file name
+    #[clap(long, value_parser)]
+    pub name: Option<String>,
+
+    /// MIME type to store instead of guessing it
+    #[clap(long, value_parser)]
+    pub mime: Option<String>,
+
+    #[clap(flatten)]
+    pub seal: SealArgs,
+}
+
+/// Rebuilds a file split with `split` from the images holding enough of its shares
+#[derive(Debug, Parser)]
+pub struct CombineArgs {
+    #[clap(value_parser)]
+    pub chunk_type: ChunkType,
+
+    /// Images holding the shares
+    #[clap(value_parser, required = true)]
+    pub images: Vec<PathBuf>,
+
+    /// Where to write the file, or `-` for standard output. Defaults to the stored file name in
+    /// the current directory
+    #[clap(short, long, value_parser)]
+    pub output: Option<PathBuf>,
+
+    /// Overwrite an existing file
+    #[clap(long, value_parser)]
+    pub force: bool,
+
+    #[clap(flatten)]
+    pub open: OpenArgs,
+}
+
+/// Compression, encryption and labelling of a payload
+#[derive(Debug, Parser)]
+pub struct SealArgs {
+    /// Compress the payload before encrypting it
+    #[clap(long, value_enum)]
+    pub compress: Option<CompressMode>,
+
+    #[clap(flatten)]
+    pub password: PasswordArgs,
+
+    /// Cipher used when encrypting with a password [default: chacha20-poly1305]
+    #[clap(long, value_parser, requires = "password-group")]
+    pub cipher: Option<Cipher>,
+
+    /// Encrypt the message to a public key, or a file containing one. May be repeated
+    #[clap(
+        long,
+        value_parser,
+        multiple_occurrences = true,
+        conflicts_with = "password-group"
+    )]
+    pub recipient: Vec<String>,
+
+    /// Label stored with the payload, in clear text
+    #[clap(long, value_parser)]
+    pub label: Option<String>,
+
+    /// Record when the payload was made, in clear text
+    #[clap(long, value_parser)]
+    pub timestamp: bool,
+}
+
+/// Decoy messages sealed next to the message, each under its own password
+#[derive(Debug, Parser)]
+pub struct DecoyArgs {
+    /// Also hide a decoy message, opened by the --decoy-password in the same position instead
+    /// of the password. May be repeated
+    #[clap(
+        long,
+        value_parser,
+        multiple_occurrences = true,
+        requires_all = &["password-group", "decoy-password"]
+    )]
+    pub decoy: Vec<String>,
+
+    /// Password of a decoy message. May be repeated, once for every --decoy
+    #[clap(long, value_parser, multiple_occurrences = true, requires = "decoy")]
+    pub decoy_password: Vec<String>,
+
+    /// Number of equal slots holding the messages, whatever their number, so the payload does
+    /// not tell how many there are [default: 4]
+    #[clap(long, value_parser, requires = "password-group")]
+    pub slots: Option<usize>,
+}
+
+/// Signing of a payload stored in chunks
+#[derive(Debug, Parser)]
+pub struct SignArgs {
+    /// Sign the message with a secret key file made by `keygen --signing`
+    #[clap(long, value_parser)]
+    pub sign_key: Option<PathBuf>,
+
+    /// Also sign the critical image data, so verification fails if the image is edited
+    #[clap(long, value_parser, requires = "sign-key")]
+    pub sign_image: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CompressMode {
+    Zlib,
+    Zstd,
+    Brotli,
+    /// The smallest of the above, or no compression if none shrinks the payload
+    Auto,
+}
+
+/// Decryption of a payload
+#[derive(Debug, Parser)]
+pub struct OpenArgs {
+    #[clap(flatten)]
+    pub password: PasswordArgs,
+
+    /// Decrypt the message with a secret key file made by `keygen`. May be repeated
+    #[clap(long, value_parser, multiple_occurrences = true)]
+    pub identity: Vec<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+#[clap(group(ArgGroup::new("password-group").args(&["password", "password-file"])))]
+pub struct PasswordArgs {
+    /// Encrypt or decrypt the message with a password
+    #[clap(long, value_parser)]
+    pub password: Option<String>,
+
+    /// Read the password from the first line of a file
+    #[clap(long, value_parser)]
+    pub password_file: Option<PathBuf>,
 }
 
 #[derive(Debug, Parser)]
@@ -57,3 +355,458 @@ pub struct PrintArgs {
     #[clap(value_parser)]
     pub path: PathBuf,
 }
+
+#[derive(Debug, Parser)]
+pub struct XmpArgs {
+    #[clap(subcommand)]
+    pub command: XmpCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum XmpCommands {
+    /// Prints XMP properties
+    Get(XmpGetArgs),
+    /// Sets an XMP property, keeping the rest of the packet intact. Setting an array property
+    /// leaves it with the one item given
+    Set(XmpSetArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct XmpGetArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    /// Property as `prefix:name`, e.g. `dc:format`. Prints all properties if omitted.
+    #[clap(value_parser)]
+    pub property: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct XmpSetArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    /// Property as `prefix:name`, e.g. `dc:format`
+    #[clap(value_parser)]
+    pub property: String,
+
+    #[clap(value_parser)]
+    pub value: String,
+
+    /// Namespace URI of the property. Only needed for prefixes that are neither well known nor
+    /// already declared in the packet.
+    #[clap(long, value_parser)]
+    pub namespace: Option<String>,
+
+    /// Writes to this file instead of modifying `path`
+    #[clap(value_parser)]
+    pub output_file: Option<PathBuf>,
+}
+
+/// Removes identifying metadata. Critical chunks are never removed.
+///
+/// Without `--keep` or `--remove` the text, exif, timestamps, private, unknown and trailing
+/// categories are removed. Rules are chunk types (`tEXt`) or categories: text, exif, color,
+/// timestamps, private, unknown, other, trailing.
+#[derive(Debug, Parser)]
+pub struct StripArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    /// Writes to this file instead of modifying `path`
+    #[clap(value_parser)]
+    pub output_file: Option<PathBuf>,
+
+    /// Allow-list: removes all ancillary data except these
+    #[clap(long, value_parser, value_delimiter = ',', conflicts_with = "remove")]
+    pub keep: Vec<Rule>,
+
+    /// Deny-list: removes only these
+    #[clap(long, value_parser, value_delimiter = ',')]
+    pub remove: Vec<Rule>,
+
+    /// Fails before writing the result if the decoded pixels would change
+    #[clap(long, value_parser)]
+    pub verify_pixels: bool,
+}
+
+/// Merges or re-splits IDAT chunks without recompressing the image data
+#[derive(Debug, Parser)]
+#[clap(group(ArgGroup::new("mode").required(true).args(&["merge", "split-size"])))]
+pub struct IdatArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    /// Writes to this file instead of modifying `path`
+    #[clap(value_parser)]
+    pub output_file: Option<PathBuf>,
+
+    /// Merges consecutive IDAT chunks into one
+    #[clap(long, value_parser)]
+    pub merge: bool,
+
+    /// Re-splits the image data into IDAT chunks of this many bytes
+    #[clap(long, value_parser)]
+    pub split_size: Option<usize>,
+}
+
+/// Losslessly recompresses a PNG file, keeping the pixels bit-identical
+#[derive(Debug, Parser)]
+pub struct OptimizeArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    /// Writes to this file instead of modifying `path`
+    #[clap(value_parser)]
+    pub output_file: Option<PathBuf>,
+
+    /// Deflate levels to try
+    #[clap(long, value_parser = clap::value_parser!(u32).range(0..=9), value_delimiter = ',', default_values = &["6", "9"])]
+    pub level: Vec<u32>,
+
+    /// Keeps the color type, bit depth and palette
+    #[clap(long, value_parser)]
+    pub no_reduce: bool,
+
+    /// Also removes the metadata `strip` removes by default
+    #[clap(long, value_parser)]
+    pub strip: bool,
+
+    /// Compresses blocks of this many rows independently, on all cores when built with the
+    /// `parallel` feature
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub rows_per_block: Option<u64>,
+
+    /// Fails before writing the result if the decoded pixels would change
+    #[clap(long, value_parser)]
+    pub verify_pixels: bool,
+}
+
+/// Prints the SHA-256 of the decoded pixels and dimensions, which stays the same whatever
+/// metadata or compression the file has
+#[derive(Debug, Parser)]
+pub struct HashArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+}
+
+/// Generates an X25519 keypair for encrypting messages to recipients, or an Ed25519 keypair
+/// for signing them
+#[derive(Debug, Parser)]
+pub struct KeygenArgs {
+    /// File to write the secret key to. The public key is written to the same path with a
+    /// `.pub` extension added
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    /// Overwrite existing key files
+    #[clap(long, value_parser)]
+    pub force: bool,
+
+    /// Generate an Ed25519 keypair for signing messages instead
+    #[clap(long, value_parser)]
+    pub signing: bool,
+}
+
+/// Checks the signature of a message, reporting it as valid, invalid or unsigned
+#[derive(Debug, Parser)]
+pub struct VerifyArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    #[clap(value_parser)]
+    pub chunk_type: ChunkType,
+
+    /// Public key the message should be signed with, or a file containing one
+    #[clap(long, value_parser)]
+    pub pubkey: String,
+}
+
+/// Hides a file in the least significant bits of the pixels instead of a chunk
+#[derive(Debug, Parser)]
+pub struct LsbArgs {
+    #[clap(subcommand)]
+    pub command: LsbCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum LsbCommands {
+    /// Embeds a file, or standard input, in the pixels of a PNG file
+    Embed(LsbEmbedArgs),
+    /// Extracts a file embedded with `lsb embed`
+    Extract(LsbExtractArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct LsbEmbedArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    /// File to embed, or `-` for standard input
+    #[clap(value_parser)]
+    pub file: PathBuf,
+
+    #[clap(value_parser)]
+    pub output_file: Option<PathBuf>,
+
+    /// Name to store instead of the file name
+    #[clap(long, value_parser)]
+    pub name: Option<String>,
+
+    /// MIME type to store instead of guessing it
+    #[clap(long, value_parser)]
+    pub mime: Option<String>,
+
+    #[clap(flatten)]
+    pub lsb: LsbOptions,
+
+    /// Convert palette images to RGB or RGBA first
+    #[clap(long, value_parser)]
+    pub convert: bool,
+
+    #[clap(flatten)]
+    pub seal: SealArgs,
+}
+
+#[derive(Debug, Parser)]
+pub struct LsbExtractArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    /// Where to write the file, or `-` for standard output. Defaults to the stored file name in
+    /// the current directory
+    #[clap(short, long, value_parser)]
+    pub output: Option<PathBuf>,
+
+    /// Overwrite an existing file
+    #[clap(long, value_parser)]
+    pub force: bool,
+
+    #[clap(flatten)]
+    pub lsb: LsbOptions,
+
+    #[clap(flatten)]
+    pub open: OpenArgs,
+}
+
+/// Key and channels of LSB embedding; extracting needs the same as embedding
+#[derive(Debug, Parser)]
+#[clap(group(ArgGroup::new("key-group").args(&["key", "key-file"]).required(true)))]
+pub struct LsbOptions {
+    /// Key deciding the order the pixels are used in
+    #[clap(long, value_parser)]
+    pub key: Option<String>,
+
+    /// Read the key from the first line of a file
+    #[clap(long, value_parser)]
+    pub key_file: Option<PathBuf>,
+
+    /// Channels carrying the payload and how many low bits of each, 1 or 2, e.g. `r:2,g,b`.
+    /// Grayscale images have their gray in `r` [default: r,g,b, or r for grayscale images]
+    #[clap(long, value_parser, value_delimiter = ',')]
+    pub channels: Vec<ChannelBits>,
+
+    /// Add Reed–Solomon error correction with this many parity bytes per 255 byte block, 2 to
+    /// 128. Each block survives half as many damaged bytes
+    #[clap(long, value_parser)]
+    pub fec: Option<u8>,
+}
+
+/// Marks the pixels with a short ID that survives re-encoding, stripping metadata, small crops
+/// and palette reduction
+#[derive(Debug, Parser)]
+pub struct WatermarkArgs {
+    #[clap(subcommand)]
+    pub command: WatermarkCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WatermarkCommands {
+    /// Adds a watermark to the pixels of a PNG file
+    Embed(WatermarkEmbedArgs),
+    /// Looks for a watermark added with `watermark embed` and reports how sure it is
+    Detect(WatermarkDetectArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct WatermarkEmbedArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    #[clap(value_parser)]
+    pub output_file: Option<PathBuf>,
+
+    /// ID to mark the image with, from 0 to 4294967295
+    #[clap(long, value_parser)]
+    pub id: u32,
+
+    /// How many 8-bit levels each sample is changed by. Stronger marks survive more but show
+    /// more; small or noisy images need them to be found at all
+    #[clap(
+        long,
+        value_parser = clap::value_parser!(u8).range(1..=32),
+        default_value_t = watermark::DEFAULT_STRENGTH
+    )]
+    pub strength: u8,
+
+    #[clap(flatten)]
+    pub key: WatermarkKey,
+
+    /// Convert palette images to RGB or RGBA first
+    #[clap(long, value_parser)]
+    pub convert: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct WatermarkDetectArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    #[clap(flatten)]
+    pub key: WatermarkKey,
+}
+
+/// Key of a watermark; detecting needs the same as embedding
+#[derive(Debug, Parser)]
+#[clap(group(ArgGroup::new("key-group").args(&["key", "key-file"]).required(true)))]
+pub struct WatermarkKey {
+    /// Key deciding the pattern of the watermark
+    #[clap(long, value_parser)]
+    pub key: Option<String>,
+
+    /// Read the key from the first line of a file
+    #[clap(long, value_parser)]
+    pub key_file: Option<PathBuf>,
+}
+
+/// Hides a file in a PNG file without a key or changing how the pixels look
+#[derive(Debug, Parser)]
+pub struct CarrierArgs {
+    #[clap(subcommand)]
+    pub command: CarrierCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CarrierCommands {
+    /// Embeds a file, or standard input, in a PNG file
+    Embed(CarrierEmbedArgs),
+    /// Extracts a file embedded with `embed`
+    Extract(CarrierExtractArgs),
+}
+
+/// Hides a file as zero-width characters in the text of an iTXt entry, which reads the same
+#[derive(Debug, Parser)]
+pub struct TextArgs {
+    #[clap(subcommand)]
+    pub command: CarrierCommands,
+
+    /// Keyword of the entry, created if the image has none
+    #[clap(long, value_parser, global = true, default_value = zero_width::DEFAULT_KEYWORD)]
+    pub keyword: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct CarrierEmbedArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    /// File to embed, or `-` for standard input
+    #[clap(value_parser)]
+    pub file: PathBuf,
+
+    #[clap(value_parser)]
+    pub output_file: Option<PathBuf>,
+
+    /// Name to store instead of the file name
+    #[clap(long, value_parser)]
+    pub name: Option<String>,
+
+    /// MIME type to store instead of guessing it
+    #[clap(long, value_parser)]
+    pub mime: Option<String>,
+
+    /// Decode the result and check it looks the same as the original over any background before
+    /// writing it
+    #[clap(long, value_parser)]
+    pub check_invisible: bool,
+
+    #[clap(flatten)]
+    pub seal: SealArgs,
+}
+
+#[derive(Debug, Parser)]
+pub struct CarrierExtractArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    /// Where to write the file, or `-` for standard output. Defaults to the stored file name in
+    /// the current directory
+    #[clap(short, long, value_parser)]
+    pub output: Option<PathBuf>,
+
+    /// Overwrite an existing file
+    #[clap(long, value_parser)]
+    pub force: bool,
+
+    #[clap(flatten)]
+    pub open: OpenArgs,
+}
+
+/// Reports the largest file or message each embedding method can hold in a PNG file
+#[derive(Debug, Parser)]
+pub struct CapacityArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    /// Account for compression. Only its header is counted, the savings depend on the content
+    #[clap(long, value_parser)]
+    pub compress: bool,
+
+    /// Account for passphrase encryption
+    #[clap(long, value_parser, conflicts_with = "recipients")]
+    pub encrypt: bool,
+
+    /// Account for encryption to this many recipients, 1 to 255
+    #[clap(long, value_parser = clap::value_parser!(u8).range(1..))]
+    pub recipients: Option<u8>,
+
+    /// Account for a signature, which only chunks can carry
+    #[clap(long, value_parser)]
+    pub sign: bool,
+
+    /// Account for a signature covering the image data
+    #[clap(long, value_parser, requires = "sign")]
+    pub sign_image: bool,
+
+    /// Account for this label
+    #[clap(long, value_parser)]
+    pub label: Option<String>,
+
+    /// Account for embedding a file under this name
+    #[clap(long, value_parser)]
+    pub name: Option<String>,
+
+    /// Account for this MIME type instead of the one guessed from the name
+    #[clap(long, value_parser)]
+    pub mime: Option<String>,
+
+    /// Account for error correction with this many parity bytes per block in the pixels
+    #[clap(long, value_parser)]
+    pub fec: Option<u8>,
+}
+
+/// Looks for signs of hidden data and scores each heuristic from 0 to 1
+#[derive(Debug, Parser)]
+pub struct DetectArgs {
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    #[clap(long, value_enum, default_value_t = ReportFormat::Text)]
+    pub format: ReportFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
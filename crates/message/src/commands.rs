@@ -1,9 +1,31 @@
 use crate::args::*;
-use anyhow::{bail, Context};
+use anyhow::{anyhow, bail, Context};
+use base64::prelude::*;
+use carrier::detect::{self, Finding};
+use carrier::disguise::Disguise;
+use carrier::fec::ReedSolomon;
+use carrier::lsb::{Channel, ChannelBits, Lsb};
+use carrier::watermark::Watermark;
+use carrier::{filters, palette, transparent, zero_width, zlib, CarrierError};
+use payload::compress::{self, Compression};
+use payload::decoy;
+use payload::envelope::{self, ContentType, Envelope, Flags};
+use payload::file::{self, EmbeddedFile};
+use payload::multipart::{self, Part};
+use payload::passphrase::{self, KdfParams};
+use payload::recipient::{self, Identity, PublicKey};
+use payload::share::{self, Share};
+use payload::signature::{self, Signed, SigningKey, VerifyingKey};
 use png_spec::chunk::Chunk;
+use png_spec::chunk_type::ChunkType;
+use png_spec::image::{ColorType, EncodeOptions, Image};
+use png_spec::optimize::OptimizeOptions;
 use png_spec::png::Png;
+use png_spec::strip::Strip;
 use std::io::{stdout, BufWriter, Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{fs::File, io::BufReader};
 
 fn read_png(path: impl AsRef<Path>) -> anyhow::Result<Png> {
@@ -17,6 +39,466 @@ fn read_png(path: impl AsRef<Path>) -> anyhow::Result<Png> {
     Ok(Png::try_from(&*buffer)?)
 }
 
+/// Writes a PNG file to `path`, replacing it if it exists
+fn write_png(png: &Png, path: impl AsRef<Path>) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    File::create(path)
+        .with_context(|| format!("cannot create file {}", path.display()))?
+        .write_all(&png.as_bytes())?;
+    Ok(())
+}
+
+/// The digest of the pixels before an edit, if `--verify-pixels` asks for it to be checked
+fn pixels_before(png: &Png, verify: bool) -> anyhow::Result<Option<[u8; 32]>> {
+    verify
+        .then(|| png.pixel_digest())
+        .transpose()
+        .map_err(Into::into)
+}
+
+/// Fails if the pixels no longer match the digest taken by [`pixels_before`]
+fn verify_pixels(png: &Png, before: Option<[u8; 32]>) -> anyhow::Result<()> {
+    if let Some(before) = before {
+        if png.pixel_digest()? != before {
+            bail!("the decoded pixels changed, the result was not written");
+        }
+    }
+    Ok(())
+}
+
+/// Reads the password given on the command line or from a file, if any
+fn read_password(args: &PasswordArgs) -> anyhow::Result<Option<String>> {
+    if let Some(path) = &args.password_file {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read password file {}", path.display()))?;
+        let password = contents.lines().next().unwrap_or_default();
+        if password.is_empty() {
+            bail!("password file {} is empty", path.display());
+        }
+        Ok(Some(password.to_string()))
+    } else {
+        Ok(args.password.clone())
+    }
+}
+
+/// Parses a public key given either as a string or as a file containing one
+fn read_public_key<K>(key: &str) -> anyhow::Result<K>
+where
+    K: FromStr<Err = payload::PayloadError>,
+{
+    match key.parse() {
+        Ok(key) => Ok(key),
+        Err(_) if Path::new(key).is_file() => std::fs::read_to_string(key)?
+            .parse()
+            .with_context(|| format!("cannot read public key file {key}")),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Reads a secret key file made by `keygen`
+fn read_secret_key<K>(
+    path: &Path,
+    parse: impl Fn(&str) -> Result<K, payload::PayloadError>,
+) -> anyhow::Result<K> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("cannot read key file {}", path.display()))?;
+    parse(&contents).with_context(|| format!("invalid key file {}", path.display()))
+}
+
+/// Compresses `content` as requested
+fn compress_content(content: &[u8], mode: Option<CompressMode>) -> anyhow::Result<Vec<u8>> {
+    Ok(match mode {
+        None => content.to_vec(),
+        Some(CompressMode::Zlib) => compress::compress(content, Compression::Zlib)?,
+        Some(CompressMode::Zstd) => compress::compress(content, Compression::Zstd)?,
+        Some(CompressMode::Brotli) => compress::compress(content, Compression::Brotli)?,
+        Some(CompressMode::Auto) => compress::compress_auto(content)?,
+    })
+}
+
+/// Compresses and encrypts `content` as requested, together with any decoy messages, and wraps
+/// the result in an envelope
+fn seal_payload(
+    content: Vec<u8>,
+    content_type: ContentType,
+    args: &SealArgs,
+    decoys: Option<&DecoyArgs>,
+) -> anyhow::Result<Envelope> {
+    let (decoys, decoy_passwords, slots) = match decoys {
+        Some(d) => (d.decoy.as_slice(), d.decoy_password.as_slice(), d.slots),
+        None => (&[][..], &[][..], None),
+    };
+    if decoys.len() != decoy_passwords.len() {
+        bail!("every --decoy needs its own --decoy-password");
+    }
+
+    let mut flags = Flags::NONE;
+    let mut data = compress_content(&content, args.compress)?;
+    let mut decoy_data = decoys
+        .iter()
+        .map(|d| compress_content(d.as_bytes(), args.compress))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    // auto leaves payloads that do not shrink alone, and the content itself may happen to start
+    // like a compressed payload. The flags cover the decoys too, so they are all compressed or
+    // none is
+    if args.compress.is_some()
+        && compress::is_compressed(&data)
+        && decoy_data.iter().all(|d| compress::is_compressed(d))
+    {
+        flags.insert(Flags::COMPRESSED);
+    } else {
+        data = content;
+        decoy_data = decoys.iter().map(|d| d.as_bytes().to_vec()).collect();
+    }
+
+    let password = read_password(&args.password)?;
+    if password.is_some() || !args.recipient.is_empty() {
+        flags.insert(Flags::ENCRYPTED);
+    }
+    // the header is settled before encrypting, which authenticates it
+    let mut envelope = Envelope::new(content_type, vec![]);
+    envelope.set_flags(flags);
+    envelope.set_label(args.label.clone())?;
+    if args.timestamp {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        envelope.set_timestamp(now.as_secs());
+    }
+    let associated_data = envelope.associated_data();
+
+    if let Some(password) = password {
+        let cipher = args.cipher.unwrap_or_default();
+        data = if decoys.is_empty() && slots.is_none() {
+            passphrase::seal(
+                &data,
+                &associated_data,
+                password.as_bytes(),
+                cipher,
+                KdfParams::default(),
+            )?
+        } else {
+            let messages: Vec<(&[u8], &[u8])> = std::iter::once((&data, &password))
+                .chain(decoy_data.iter().zip(decoy_passwords))
+                .map(|(d, p)| (d.as_slice(), p.as_bytes()))
+                .collect();
+            let slots = slots.unwrap_or(decoy::DEFAULT_SLOTS);
+            decoy::seal(
+                &messages,
+                &associated_data,
+                slots,
+                cipher,
+                KdfParams::default(),
+            )?
+        };
+    }
+
+    if !args.recipient.is_empty() {
+        let recipients = args
+            .recipient
+            .iter()
+            .map(|r| read_public_key::<PublicKey>(r))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        data = recipient::encrypt(&data, &associated_data, &recipients)?;
+    }
+
+    envelope.set_body(data);
+    Ok(envelope)
+}
+
+/// Signs the body of `envelope` if requested, for storing in a `chunk_type` chunk of `png`
+fn sign_payload(
+    envelope: &mut Envelope,
+    chunk_type: &ChunkType,
+    png: &Png,
+    args: &SignArgs,
+) -> anyhow::Result<()> {
+    if let Some(path) = &args.sign_key {
+        let key = read_secret_key(path, SigningKey::from_file_contents)?;
+        let digest = args.sign_image.then(|| png.critical_digest());
+        let signed = signature::sign(
+            envelope.body(),
+            &envelope.associated_data(),
+            &chunk_type.bytes(),
+            digest.as_ref(),
+            &key,
+        );
+        envelope.set_body(signed);
+        let mut flags = envelope.flags();
+        flags.insert(Flags::SIGNED);
+        envelope.set_flags(flags);
+    }
+    Ok(())
+}
+
+/// Largest body stored in one chunk, leaving room for the envelope and part headers
+const MAX_PART_SIZE: usize = Png::MAX_CHUNK_DATA_LENGTH - u16::MAX as usize - 64;
+
+/// Stores an envelope in `chunk_type` chunks before IEND. The body is split over several
+/// envelopes if asked to or if it does not fit in one chunk. Standard chunks that can carry a
+/// payload get it disguised inside valid contents, in a single chunk. A disguise has no key of
+/// its own, so those payloads must be encrypted to not show up as a plain envelope
+fn store_payload(
+    png: &mut Png,
+    chunk_type: ChunkType,
+    mut envelope: Envelope,
+    part_size: Option<usize>,
+) -> anyhow::Result<()> {
+    if let Some(disguise) = Disguise::for_chunk_type(&chunk_type) {
+        if part_size.is_some() || envelope.body().len() > MAX_PART_SIZE {
+            bail!("a payload disguised in {chunk_type} cannot be split over several chunks");
+        }
+        if !envelope.flags().contains(Flags::ENCRYPTED) {
+            bail!("a payload disguised in {chunk_type} needs --password or --recipient");
+        }
+        disguise.embed(png, &envelope.to_bytes())?;
+        return Ok(());
+    }
+
+    let part_size = match part_size {
+        Some(size) if size > MAX_PART_SIZE => {
+            bail!("part size {size} is larger than the maximum of {MAX_PART_SIZE}")
+        }
+        None if envelope.body().len() > MAX_PART_SIZE => Some(MAX_PART_SIZE),
+        size => size,
+    };
+
+    match part_size {
+        Some(size) => {
+            let parts = multipart::split(envelope.body(), size)?;
+            envelope.set_flags(envelope.flags() | Flags::MULTIPART);
+            for part in parts {
+                envelope.set_body(part);
+                png.insert_before_end(Chunk::new(chunk_type, envelope.to_bytes()));
+            }
+        }
+        None => png.insert_before_end(Chunk::new(chunk_type, envelope.to_bytes())),
+    }
+    Ok(())
+}
+
+/// A payload found in `chunk_type` chunks
+enum Found {
+    Envelope(Envelope),
+    /// Written before envelopes existed: a bare message or bare layers
+    Legacy(Vec<u8>),
+}
+
+impl Found {
+    fn data(&self) -> &[u8] {
+        match self {
+            Found::Envelope(envelope) => envelope.body(),
+            Found::Legacy(data) => data,
+        }
+    }
+
+    /// What the encryption and signature layers authenticate besides their own contents, nothing
+    /// for legacy payloads
+    fn associated_data(&self) -> Vec<u8> {
+        match self {
+            Found::Envelope(envelope) => envelope.associated_data(),
+            Found::Legacy(_) => vec![],
+        }
+    }
+
+    /// Whether the layer `flag` wraps `data`, as the envelope says or, for legacy payloads, as
+    /// its magic bytes say
+    fn has_layer(
+        &self,
+        flag: Flags,
+        data: &[u8],
+        is_layer: fn(&[u8]) -> bool,
+    ) -> anyhow::Result<bool> {
+        match self {
+            Found::Legacy(_) => Ok(is_layer(data)),
+            Found::Envelope(envelope) if !envelope.flags().contains(flag) => Ok(false),
+            Found::Envelope(_) if is_layer(data) => Ok(true),
+            Found::Envelope(_) => bail!("the envelope says the payload is {flag} but it is not"),
+        }
+    }
+}
+
+/// Finds the payload stored in `chunk_type` chunks, joining it back together if it was split.
+/// Disguised payloads are looked for in every chunk of the type, skipping those without one
+fn find_payload(png: &Png, chunk_type: &ChunkType) -> anyhow::Result<Option<Found>> {
+    // older versions appended the message after IEND
+    let trailing = png.trailing_chunks();
+    let chunks = png
+        .chunks()
+        .iter()
+        .chain(&trailing)
+        .filter(|c| c.chunk_type() == chunk_type);
+    let payloads: Vec<Vec<u8>> = match Disguise::for_chunk_type(chunk_type) {
+        Some(disguise) => chunks.filter_map(|c| disguise.reveal(c)).collect(),
+        None => chunks.map(|c| c.data().to_vec()).collect(),
+    };
+    let Some(first) = payloads.first() else {
+        return Ok(None);
+    };
+
+    if !envelope::is_envelope(first) {
+        // before envelopes, parts were stored bare
+        if !multipart::is_part(first) {
+            return Ok(Some(Found::Legacy(first.to_vec())));
+        }
+        let parts = payloads
+            .iter()
+            .filter(|p| multipart::is_part(p))
+            .map(|p| Part::parse(p))
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok(Some(Found::Legacy(multipart::join(&parts)?)));
+    }
+
+    let mut envelope = Envelope::try_from(first.as_slice())?;
+    if envelope.flags().contains(Flags::MULTIPART) {
+        let envelopes = payloads
+            .iter()
+            .filter(|p| envelope::is_envelope(p))
+            .map(|p| Envelope::try_from(p.as_slice()))
+            .filter(|e| {
+                e.as_ref()
+                    .map_or(true, |e| e.flags().contains(Flags::MULTIPART))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let parts = envelopes
+            .iter()
+            .map(|e| Part::parse(e.body()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let body = multipart::join(&parts)?;
+        envelope.set_body(body);
+        let mut flags = envelope.flags();
+        flags.remove(Flags::MULTIPART);
+        envelope.set_flags(flags);
+    }
+    Ok(Some(Found::Envelope(envelope)))
+}
+
+/// Finds the payload stored in `chunk_type` chunks and opens it with [`open_found`]
+fn open_payload(
+    png: &Png,
+    chunk_type: &ChunkType,
+    args: &OpenArgs,
+) -> anyhow::Result<Option<Envelope>> {
+    find_payload(png, chunk_type)?
+        .map(|found| open_found(found, args))
+        .transpose()
+}
+
+/// Removes the signature, encryption and compression of a payload. The returned envelope holds
+/// the content, and is made up for legacy payloads
+fn open_found(found: Found, args: &OpenArgs) -> anyhow::Result<Envelope> {
+    if matches!(&found, Found::Envelope(e) if e.flags().contains(Flags::SHARED)) {
+        bail!("the payload is one share of a split file, rebuild it with combine");
+    }
+    let associated_data = found.associated_data();
+    let mut data = found.data();
+    if found.has_layer(Flags::SIGNED, data, signature::is_signed)? {
+        data = Signed::parse(data)?.payload();
+    }
+
+    let is_encrypted =
+        |d: &[u8]| passphrase::is_sealed(d) || decoy::is_decoy(d) || recipient::is_encrypted(d);
+    let data = if !found.has_layer(Flags::ENCRYPTED, data, is_encrypted)? {
+        data.to_vec()
+    } else if passphrase::is_sealed(data) || decoy::is_decoy(data) {
+        let Some(password) = read_password(&args.password)? else {
+            bail!("message is encrypted, use --password or --password-file");
+        };
+        if decoy::is_decoy(data) {
+            decoy::open(data, &associated_data, password.as_bytes())?
+        } else {
+            passphrase::open(data, &associated_data, password.as_bytes())?
+        }
+    } else {
+        if args.identity.is_empty() {
+            bail!("message is encrypted to recipients, use --identity");
+        }
+        let identities = args
+            .identity
+            .iter()
+            .map(|p| read_secret_key(p, Identity::from_file_contents))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        recipient::decrypt(data, &associated_data, &identities)?
+    };
+
+    let content = if found.has_layer(Flags::COMPRESSED, &data, compress::is_compressed)? {
+        compress::decompress(&data)?
+    } else {
+        data
+    };
+
+    let mut envelope = match found {
+        Found::Envelope(envelope) => envelope,
+        Found::Legacy(_) if file::is_file(&content) => Envelope::new(ContentType::File, vec![]),
+        Found::Legacy(_) => Envelope::new(ContentType::Binary, vec![]),
+    };
+    envelope.set_body(content);
+    envelope.set_flags(Flags::NONE);
+    Ok(envelope)
+}
+
+/// Reads `path`, or standard input if it is `-`, into a file to embed. The name defaults to the
+/// file name and the MIME type is guessed unless given
+fn read_embedded_file(
+    path: &Path,
+    name: Option<String>,
+    mime: Option<String>,
+) -> anyhow::Result<EmbeddedFile> {
+    let stdin = path.as_os_str() == "-";
+    let contents = if stdin {
+        let mut contents = vec![];
+        std::io::stdin().lock().read_to_end(&mut contents)?;
+        contents
+    } else {
+        std::fs::read(path).with_context(|| format!("cannot read file {}", path.display()))?
+    };
+
+    let name = name.unwrap_or_else(|| match path.file_name() {
+        Some(name) if !stdin => name.to_string_lossy().into_owned(),
+        _ => String::new(),
+    });
+    let mime = mime.unwrap_or_else(|| file::guess_mime(&name, &contents).to_string());
+    Ok(EmbeddedFile::new(name, mime, contents)?)
+}
+
+/// Writes an extracted file to `output`, standard output if it is `-`, or by default to its
+/// stored name in the current directory
+fn write_embedded_file(
+    embedded: &EmbeddedFile,
+    output: Option<PathBuf>,
+    force: bool,
+) -> anyhow::Result<()> {
+    let output = match output {
+        Some(output) => output,
+        // only the final component, so a stored name cannot point outside the directory
+        None => match Path::new(embedded.name()).file_name() {
+            Some(name) => PathBuf::from(name),
+            None => bail!("the embedded file has no name, use --output"),
+        },
+    };
+
+    if output.as_os_str() == "-" {
+        let mut stdout = stdout().lock();
+        stdout.write_all(embedded.contents())?;
+        stdout.flush()?;
+        return Ok(());
+    }
+
+    if output.exists() && !force {
+        bail!(
+            "{} already exists, use --force to overwrite",
+            output.display()
+        );
+    }
+    std::fs::write(&output, embedded.contents())
+        .with_context(|| format!("cannot create file {}", output.display()))?;
+    println!(
+        "{} ({} bytes, {})",
+        output.display(),
+        embedded.contents().len(),
+        embedded.mime()
+    );
+    Ok(())
+}
+
 /// Encodes a message into a PNG file and saves the result
 pub fn encode(args: EncodeArgs) -> anyhow::Result<()> {
     // If creating output file fails then return early
@@ -27,8 +509,16 @@ pub fn encode(args: EncodeArgs) -> anyhow::Result<()> {
     };
 
     let mut png = read_png(args.path)?;
-    let chunk = Chunk::new(args.chunk_type, args.message.as_bytes().to_vec());
-    png.append_chunk(chunk);
+    let before = pixels_before(&png, args.verify_pixels)?;
+    let mut envelope = seal_payload(
+        args.message.into_bytes(),
+        ContentType::Text,
+        &args.seal,
+        Some(&args.decoys),
+    )?;
+    sign_payload(&mut envelope, &args.chunk_type, &png, &args.sign)?;
+    store_payload(&mut png, args.chunk_type, envelope, args.part_size)?;
+    verify_pixels(&png, before)?;
 
     if let Some(mut output) = output {
         output.write_all(&png.as_bytes())?;
@@ -44,21 +534,374 @@ pub fn encode(args: EncodeArgs) -> anyhow::Result<()> {
 /// Searches for a message hidden in a PNG file and prints the message if one is found
 pub fn decode(args: DecodeArgs) -> anyhow::Result<()> {
     let png = read_png(&args.path)?;
-    if let Some(chunk) = png.chunk_by_type(&args.chunk_type.to_string()) {
-        if let Ok(s) = chunk.data_as_string() {
-            println!("{s}",);
-        } else {
-            bail!("message not valid UTF-8")
+    if let Some(envelope) = open_payload(&png, &args.chunk_type, &args.open)? {
+        if args.metadata {
+            eprintln!("content type: {}", envelope.content_type());
+            if envelope.timestamp() != 0 {
+                eprintln!("timestamp: {}", envelope.timestamp());
+            }
+            if let Some(label) = envelope.label() {
+                eprintln!("label: {label}");
+            }
+        }
+
+        let data = match envelope.content_type() {
+            ContentType::File => EmbeddedFile::try_from(envelope.body())?.into_contents(),
+            _ => envelope.into_body(),
+        };
+        match args.format {
+            OutputFormat::Utf8 => match String::from_utf8(data) {
+                Ok(s) => println!("{s}"),
+                Err(_) => bail!("message not valid UTF-8, try --format lossy, hex or base64"),
+            },
+            OutputFormat::Lossy => println!("{}", String::from_utf8_lossy(&data)),
+            OutputFormat::Raw => {
+                let mut stdout = stdout().lock();
+                stdout.write_all(&data)?;
+                stdout.flush()?;
+            }
+            OutputFormat::Hex => println!("{}", hex::encode(data)),
+            OutputFormat::Base64 => println!("{}", BASE64_STANDARD.encode(data)),
         }
-        // TODO: use arg option to use lossy, utf8, byte, hex
-        // else {
-        //     let s = String::from_utf8_lossy(chunk.data());
-        //     println!("{s}");
-        // }
     }
     Ok(())
 }
 
+/// Embeds a file with its name, size and MIME type into a PNG file
+pub fn embed(args: EmbedArgs) -> anyhow::Result<()> {
+    let embedded = read_embedded_file(&args.file, args.name, args.mime)?;
+
+    let mut png = read_png(&args.path)?;
+    let mut envelope = seal_payload(embedded.to_bytes(), ContentType::File, &args.seal, None)?;
+    sign_payload(&mut envelope, &args.chunk_type, &png, &args.sign)?;
+    store_payload(&mut png, args.chunk_type, envelope, args.part_size)?;
+
+    write_png(&png, args.output_file.unwrap_or(args.path))
+}
+
+/// Extracts a file embedded with `embed`, byte for byte
+pub fn extract(args: ExtractArgs) -> anyhow::Result<()> {
+    let png = read_png(&args.path)?;
+    let Some(envelope) = open_payload(&png, &args.chunk_type, &args.open)? else {
+        bail!("no {} chunk found", args.chunk_type);
+    };
+    if envelope.content_type() != ContentType::File {
+        bail!(
+            "the {} chunk does not hold an embedded file",
+            args.chunk_type
+        );
+    }
+    let embedded = EmbeddedFile::try_from(envelope.body())?;
+    write_embedded_file(&embedded, args.output, args.force)
+}
+
+/// Splits a file into Shamir shares, each embedded in its own image
+pub fn split(args: SplitArgs) -> anyhow::Result<()> {
+    let count = args.shares.unwrap_or(args.images.len());
+    if count != args.images.len() {
+        bail!(
+            "{count} shares need as many images, got {}",
+            args.images.len()
+        );
+    }
+    let outputs: Vec<PathBuf> = args
+        .images
+        .iter()
+        .map(|path| match (&args.output_dir, path.file_name()) {
+            (Some(dir), Some(name)) => dir.join(name),
+            _ => path.clone(),
+        })
+        .collect();
+    for (i, output) in outputs.iter().enumerate() {
+        if outputs[..i].contains(output) {
+            bail!("two shares would be written to {}", output.display());
+        }
+    }
+    // every image is read and given its share before any is written, so a bad one leaves the
+    // others alone
+    let mut pngs = args
+        .images
+        .iter()
+        .map(read_png)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let embedded = read_embedded_file(&args.file, args.name, args.mime)?;
+    let sealed = seal_payload(embedded.to_bytes(), ContentType::File, &args.seal, None)?;
+    let shares = share::split(sealed.body(), args.threshold, count)?;
+    for (png, share) in pngs.iter_mut().zip(shares) {
+        let mut envelope = sealed.clone();
+        envelope.set_body(share);
+        envelope.set_flags(envelope.flags() | Flags::SHARED);
+        store_payload(png, args.chunk_type, envelope, None)?;
+    }
+
+    for (index, (png, output)) in pngs.iter().zip(&outputs).enumerate() {
+        write_png(png, output)?;
+        println!("{}: share {} of {count}", output.display(), index + 1);
+    }
+    println!("any {} of them rebuild the file", args.threshold);
+    Ok(())
+}
+
+/// Rebuilds a file from the shares embedded in the images. Images that cannot be read, or hold
+/// no share or a corrupted one, are skipped as long as enough shares remain
+pub fn combine(args: CombineArgs) -> anyhow::Result<()> {
+    let mut envelopes = Vec::new();
+    for path in &args.images {
+        let found = read_png(path).and_then(|png| find_payload(&png, &args.chunk_type));
+        match found {
+            Err(e) => eprintln!("skipping {}: {e}", path.display()),
+            Ok(Some(Found::Envelope(envelope))) if envelope.flags().contains(Flags::SHARED) => {
+                match Share::parse(envelope.body()) {
+                    Ok(_) => envelopes.push(envelope),
+                    Err(e) => eprintln!("skipping {}: {e}", path.display()),
+                }
+            }
+            _ => eprintln!(
+                "skipping {}: no share in a {} chunk",
+                path.display(),
+                args.chunk_type
+            ),
+        }
+    }
+
+    let shares = envelopes
+        .iter()
+        .map(|e| Share::parse(e.body()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let payload = share::combine(&shares)?;
+    let mut envelope = envelopes.swap_remove(0);
+    envelope.set_body(payload);
+    let mut flags = envelope.flags();
+    flags.remove(Flags::SHARED);
+    envelope.set_flags(flags);
+
+    let envelope = open_found(Found::Envelope(envelope), &args.open)?;
+    if envelope.content_type() != ContentType::File {
+        bail!("the shares do not hold an embedded file");
+    }
+    let embedded = EmbeddedFile::try_from(envelope.body())?;
+    write_embedded_file(&embedded, args.output, args.force)
+}
+
+/// Hides files in, or recovers them from, the low bits of the pixels
+pub fn lsb(args: LsbArgs) -> anyhow::Result<()> {
+    match args.command {
+        LsbCommands::Embed(args) => lsb_embed(args),
+        LsbCommands::Extract(args) => lsb_extract(args),
+    }
+}
+
+/// Reads the key and channels shared by `lsb embed` and `lsb extract`. The channels default to
+/// those of `image`
+fn read_lsb(args: &LsbOptions, image: &Image) -> anyhow::Result<Lsb> {
+    let key = read_key(args.key.as_deref(), args.key_file.as_deref())?;
+    let channels = match args.channels.as_slice() {
+        [] => Lsb::default_channels(image.header().color_type()),
+        channels => channels.to_vec(),
+    };
+    let mut lsb = Lsb::new(key.as_bytes(), channels)?;
+    lsb.set_fec(args.fec.map(ReedSolomon::new).transpose()?);
+    Ok(lsb)
+}
+
+/// Takes the key given with `--key`, or the first line of the file given with `--key-file`
+fn read_key(key: Option<&str>, key_file: Option<&Path>) -> anyhow::Result<String> {
+    match (key, key_file) {
+        (Some(key), _) => Ok(key.to_string()),
+        (None, Some(path)) => {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("cannot read key file {}", path.display()))?;
+            match contents.lines().next() {
+                Some(line) if !line.is_empty() => Ok(line.to_string()),
+                _ => bail!("key file {} is empty", path.display()),
+            }
+        }
+        (None, None) => bail!("no key given, use --key or --key-file"),
+    }
+}
+
+fn lsb_embed(args: LsbEmbedArgs) -> anyhow::Result<()> {
+    let embedded = read_embedded_file(&args.file, args.name, args.mime)?;
+    let envelope = seal_payload(embedded.to_bytes(), ContentType::File, &args.seal, None)?;
+
+    let mut png = read_png(&args.path)?;
+    let mut image = Image::from_png(&png)?;
+    if image.header().color_type() == ColorType::Indexed {
+        if !args.convert {
+            bail!("cannot embed in the pixels of a palette image, use --convert to make it RGB");
+        }
+        image = image.expand_palette();
+    }
+    let lsb = read_lsb(&args.lsb, &image)?;
+    let payload = envelope.to_bytes();
+    let available = lsb.capacity(&image)?;
+    if payload.len() > available {
+        bail!(
+            "the payload needs {} bytes but the pixels hold {available} with channels {}, \
+             use more channels or bits, --compress or a larger image",
+            payload.len(),
+            channel_list(lsb.channels())
+        );
+    }
+    lsb.embed(&mut image, &payload)?;
+    image.write_to(&mut png, &EncodeOptions::default())?;
+
+    write_png(&png, args.output_file.unwrap_or(args.path))
+}
+
+fn lsb_extract(args: LsbExtractArgs) -> anyhow::Result<()> {
+    let png = read_png(&args.path)?;
+    let image = Image::from_png(&png)?;
+    let lsb = read_lsb(&args.lsb, &image)?;
+
+    let (data, corrected) = lsb.extract_corrected(&image)?;
+    if let Some(fec) = lsb.fec() {
+        eprintln!(
+            "error correction repaired {corrected} bytes, up to {} per block",
+            fec.correctable()
+        );
+    }
+    if !envelope::is_envelope(&data) {
+        bail!("no payload found in the pixels, check the key, channels and --fec");
+    }
+    let envelope = Envelope::try_from(data.as_slice())?;
+    if envelope.flags().contains(Flags::MULTIPART) {
+        bail!("payloads split over several parts cannot be embedded in pixels");
+    }
+    let envelope = open_found(Found::Envelope(envelope), &args.open)?;
+    if envelope.content_type() != ContentType::File {
+        bail!("the pixels do not hold an embedded file");
+    }
+    let embedded = EmbeddedFile::try_from(envelope.body())?;
+    write_embedded_file(&embedded, args.output, args.force)
+}
+
+/// Carriers that need no key and leave the pixels looking the same
+#[derive(Debug, Clone)]
+pub enum Carrier {
+    Palette,
+    Filters,
+    Zlib,
+    Transparent,
+    /// Zero-width characters in the iTXt entry with this keyword
+    Text(String),
+}
+
+impl Carrier {
+    fn name(&self) -> &'static str {
+        match self {
+            Carrier::Palette => "the palette order",
+            Carrier::Filters => "the scanline filters",
+            Carrier::Zlib => "the image data stream",
+            Carrier::Transparent => "the transparent pixels",
+            Carrier::Text(_) => "the text entry",
+        }
+    }
+
+    /// Largest payload in bytes, `None` if there is no limit
+    fn capacity(&self, png: &Png) -> anyhow::Result<Option<usize>> {
+        Ok(match self {
+            Carrier::Palette => Some(palette::capacity(&Image::from_png(png)?)?),
+            Carrier::Filters => Some(filters::capacity(&Image::from_png(png)?)?),
+            Carrier::Zlib | Carrier::Text(_) => None,
+            Carrier::Transparent => Some(transparent::capacity(&Image::from_png(png)?)?),
+        })
+    }
+
+    fn embed(&self, png: &mut Png, payload: &[u8]) -> Result<(), CarrierError> {
+        match self {
+            Carrier::Palette => palette::embed(png, payload),
+            Carrier::Filters => filters::embed(png, payload),
+            Carrier::Zlib => zlib::embed(png, payload),
+            Carrier::Transparent => {
+                let mut image = Image::from_png(png)?;
+                transparent::embed(&mut image, payload)?;
+                Ok(image.write_to(png, &EncodeOptions::default())?)
+            }
+            Carrier::Text(keyword) => zero_width::embed(png, keyword, payload),
+        }
+    }
+
+    fn extract(&self, png: &Png) -> Result<Vec<u8>, CarrierError> {
+        match self {
+            Carrier::Palette => palette::extract(&Image::from_png(png)?),
+            Carrier::Filters => filters::extract(png),
+            Carrier::Zlib => zlib::extract(png),
+            Carrier::Transparent => transparent::extract(&Image::from_png(png)?),
+            Carrier::Text(keyword) => zero_width::extract(png, keyword),
+        }
+    }
+
+    /// What makes room for a larger payload
+    fn larger(&self) -> &'static str {
+        match self {
+            Carrier::Palette => "an image with more colors",
+            Carrier::Filters => "a taller image",
+            Carrier::Zlib | Carrier::Text(_) => unreachable!("{} has no limit", self.name()),
+            Carrier::Transparent => "an image with more transparent pixels",
+        }
+    }
+}
+
+/// Hides a file in, or recovers it from, a PNG file with one of the keyless carriers
+pub fn carrier(carrier: Carrier, command: CarrierCommands) -> anyhow::Result<()> {
+    match command {
+        CarrierCommands::Embed(args) => carrier_embed(carrier, args),
+        CarrierCommands::Extract(args) => carrier_extract(carrier, args),
+    }
+}
+
+fn carrier_embed(carrier: Carrier, args: CarrierEmbedArgs) -> anyhow::Result<()> {
+    let embedded = read_embedded_file(&args.file, args.name, args.mime)?;
+    let envelope = seal_payload(embedded.to_bytes(), ContentType::File, &args.seal, None)?;
+
+    let mut png = read_png(&args.path)?;
+    let payload = envelope.to_bytes();
+    if let Some(available) = carrier.capacity(&png)? {
+        if payload.len() > available {
+            bail!(
+                "the payload needs {} bytes but there is room for {available} in {}, \
+                 use --compress or {}",
+                payload.len(),
+                carrier.name(),
+                carrier.larger()
+            );
+        }
+    }
+    let before = args
+        .check_invisible
+        .then(|| Image::from_png(&png))
+        .transpose()?;
+    carrier.embed(&mut png, &payload)?;
+
+    if let Some(before) = before {
+        // decode the bytes that will be written, not the image in memory
+        let after = Image::from_png(&Png::try_from(png.as_bytes().as_slice())?)?;
+        if !after.looks_like(&before) {
+            bail!("the result would look different from the original, not writing it");
+        }
+        eprintln!("checked: the result looks the same as the original");
+    }
+    write_png(&png, args.output_file.unwrap_or(args.path))
+}
+
+fn carrier_extract(carrier: Carrier, args: CarrierExtractArgs) -> anyhow::Result<()> {
+    let png = read_png(&args.path)?;
+    let data = match carrier.extract(&png) {
+        Ok(data) if envelope::is_envelope(&data) => data,
+        Ok(_) | Err(CarrierError::NoPayload) => bail!("no payload found in {}", carrier.name()),
+        Err(e) => return Err(e.into()),
+    };
+    let envelope = Envelope::try_from(data.as_slice())?;
+    let envelope = open_found(Found::Envelope(envelope), &args.open)?;
+    if envelope.content_type() != ContentType::File {
+        bail!("{} does not hold an embedded file", carrier.name());
+    }
+    let embedded = EmbeddedFile::try_from(envelope.body())?;
+    write_embedded_file(&embedded, args.output, args.force)
+}
+
 /// Removes a chunk from a PNG file and saves the result
 pub fn remove(args: RemoveArgs) -> anyhow::Result<()> {
     let mut png = read_png(&args.path)?;
@@ -75,3 +918,478 @@ pub fn print_chunks(args: PrintArgs) -> anyhow::Result<()> {
     println!("{png}");
     Ok(())
 }
+
+/// Namespaces commonly found in XMP packets, by their conventional prefix
+const XMP_NAMESPACES: [(&str, &str); 8] = [
+    ("dc", "http://purl.org/dc/elements/1.1/"),
+    ("xmp", "http://ns.adobe.com/xap/1.0/"),
+    ("xmpRights", "http://ns.adobe.com/xap/1.0/rights/"),
+    ("xmpMM", "http://ns.adobe.com/xap/1.0/mm/"),
+    ("photoshop", "http://ns.adobe.com/photoshop/1.0/"),
+    ("tiff", "http://ns.adobe.com/tiff/1.0/"),
+    ("exif", "http://ns.adobe.com/exif/1.0/"),
+    (
+        "Iptc4xmpCore",
+        "http://iptc.org/std/Iptc4xmpCore/1.0/xmlns/",
+    ),
+];
+
+/// Reads or changes the XMP packet of a PNG file
+pub fn xmp(args: XmpArgs) -> anyhow::Result<()> {
+    match args.command {
+        XmpCommands::Get(args) => xmp_get(args),
+        XmpCommands::Set(args) => xmp_set(args),
+    }
+}
+
+fn xmp_get(args: XmpGetArgs) -> anyhow::Result<()> {
+    let png = read_png(&args.path)?;
+    let xmp = png.xmp()?.context("no XMP packet found")?;
+
+    let wanted = args.property.as_deref().map(split_property).transpose()?;
+    let mut found = false;
+    for p in xmp.properties()? {
+        match wanted {
+            Some((prefix, name)) if prefix == p.prefix && name == p.name => println!("{}", p.value),
+            Some(_) => continue,
+            None => println!("{}:{} = {}  [{}]", p.prefix, p.name, p.value, p.namespace),
+        }
+        found = true;
+    }
+
+    if let (Some(property), false) = (&args.property, found) {
+        bail!("property '{property}' not found");
+    }
+    Ok(())
+}
+
+fn xmp_set(args: XmpSetArgs) -> anyhow::Result<()> {
+    let mut png = read_png(&args.path)?;
+    let mut xmp = png.xmp()?.unwrap_or_default();
+
+    let (prefix, name) = split_property(&args.property)?;
+    let namespace = match args.namespace {
+        Some(namespace) => namespace,
+        None => xmp
+            .properties()?
+            .into_iter()
+            .find(|p| p.prefix == prefix)
+            .map(|p| p.namespace)
+            .or_else(|| {
+                XMP_NAMESPACES
+                    .iter()
+                    .find(|(p, _)| *p == prefix)
+                    .map(|(_, ns)| ns.to_string())
+            })
+            .ok_or_else(|| anyhow!("unknown prefix '{prefix}': use --namespace"))?,
+    };
+
+    xmp.set(&namespace, prefix, name, &args.value)?;
+    png.set_xmp(&xmp)?;
+
+    write_png(&png, args.output_file.unwrap_or(args.path))
+}
+
+/// Splits `prefix:name`
+fn split_property(property: &str) -> anyhow::Result<(&str, &str)> {
+    property
+        .split_once(':')
+        .ok_or_else(|| anyhow!("property '{property}' must be written as 'prefix:name'"))
+}
+
+/// Removes metadata from a PNG file and reports the bytes saved per category
+pub fn strip(args: StripArgs) -> anyhow::Result<()> {
+    let mut png = read_png(&args.path)?;
+    let before = pixels_before(&png, args.verify_pixels)?;
+
+    let strip = if !args.keep.is_empty() {
+        Strip::AllowList(args.keep)
+    } else if !args.remove.is_empty() {
+        Strip::DenyList(args.remove)
+    } else {
+        Strip::Default
+    };
+    let report = png.strip(&strip);
+    verify_pixels(&png, before)?;
+
+    write_png(&png, args.output_file.unwrap_or(args.path))?;
+    println!("{report}");
+    Ok(())
+}
+
+/// Merges or re-splits the IDAT chunks of a PNG file
+pub fn idat(args: IdatArgs) -> anyhow::Result<()> {
+    let mut png = read_png(&args.path)?;
+    let before = png.idat_count();
+
+    if let Some(size) = args.split_size {
+        png.split_idat(size)?;
+    } else {
+        png.merge_idat();
+    }
+
+    write_png(&png, args.output_file.unwrap_or(args.path))?;
+    println!("IDAT chunks: {before} -> {}", png.idat_count());
+    Ok(())
+}
+
+/// Losslessly recompresses a PNG file
+pub fn optimize(args: OptimizeArgs) -> anyhow::Result<()> {
+    let mut png = read_png(&args.path)?;
+    let before = pixels_before(&png, args.verify_pixels)?;
+
+    let options = OptimizeOptions {
+        levels: args.level,
+        reduce: !args.no_reduce,
+        strip: args.strip.then_some(Strip::Default),
+        rows_per_block: args.rows_per_block.map(|rows| rows as usize),
+        ..Default::default()
+    };
+    let report = png.optimize(&options)?;
+    verify_pixels(&png, before)?;
+
+    write_png(&png, args.output_file.unwrap_or(args.path))?;
+
+    match report.chosen {
+        Some((header, encode)) => println!(
+            "image data: {:?} {}-bit, filter {:?}, level {}",
+            header.color_type(),
+            header.bit_depth(),
+            encode.filter,
+            encode.level
+        ),
+        None => println!("image data: kept, already smallest"),
+    }
+    if let Some(stripped) = report.stripped {
+        println!("{stripped}");
+    }
+    println!(
+        "size: {} -> {} bytes",
+        report.original_size, report.optimized_size
+    );
+    Ok(())
+}
+
+/// Prints the digest of the pixels of a PNG file
+pub fn hash(args: HashArgs) -> anyhow::Result<()> {
+    let png = read_png(&args.path)?;
+    println!("{}", hex::encode(png.pixel_digest()?));
+    Ok(())
+}
+
+/// Writes a new keypair and prints the public key
+pub fn keygen(args: KeygenArgs) -> anyhow::Result<()> {
+    let mut public_path = args.path.clone().into_os_string();
+    public_path.push(".pub");
+    let public_path = PathBuf::from(public_path);
+
+    if !args.force {
+        for path in [&args.path, &public_path] {
+            if path.exists() {
+                bail!(
+                    "{} already exists, use --force to overwrite",
+                    path.display()
+                );
+            }
+        }
+    }
+
+    let (secret, public_key) = if args.signing {
+        let key = SigningKey::generate();
+        (key.to_file_contents(), key.verifying_key().to_string())
+    } else {
+        let identity = Identity::generate();
+        (
+            identity.to_file_contents(),
+            identity.public_key().to_string(),
+        )
+    };
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true);
+    if args.force {
+        options.create(true).truncate(true);
+    } else {
+        // fails if a file appeared since the check above
+        options.create_new(true);
+    }
+    let mut secret_options = options.clone();
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut secret_options, 0o600);
+    let mut file = secret_options
+        .open(&args.path)
+        .with_context(|| format!("cannot create file {}", args.path.display()))?;
+    // the mode only applies to new files, an overwritten one would keep its permissions
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(secret.as_bytes())?;
+
+    options
+        .open(&public_path)
+        .with_context(|| format!("cannot create file {}", public_path.display()))?
+        .write_all(format!("{public_key}\n").as_bytes())?;
+
+    println!("{public_key}");
+    Ok(())
+}
+
+/// Marks the pixels with an ID, or looks for one
+pub fn watermark(args: WatermarkArgs) -> anyhow::Result<()> {
+    match args.command {
+        WatermarkCommands::Embed(args) => watermark_embed(args),
+        WatermarkCommands::Detect(args) => watermark_detect(args),
+    }
+}
+
+fn read_watermark(args: &WatermarkKey) -> anyhow::Result<Watermark> {
+    let key = read_key(args.key.as_deref(), args.key_file.as_deref())?;
+    Ok(Watermark::new(key.as_bytes()))
+}
+
+fn watermark_embed(args: WatermarkEmbedArgs) -> anyhow::Result<()> {
+    let watermark = read_watermark(&args.key)?;
+    let mut png = read_png(&args.path)?;
+    let mut image = Image::from_png(&png)?;
+    if image.header().color_type() == ColorType::Indexed {
+        if !args.convert {
+            bail!("cannot watermark the pixels of a palette image, use --convert to make it RGB");
+        }
+        image = image.expand_palette();
+    }
+    watermark.embed(&mut image, args.id, args.strength)?;
+    image.write_to(&mut png, &EncodeOptions::default())?;
+
+    write_png(&png, args.output_file.unwrap_or(args.path))
+}
+
+/// Prints the ID found and the confidence. Fails if the confidence is below one half, which is
+/// what an image without the watermark, or with another key, gives.
+fn watermark_detect(args: WatermarkDetectArgs) -> anyhow::Result<()> {
+    let watermark = read_watermark(&args.key)?;
+    let png = read_png(&args.path)?;
+    let detection = watermark.detect(&Image::from_png(&png)?);
+
+    let confidence = detection.confidence() * 100.0;
+    if detection.confidence() < 0.5 {
+        bail!("no watermark found (confidence {confidence:.1}%)");
+    }
+    println!("id {} (confidence {confidence:.1}%)", detection.id());
+    let (x, y) = detection.shift();
+    if (x, y) != (0, 0) {
+        eprintln!("the pattern is shifted by {x} columns and {y} rows, the image may be cropped");
+    }
+    Ok(())
+}
+
+/// Reports whether a message is signed by the given key. Exits with status 1 unless the
+/// signature is valid.
+pub fn verify(args: VerifyArgs) -> anyhow::Result<()> {
+    let key: VerifyingKey = read_public_key(&args.pubkey)?;
+    let png = read_png(&args.path)?;
+    let Some(found) = find_payload(&png, &args.chunk_type)? else {
+        bail!("no {} chunk found", args.chunk_type);
+    };
+
+    if !found.has_layer(Flags::SIGNED, found.data(), signature::is_signed)? {
+        bail!("unsigned");
+    }
+    Signed::parse(found.data())
+        .and_then(|signed| {
+            signed.verify(
+                &key,
+                &found.associated_data(),
+                &args.chunk_type.bytes(),
+                &png.critical_digest(),
+            )
+        })
+        .context("invalid signature")?;
+    println!("valid");
+    Ok(())
+}
+
+/// Channels written the way `--channels` takes them
+fn channel_list(channels: &[ChannelBits]) -> String {
+    channels
+        .iter()
+        .map(ChannelBits::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Reports the largest message or file contents each embedding method can hold, after the
+/// overhead of the planned layers
+pub fn capacity(args: CapacityArgs) -> anyhow::Result<()> {
+    let png = read_png(&args.path)?;
+
+    let name = args.name.unwrap_or_default();
+    let mime = args
+        .mime
+        .unwrap_or_else(|| file::guess_mime(&name, &[]).to_string());
+    let mut layers = vec![(
+        "envelope",
+        envelope::HEADER_LENGTH + args.label.map_or(0, |l| l.len()),
+    )];
+    if args.compress {
+        layers.push(("compression", compress::HEADER_LENGTH));
+    }
+    if args.encrypt {
+        layers.push(("encryption", passphrase::OVERHEAD));
+    } else if let Some(recipients) = args.recipients {
+        layers.push(("encryption", recipient::overhead(recipients as usize)));
+    }
+    let overhead: usize = layers.iter().map(|(_, bytes)| bytes).sum();
+    let file_overhead = file::HEADER_LENGTH + name.len() + mime.len();
+    let signature_overhead = if args.sign {
+        signature::overhead(args.sign_image)
+    } else {
+        0
+    };
+
+    let summary = layers
+        .iter()
+        .map(|(layer, bytes)| format!("{layer} {bytes}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    println!("overhead: {overhead} bytes ({summary}), file header {file_overhead} bytes");
+    if args.sign {
+        println!("signature: {signature_overhead} bytes, chunks only");
+    }
+
+    // the envelope is not counted against the part size
+    let chunk = MAX_PART_SIZE.saturating_sub(overhead - layers[0].1 + signature_overhead);
+    println!("encode, one chunk: {chunk} bytes");
+    println!(
+        "embed, one chunk: {} bytes",
+        chunk.saturating_sub(file_overhead)
+    );
+    println!("chunks, split into parts: no limit");
+
+    let mut image = Image::from_png(&png)?;
+    println!(
+        "scanline filters: {} bytes",
+        filters::capacity(&image)?.saturating_sub(overhead + file_overhead)
+    );
+    // five payload bits in every five byte block
+    println!(
+        "image data stream: no limit, the file grows about {} bytes plus 8 per byte embedded",
+        zlib::stream_growth(overhead + file_overhead)
+    );
+
+    if image.header().color_type().has_alpha() {
+        println!(
+            "transparent pixels: {} bytes in {} pixels",
+            transparent::capacity(&image)?.saturating_sub(overhead + file_overhead),
+            transparent::transparent_pixels(&image)?
+        );
+    }
+
+    println!(
+        "zero-width text: no limit, the text grows about {} bytes plus 12 per byte embedded",
+        zero_width::hidden_length(overhead + file_overhead)
+    );
+
+    let converted = image.header().color_type() == ColorType::Indexed;
+    if converted {
+        match palette::capacity(&image) {
+            Ok(available) => println!(
+                "palette order: {} bytes",
+                available.saturating_sub(overhead + file_overhead)
+            ),
+            Err(CarrierError::PaletteOrder(reason)) => {
+                println!("palette order: not available, {reason}")
+            }
+            Err(e) => return Err(e.into()),
+        }
+        image = image.expand_palette();
+    }
+    if !image.transparency().is_empty() {
+        println!("lsb: not available, {}", CarrierError::ColorKey);
+        return Ok(());
+    }
+    let channels: Vec<Channel> = [Channel::Red, Channel::Green, Channel::Blue, Channel::Alpha]
+        .into_iter()
+        .filter(|&channel| {
+            let bits = ChannelBits::new(channel, 1).expect("1 bit is valid");
+            Lsb::new(&[], vec![bits])
+                .and_then(|lsb| lsb.capacity(&image))
+                .is_ok()
+        })
+        .collect();
+    if channels.is_empty() {
+        println!("lsb: not available, the samples are too small");
+        return Ok(());
+    }
+
+    let mut selections: Vec<Vec<ChannelBits>> = vec![];
+    for bits in 1..=2 {
+        for &channel in &channels {
+            selections.push(vec![ChannelBits::new(channel, bits)?]);
+        }
+    }
+    if channels.len() > 1 {
+        for bits in 1..=2 {
+            let all = channels
+                .iter()
+                .map(|&channel| ChannelBits::new(channel, bits))
+                .collect::<Result<Vec<_>, _>>()?;
+            selections.push(all);
+        }
+    }
+    let fec = args.fec.map(ReedSolomon::new).transpose()?;
+    if let Some(fec) = &fec {
+        println!(
+            "error correction: {} parity bytes per 255 byte block, in the pixels only",
+            fec.parity()
+        );
+    }
+    let note = if converted { ", with --convert" } else { "" };
+    for selection in selections {
+        let mut lsb = Lsb::new(&[], selection.clone())?;
+        lsb.set_fec(fec.clone());
+        let available = lsb.capacity(&image)?;
+        println!(
+            "lsb {}: {} bytes{note}",
+            channel_list(&selection),
+            available.saturating_sub(overhead + file_overhead)
+        );
+    }
+    Ok(())
+}
+
+/// Runs the steganalysis heuristics on a PNG file and reports their scores and evidence
+pub fn detect(args: DetectArgs) -> anyhow::Result<()> {
+    let png = read_png(&args.path)?;
+    let findings = detect::detect(&png);
+    let suspicion = findings.iter().map(Finding::score).fold(0.0, f64::max);
+
+    match args.format {
+        ReportFormat::Text => {
+            for finding in &findings {
+                println!("{:<14}{:.2}", finding.heuristic(), finding.score());
+                for evidence in finding.evidence() {
+                    println!("    {evidence}");
+                }
+            }
+            println!("suspicion: {suspicion:.2}");
+        }
+        ReportFormat::Json => {
+            let findings: Vec<_> = findings
+                .iter()
+                .map(|f| {
+                    serde_json::json!({
+                        "heuristic": f.heuristic().name(),
+                        "score": f.score(),
+                        "evidence": f.evidence(),
+                    })
+                })
+                .collect();
+            let report = serde_json::json!({
+                "file": args.path.display().to_string(),
+                "suspicion": suspicion,
+                "findings": findings,
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use args::{Cli, Commands};
 use clap::Parser;
+use commands::Carrier;
 
 mod args;
 mod commands;
@@ -12,6 +13,26 @@ fn main() -> anyhow::Result<()> {
         Commands::Decode(args) => commands::decode(args)?,
         Commands::Remove(args) => commands::remove(args)?,
         Commands::Print(args) => commands::print_chunks(args)?,
+        Commands::Xmp(args) => commands::xmp(args)?,
+        Commands::Strip(args) => commands::strip(args)?,
+        Commands::Idat(args) => commands::idat(args)?,
+        Commands::Optimize(args) => commands::optimize(args)?,
+        Commands::Hash(args) => commands::hash(args)?,
+        Commands::Keygen(args) => commands::keygen(args)?,
+        Commands::Verify(args) => commands::verify(args)?,
+        Commands::Embed(args) => commands::embed(args)?,
+        Commands::Extract(args) => commands::extract(args)?,
+        Commands::Split(args) => commands::split(args)?,
+        Commands::Combine(args) => commands::combine(args)?,
+        Commands::Lsb(args) => commands::lsb(args)?,
+        Commands::Watermark(args) => commands::watermark(args)?,
+        Commands::Palette(args) => commands::carrier(Carrier::Palette, args.command)?,
+        Commands::Filters(args) => commands::carrier(Carrier::Filters, args.command)?,
+        Commands::Zlib(args) => commands::carrier(Carrier::Zlib, args.command)?,
+        Commands::Transparent(args) => commands::carrier(Carrier::Transparent, args.command)?,
+        Commands::Text(args) => commands::carrier(Carrier::Text(args.keyword), args.command)?,
+        Commands::Capacity(args) => commands::capacity(args)?,
+        Commands::Detect(args) => commands::detect(args)?,
     }
 
     Ok(())
@@ -0,0 +1,780 @@
+use png_spec::{
+    chunk::Chunk,
+    chunk_type::ChunkType,
+    image::{ColorType, EncodeOptions, Header, Image},
+    png::Png,
+};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::str::FromStr;
+
+/// A 1x1 gray image
+const PNG_FILE: [u8; 67] = [
+    137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0,
+    0, 0, 58, 126, 155, 85, 0, 0, 0, 10, 73, 68, 65, 84, 120, 156, 99, 104, 0, 0, 0, 130, 0, 129,
+    119, 205, 114, 182, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66, 96, 130,
+];
+
+/// A path in the temporary directory unique to this test run
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("message-cli-{}-{name}", std::process::id()))
+}
+
+/// An empty directory in the temporary directory unique to this test run
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = temp_path(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// `name` in `dir`, as an argument
+fn file(dir: &Path, name: &str) -> String {
+    dir.join(name).to_str().unwrap().to_owned()
+}
+
+/// An image 64 pixels wide of a noisy gradient, with a palette of 256 colors for indexed images
+/// and a transparent corner for those with alpha
+fn image(color_type: ColorType, height: u32) -> Image {
+    let header = Header::new(64, height, 8, color_type).unwrap();
+    let mut image = Image::new(header, vec![0; header.data_length().unwrap()]).unwrap();
+    for y in 0..height {
+        for x in 0..64 {
+            let noise = (x * 7 + y * 13) % 5;
+            let samples = match color_type {
+                ColorType::Indexed => vec![(x / 4 + y / 4 % 16 * 16) as u16],
+                ColorType::Grayscale => vec![((x + y * 2 + noise) % 256) as u16],
+                ColorType::Rgb => vec![(x * 3 + noise) as u16, (y * 3 % 256) as u16, 128],
+                _ => {
+                    let alpha = if x < 16 && y < 16 { 0 } else { 255 };
+                    vec![(x * 3 + noise) as u16, (y * 3 % 256) as u16, 128, alpha]
+                }
+            };
+            for (c, sample) in samples.into_iter().enumerate() {
+                image.set_sample(x, y, c, sample);
+            }
+        }
+    }
+    if color_type == ColorType::Indexed {
+        let palette = (0..=255u8).map(|i| [i, 255 - i, i % 8 * 30]).collect();
+        image = image.with_palette(palette, vec![]);
+    }
+    image
+}
+
+/// Writes `image` as a PNG file at `path`, with any `extra` chunks before IDAT
+fn write_image(path: &str, image: &Image, extra: Vec<Chunk>) {
+    let mut chunks = vec![image.header().to_chunk()];
+    if !image.palette().is_empty() {
+        chunks.push(Chunk::new(ChunkType::PLTE, image.palette().concat()));
+    }
+    chunks.push(Chunk::new(ChunkType::IDAT, Vec::new()));
+    chunks.push(Chunk::new(ChunkType::IEND, Vec::new()));
+    let mut png = Png::from_chunks(chunks);
+    image.write_to(&mut png, &EncodeOptions::default()).unwrap();
+    let idat = png
+        .chunks()
+        .iter()
+        .position(|c| c.chunk_type() == &ChunkType::IDAT)
+        .unwrap();
+    for (i, chunk) in extra.into_iter().enumerate() {
+        png.insert_chunk(idat + i, chunk);
+    }
+    std::fs::write(path, png.as_bytes()).unwrap();
+}
+
+fn read_png(path: &str) -> Png {
+    Png::try_from(std::fs::read(path).unwrap().as_slice()).unwrap()
+}
+
+fn pixels(path: &str) -> Vec<[u16; 4]> {
+    Image::from_png(&read_png(path)).unwrap().to_rgba16()
+}
+
+/// Runs `message` expecting it to fail
+fn message_fails(args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_message"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "message {args:?} succeeded");
+    output
+}
+
+fn message(args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_message"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "message {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+#[test]
+fn test_encode_decode() {
+    let input = temp_path("plain.png");
+    let output = temp_path("encoded.png");
+    std::fs::write(&input, PNG_FILE).unwrap();
+    let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+
+    message(&["encode", input, "ruSt", "hidden message", output]);
+    let decoded = message(&["decode", output, "ruSt"]);
+    assert_eq!(decoded.stdout, b"hidden message\n");
+
+    // the message goes before IEND, so nothing trails the image
+    let encoded = std::fs::read(output).unwrap();
+    assert!(encoded.ends_with(&PNG_FILE[PNG_FILE.len() - 12..]));
+
+    std::fs::remove_file(input).unwrap();
+    std::fs::remove_file(output).unwrap();
+}
+
+#[test]
+fn test_decode_after_end() {
+    // older versions appended the message after IEND
+    let path = temp_path("legacy.png");
+    let chunk = Chunk::new(
+        ChunkType::from_str("ruSt").unwrap(),
+        b"old message".to_vec(),
+    );
+    std::fs::write(&path, [&PNG_FILE[..], &chunk.as_bytes()].concat()).unwrap();
+    let path = path.to_str().unwrap();
+
+    let decoded = message(&["decode", path, "ruSt"]);
+    assert_eq!(decoded.stdout, b"old message\n");
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_strip() {
+    let dir = temp_dir("strip");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    let comment = Chunk::new(
+        ChunkType::from_str("tEXt").unwrap(),
+        b"Author\0someone".to_vec(),
+    );
+    let gamma = Chunk::new(ChunkType::from_str("gAMA").unwrap(), vec![0, 0, 0xb1, 0x8f]);
+    write_image(&input, &image(ColorType::Rgb, 64), vec![comment, gamma]);
+
+    message(&["strip", &input, &output]);
+    let stripped = read_png(&output);
+    assert!(stripped.chunk_by_type("tEXt").is_none());
+    assert!(stripped.chunk_by_type("gAMA").is_some());
+    assert_eq!(pixels(&output), pixels(&input));
+
+    message(&["strip", "--remove", "color", &input, &output]);
+    let stripped = read_png(&output);
+    assert!(stripped.chunk_by_type("tEXt").is_some());
+    assert!(stripped.chunk_by_type("gAMA").is_none());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+fn idat_count(path: &str) -> usize {
+    let png = read_png(path);
+    png.chunks()
+        .iter()
+        .filter(|c| c.chunk_type() == &ChunkType::IDAT)
+        .count()
+}
+
+#[test]
+fn test_idat() {
+    let dir = temp_dir("idat");
+    let (input, split, merged) = (
+        file(&dir, "in.png"),
+        file(&dir, "split.png"),
+        file(&dir, "merged.png"),
+    );
+    write_image(&input, &image(ColorType::Rgb, 64), vec![]);
+
+    message(&["idat", "--split-size", "100", &input, &split]);
+    assert!(idat_count(&split) > 1);
+    message(&["idat", "--merge", &split, &merged]);
+    assert_eq!(idat_count(&merged), 1);
+    assert_eq!(pixels(&merged), pixels(&input));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_optimize() {
+    let dir = temp_dir("optimize");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    let comment = Chunk::new(
+        ChunkType::from_str("tEXt").unwrap(),
+        b"Author\0someone".to_vec(),
+    );
+    write_image(&input, &image(ColorType::Rgb, 64), vec![comment]);
+
+    message(&["optimize", "--verify-pixels", "--strip", &input, &output]);
+    assert!(read_png(&output).chunk_by_type("tEXt").is_none());
+    assert!(std::fs::metadata(&output).unwrap().len() < std::fs::metadata(&input).unwrap().len());
+    assert_eq!(pixels(&output), pixels(&input));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_password() {
+    let dir = temp_dir("password");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    std::fs::write(&input, PNG_FILE).unwrap();
+
+    message(&[
+        "encode",
+        "--password",
+        "hunter2",
+        &input,
+        "ruSt",
+        "hidden message",
+        &output,
+    ]);
+    let encoded = std::fs::read(&output).unwrap();
+    assert!(!encoded.windows(6).any(|w| w == b"hidden"));
+
+    let decoded = message(&["decode", "--password", "hunter2", &output, "ruSt"]);
+    assert_eq!(decoded.stdout, b"hidden message\n");
+    message_fails(&["decode", "--password", "hunter3", &output, "ruSt"]);
+    message_fails(&["decode", &output, "ruSt"]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_recipients() {
+    let dir = temp_dir("recipients");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    let (alice, bob, eve) = (file(&dir, "alice"), file(&dir, "bob"), file(&dir, "eve"));
+    std::fs::write(&input, PNG_FILE).unwrap();
+    for key in [&alice, &bob, &eve] {
+        message(&["keygen", key]);
+    }
+    message_fails(&["keygen", &alice]);
+
+    let (alice_pub, bob_pub) = (format!("{alice}.pub"), format!("{bob}.pub"));
+    let args = ["--recipient", &alice_pub, "--recipient", &bob_pub];
+    message(
+        &[
+            &["encode"],
+            &args[..],
+            &[&input, "ruSt", "for two", &output],
+        ]
+        .concat(),
+    );
+    for identity in [&alice, &bob] {
+        let decoded = message(&["decode", "--identity", identity, &output, "ruSt"]);
+        assert_eq!(decoded.stdout, b"for two\n");
+    }
+    message_fails(&["decode", "--identity", &eve, &output, "ruSt"]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_sign_verify() {
+    let dir = temp_dir("sign");
+    let (input, signed, unsigned) = (
+        file(&dir, "in.png"),
+        file(&dir, "signed.png"),
+        file(&dir, "unsigned.png"),
+    );
+    let (key, other) = (file(&dir, "key"), file(&dir, "other"));
+    std::fs::write(&input, PNG_FILE).unwrap();
+    message(&["keygen", "--signing", &key]);
+    message(&["keygen", "--signing", &other]);
+    let (key_pub, other_pub) = (format!("{key}.pub"), format!("{other}.pub"));
+
+    message(&[
+        "encode",
+        "--sign-key",
+        &key,
+        &input,
+        "ruSt",
+        "signed",
+        &signed,
+    ]);
+    let verified = message(&["verify", "--pubkey", &key_pub, &signed, "ruSt"]);
+    assert_eq!(verified.stdout, b"valid\n");
+    let decoded = message(&["decode", &signed, "ruSt"]);
+    assert_eq!(decoded.stdout, b"signed\n");
+    message_fails(&["verify", "--pubkey", &other_pub, &signed, "ruSt"]);
+
+    message(&["encode", &input, "ruSt", "unsigned", &unsigned]);
+    message_fails(&["verify", "--pubkey", &key_pub, &unsigned, "ruSt"]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_embed_extract() {
+    let dir = temp_dir("embed");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    let (secret, extracted) = (file(&dir, "secret.bin"), file(&dir, "extracted.bin"));
+    std::fs::write(&input, PNG_FILE).unwrap();
+    let contents: Vec<u8> = (0..=255).cycle().take(1000).collect();
+    std::fs::write(&secret, &contents).unwrap();
+
+    message(&["embed", &input, "ruSt", &secret, &output]);
+    message(&["extract", "-o", &extracted, &output, "ruSt"]);
+    assert_eq!(std::fs::read(&extracted).unwrap(), contents);
+    message_fails(&["extract", "-o", &extracted, &output, "ruSt"]);
+    message(&["extract", "--force", "-o", &extracted, &output, "ruSt"]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_part_size() {
+    let dir = temp_dir("parts");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    std::fs::write(&input, PNG_FILE).unwrap();
+    let long = "a message long enough to need several parts ".repeat(4);
+
+    message(&[
+        "encode",
+        "--part-size",
+        "32",
+        &input,
+        "ruSt",
+        &long,
+        &output,
+    ]);
+    let parts = read_png(&output)
+        .chunks()
+        .iter()
+        .filter(|c| c.chunk_type().to_string() == "ruSt")
+        .count();
+    assert!(parts > 1);
+    let decoded = message(&["decode", &output, "ruSt"]);
+    assert_eq!(decoded.stdout, format!("{long}\n").as_bytes());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_compress() {
+    let dir = temp_dir("compress");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    std::fs::write(&input, PNG_FILE).unwrap();
+    let repetitive = "again and again ".repeat(64);
+
+    for mode in ["zlib", "zstd", "brotli", "auto"] {
+        message(&[
+            "encode",
+            "--compress",
+            mode,
+            &input,
+            "ruSt",
+            &repetitive,
+            &output,
+        ]);
+        let png = read_png(&output);
+        assert!(png.chunk_by_type("ruSt").unwrap().data().len() < repetitive.len() / 4);
+        let decoded = message(&["decode", &output, "ruSt"]);
+        assert_eq!(decoded.stdout, format!("{repetitive}\n").as_bytes());
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_metadata() {
+    let dir = temp_dir("metadata");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    std::fs::write(&input, PNG_FILE).unwrap();
+
+    let args = ["--label", "notes", "--timestamp", "--password", "pw"];
+    message(
+        &[
+            &["encode"],
+            &args[..],
+            &[&input, "ruSt", "labelled", &output],
+        ]
+        .concat(),
+    );
+    let decoded = message(&["decode", "--metadata", "--password", "pw", &output, "ruSt"]);
+    assert_eq!(decoded.stdout, b"labelled\n");
+    let metadata = String::from_utf8(decoded.stderr).unwrap();
+    assert!(metadata.contains("label: notes"), "{metadata}");
+    assert!(metadata.contains("timestamp: "), "{metadata}");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_lsb() {
+    let dir = temp_dir("lsb");
+    let (input, output, keyed) = (
+        file(&dir, "in.png"),
+        file(&dir, "out.png"),
+        file(&dir, "keyed.png"),
+    );
+    let (secret, extracted) = (file(&dir, "secret.txt"), file(&dir, "extracted.txt"));
+    write_image(&input, &image(ColorType::Rgb, 64), vec![]);
+    std::fs::write(&secret, b"in the low bits").unwrap();
+
+    message(&["lsb", "embed", "--key", "k", &input, &secret, &output]);
+    assert_ne!(pixels(&output), pixels(&input));
+    message(&["lsb", "extract", "--key", "k", "-o", &extracted, &output]);
+    assert_eq!(std::fs::read(&extracted).unwrap(), b"in the low bits");
+    message_fails(&[
+        "lsb", "extract", "--key", "other", "-o", &extracted, &output,
+    ]);
+
+    // a transparent color could be turned on or off by changing a bit
+    let color_key = Chunk::new(ChunkType::from_str("tRNS").unwrap(), vec![0; 6]);
+    write_image(&keyed, &image(ColorType::Rgb, 64), vec![color_key]);
+    message_fails(&["lsb", "embed", "--key", "k", &keyed, &secret, &output]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_capacity() {
+    let dir = temp_dir("capacity");
+    let (input, output, secret) = (
+        file(&dir, "in.png"),
+        file(&dir, "out.png"),
+        file(&dir, "secret.bin"),
+    );
+    write_image(&input, &image(ColorType::Rgb, 64), vec![]);
+
+    let report = message(&["capacity", "--name", "secret.bin", &input]);
+    let report = String::from_utf8(report.stdout).unwrap();
+    let line = report
+        .lines()
+        .find_map(|l| l.strip_prefix("lsb red:1,green:1,blue:1: "))
+        .unwrap();
+    let capacity: usize = line.strip_suffix(" bytes").unwrap().parse().unwrap();
+
+    // exactly the capacity fits, one byte more does not
+    std::fs::write(&secret, vec![7; capacity]).unwrap();
+    message(&["lsb", "embed", "--key", "k", &input, &secret, &output]);
+    std::fs::write(&secret, vec![7; capacity + 1]).unwrap();
+    message_fails(&["lsb", "embed", "--key", "k", &input, &secret, &output]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_lsb_fec() {
+    let dir = temp_dir("fec");
+    let (input, output, damaged) = (
+        file(&dir, "in.png"),
+        file(&dir, "out.png"),
+        file(&dir, "damaged.png"),
+    );
+    let (secret, extracted) = (file(&dir, "secret.txt"), file(&dir, "extracted.txt"));
+    write_image(&input, &image(ColorType::Rgb, 64), vec![]);
+    std::fs::write(&secret, b"survives a few flipped bits").unwrap();
+
+    let options = ["--key", "k", "--fec", "32"];
+    message(&[&["lsb", "embed"], &options[..], &[&input, &secret, &output]].concat());
+    let mut image = Image::from_png(&read_png(&output)).unwrap();
+    for x in 0..64 {
+        let red = image.sample(x, 0, 0);
+        image.set_sample(x, 0, 0, red ^ 1);
+    }
+    write_image(&damaged, &image, vec![]);
+
+    message(
+        &[
+            &["lsb", "extract"],
+            &options[..],
+            &["-o", &extracted, &damaged],
+        ]
+        .concat(),
+    );
+    assert_eq!(
+        std::fs::read(&extracted).unwrap(),
+        b"survives a few flipped bits"
+    );
+    let without_fec = ["lsb", "extract", "--key", "k", "-o", &extracted, "--force"];
+    message_fails(&[&without_fec[..], &[&damaged]].concat());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_detect() {
+    let dir = temp_dir("detect");
+    let (clean, trailing) = (file(&dir, "clean.png"), file(&dir, "trailing.png"));
+    std::fs::write(&clean, PNG_FILE).unwrap();
+    std::fs::write(
+        &trailing,
+        [&PNG_FILE[..], b"appended after the end"].concat(),
+    )
+    .unwrap();
+
+    let score = |path: &str| {
+        let report = message(&["detect", "--format", "json", path]);
+        let report: serde_json::Value = serde_json::from_slice(&report.stdout).unwrap();
+        let findings = report["findings"].as_array().unwrap();
+        let finding = findings.iter().find(|f| f["heuristic"] == "trailing-data");
+        finding.unwrap()["score"].as_f64().unwrap()
+    };
+    assert_eq!(score(&clean), 0.0);
+    assert!(score(&trailing) > 0.5);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_palette() {
+    let dir = temp_dir("palette");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    let (secret, extracted) = (file(&dir, "secret.txt"), file(&dir, "extracted.txt"));
+    write_image(&input, &image(ColorType::Indexed, 64), vec![]);
+    std::fs::write(&secret, b"order").unwrap();
+
+    message(&["palette", "embed", &input, &secret, &output]);
+    assert_eq!(pixels(&output), pixels(&input));
+    message(&["palette", "extract", "-o", &extracted, &output]);
+    assert_eq!(std::fs::read(&extracted).unwrap(), b"order");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_filters_zlib() {
+    let dir = temp_dir("filters");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    let (secret, extracted) = (file(&dir, "secret.txt"), file(&dir, "extracted.txt"));
+    write_image(&input, &image(ColorType::Rgb, 512), vec![]);
+    std::fs::write(&secret, b"in the stream").unwrap();
+
+    for carrier in ["filters", "zlib"] {
+        message(&[carrier, "embed", &input, &secret, &output]);
+        assert_eq!(pixels(&output), pixels(&input));
+        message(&[carrier, "extract", "--force", "-o", &extracted, &output]);
+        assert_eq!(std::fs::read(&extracted).unwrap(), b"in the stream");
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_transparent() {
+    let dir = temp_dir("transparent");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    let (secret, extracted) = (file(&dir, "secret.txt"), file(&dir, "extracted.txt"));
+    write_image(&input, &image(ColorType::Rgba, 64), vec![]);
+    std::fs::write(&secret, b"under the invisible pixels").unwrap();
+
+    message(&["transparent", "embed", &input, &secret, &output]);
+    let visible = |path: &str| {
+        let pixels = pixels(path).into_iter();
+        pixels
+            .map(|p| if p[3] == 0 { [0; 4] } else { p })
+            .collect::<Vec<_>>()
+    };
+    assert_ne!(pixels(&output), pixels(&input));
+    assert_eq!(visible(&output), visible(&input));
+    message(&["transparent", "extract", "-o", &extracted, &output]);
+    assert_eq!(
+        std::fs::read(&extracted).unwrap(),
+        b"under the invisible pixels"
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_text() {
+    let dir = temp_dir("text");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    let (secret, extracted) = (file(&dir, "secret.txt"), file(&dir, "extracted.txt"));
+    let description = Chunk::new(
+        ChunkType::from_str("iTXt").unwrap(),
+        b"Description\0\0\0\0\0A quiet lake".to_vec(),
+    );
+    write_image(&input, &image(ColorType::Rgb, 64), vec![description]);
+    std::fs::write(&secret, b"between the letters").unwrap();
+
+    let keyword = ["--keyword", "Description"];
+    message(
+        &[
+            &["text", "embed"],
+            &keyword[..],
+            &[&input, &secret, &output],
+        ]
+        .concat(),
+    );
+    let data = read_png(&output)
+        .chunk_by_type("iTXt")
+        .unwrap()
+        .data()
+        .to_vec();
+    let visible: String = String::from_utf8(data)
+        .unwrap()
+        .chars()
+        .filter(|c| !matches!(c, '\u{200b}'..='\u{200d}' | '\u{2060}'))
+        .collect();
+    assert_eq!(visible, "Description\0\0\0\0\0A quiet lake");
+
+    message(
+        &[
+            &["text", "extract"],
+            &keyword[..],
+            &["-o", &extracted, &output],
+        ]
+        .concat(),
+    );
+    assert_eq!(std::fs::read(&extracted).unwrap(), b"between the letters");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_disguise() {
+    let dir = temp_dir("disguise");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    write_image(&input, &image(ColorType::Rgb, 64), vec![]);
+
+    for chunk_type in ["zTXt", "iCCP"] {
+        let password = ["--password", "pw"];
+        let args = [&input, chunk_type, "in plain sight", &output];
+        message(&[&["encode"], &password[..], &args[..]].concat());
+        assert!(read_png(&output).chunk_by_type(chunk_type).is_some());
+        assert_eq!(pixels(&output), pixels(&input));
+        let decoded = message(&[&["decode"], &password[..], &[&output, chunk_type]].concat());
+        assert_eq!(decoded.stdout, b"in plain sight\n");
+
+        // anyone can read a disguised payload, so it must be encrypted
+        message_fails(&[&["encode"], &args[..]].concat());
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_decoys() {
+    let dir = temp_dir("decoys");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    std::fs::write(&input, PNG_FILE).unwrap();
+
+    let decoy = ["--decoy", "shopping list", "--decoy-password", "weak"];
+    let args = [&input, "ruSt", "the real message", &output];
+    message(&[&["encode", "--password", "strong"], &decoy[..], &args[..]].concat());
+
+    for (password, expected) in [("strong", "the real message"), ("weak", "shopping list")] {
+        let decoded = message(&["decode", "--password", password, &output, "ruSt"]);
+        assert_eq!(decoded.stdout, format!("{expected}\n").as_bytes());
+    }
+    message_fails(&["decode", "--password", "guess", &output, "ruSt"]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_split_combine() {
+    let dir = temp_dir("split");
+    let shares = dir.join("shares");
+    std::fs::create_dir(&shares).unwrap();
+    let (secret, combined) = (file(&dir, "secret.bin"), file(&dir, "combined.bin"));
+    let inputs: Vec<String> = (0..3).map(|i| file(&dir, &format!("{i}.png"))).collect();
+    for input in &inputs {
+        std::fs::write(input, PNG_FILE).unwrap();
+    }
+    std::fs::write(&secret, b"two of three").unwrap();
+
+    let output_dir = ["--threshold", "2", "--output-dir", shares.to_str().unwrap()];
+    let args = [&["split"], &output_dir[..], &["ruSt", &secret]].concat();
+    message(
+        &[
+            &args[..],
+            &inputs.iter().map(String::as_str).collect::<Vec<_>>(),
+        ]
+        .concat(),
+    );
+    let shares: Vec<String> = (0..3).map(|i| file(&shares, &format!("{i}.png"))).collect();
+
+    message(&["combine", "-o", &combined, "ruSt", &shares[0], &shares[2]]);
+    assert_eq!(std::fs::read(&combined).unwrap(), b"two of three");
+    message_fails(&["combine", "-o", &file(&dir, "one.bin"), "ruSt", &shares[1]]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_watermark() {
+    let dir = temp_dir("watermark");
+    let (input, marked, optimized) = (
+        file(&dir, "in.png"),
+        file(&dir, "marked.png"),
+        file(&dir, "optimized.png"),
+    );
+    write_image(&input, &image(ColorType::Rgb, 256), vec![]);
+
+    message(&[
+        "watermark",
+        "embed",
+        "--id",
+        "12345",
+        "--key",
+        "k",
+        &input,
+        &marked,
+    ]);
+    message(&["optimize", "--strip", &marked, &optimized]);
+    let detected = message(&["watermark", "detect", "--key", "k", &optimized]);
+    let detected = String::from_utf8(detected.stdout).unwrap();
+    assert!(detected.starts_with("id 12345 "), "{detected}");
+    message_fails(&["watermark", "detect", "--key", "other", &optimized]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_hash() {
+    let dir = temp_dir("hash");
+    let (input, rewritten, changed) = (
+        file(&dir, "in.png"),
+        file(&dir, "rewritten.png"),
+        file(&dir, "changed.png"),
+    );
+    let secret = file(&dir, "secret.txt");
+    write_image(&input, &image(ColorType::Rgb, 64), vec![]);
+    std::fs::write(&secret, b"changes a few bits").unwrap();
+    let hash = |path: &str| message(&["hash", path]).stdout;
+
+    let args = [
+        "--verify-pixels",
+        &input,
+        "ruSt",
+        "metadata only",
+        &rewritten,
+    ];
+    message(&[&["encode"], &args[..]].concat());
+    message(&["optimize", "--verify-pixels", &rewritten]);
+    assert_eq!(hash(&rewritten), hash(&input));
+
+    message(&["lsb", "embed", "--key", "k", &input, &secret, &changed]);
+    assert_ne!(hash(&changed), hash(&input));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_xmp() {
+    let dir = temp_dir("xmp");
+    let (input, output) = (file(&dir, "in.png"), file(&dir, "out.png"));
+    std::fs::write(&input, PNG_FILE).unwrap();
+
+    message(&["xmp", "set", &input, "dc:title", "A lake", &output]);
+    message(&["xmp", "set", &output, "dc:format", "image/png"]);
+    let title = message(&["xmp", "get", &output, "dc:title"]);
+    assert_eq!(title.stdout, b"A lake\n");
+    let all = String::from_utf8(message(&["xmp", "get", &output]).stdout).unwrap();
+    assert!(all.contains("dc:title = A lake"), "{all}");
+    assert!(all.contains("dc:format = image/png"), "{all}");
+    assert!(read_png(&output).chunk_by_type("iTXt").is_some());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
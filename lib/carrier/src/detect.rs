@@ -0,0 +1,534 @@
+//! Steganalysis: heuristics that look for data hidden in a PNG file.
+//!
+//! Each heuristic gives a suspicion score from 0 (nothing found) to 1 (almost certainly hidden
+//! data) and the evidence behind it. The scores are not probabilities and do not add up; a clean
+//! image can score on one heuristic, which is why the evidence is reported as well.
+//!
+//! The pixel statistics are the chi-square attack of Westfeld and Pfitzmann and the RS analysis
+//! of Fridrich, Goljan and Du. Both look at the lowest bit of 8-bit samples, and both mainly
+//! notice embedding that changes a large share of the samples.
+
+use png_spec::{
+    image::{ColorType, FilterType, Image, ImageError},
+    png::Png,
+};
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+/// Chunk types defined by the PNG specification and its registered extensions.
+const KNOWN_CHUNKS: [&str; 29] = [
+    "IHDR", "PLTE", "IDAT", "IEND", "tRNS", "cHRM", "gAMA", "iCCP", "sBIT", "sRGB", "cICP", "mDCV",
+    "cLLI", "tEXt", "zTXt", "iTXt", "bKGD", "hIST", "pHYs", "sPLT", "eXIf", "tIME", "acTL", "fcTL",
+    "fdAT", "oFFs", "pCAL", "sCAL", "sTER",
+];
+
+/// Chunks whose data is compressed by definition, so high entropy is expected.
+const COMPRESSED_CHUNKS: [&str; 5] = ["IDAT", "fdAT", "zTXt", "iTXt", "iCCP"];
+
+/// Shortest chunk data whose entropy says anything.
+const MIN_ENTROPY_LENGTH: usize = 128;
+
+/// Entropy in bits per byte above which data stops looking like text. Base64 stays at 6.
+const TEXT_ENTROPY: f64 = 5.0;
+
+/// Fewest samples in a pair of values for the chi-square test to count it.
+const MIN_PAIR_COUNT: u32 = 10;
+
+/// Smallest share of pixel groups that must change under flipping for RS analysis to estimate.
+const MIN_TEXTURED_GROUPS: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heuristic {
+    /// Private or unknown ancillary chunks
+    Chunks,
+    /// Chunk data that looks random
+    Entropy,
+    /// Bytes after IEND
+    TrailingData,
+    /// Pairs of sample values equalized by LSB embedding
+    ChiSquare,
+    /// Regular and singular pixel groups, estimating how many samples carry a message
+    Rs,
+    /// Scanline filter types no encoder would choose
+    Filters,
+}
+
+impl Heuristic {
+    pub const ALL: [Heuristic; 6] = [
+        Heuristic::Chunks,
+        Heuristic::Entropy,
+        Heuristic::TrailingData,
+        Heuristic::ChiSquare,
+        Heuristic::Rs,
+        Heuristic::Filters,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Heuristic::Chunks => "chunks",
+            Heuristic::Entropy => "entropy",
+            Heuristic::TrailingData => "trailing-data",
+            Heuristic::ChiSquare => "chi-square",
+            Heuristic::Rs => "rs",
+            Heuristic::Filters => "filters",
+        }
+    }
+}
+
+impl fmt::Display for Heuristic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// What one heuristic found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    heuristic: Heuristic,
+    score: f64,
+    evidence: Vec<String>,
+}
+
+impl Finding {
+    fn new(heuristic: Heuristic, score: f64, evidence: Vec<String>) -> Finding {
+        Finding {
+            heuristic,
+            score: score.clamp(0.0, 1.0),
+            evidence,
+        }
+    }
+
+    pub fn heuristic(&self) -> Heuristic {
+        self.heuristic
+    }
+
+    /// Suspicion from 0 to 1.
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    pub fn evidence(&self) -> &[String] {
+        &self.evidence
+    }
+}
+
+/// Runs every heuristic on `png`.
+pub fn detect(png: &Png) -> Vec<Finding> {
+    let image = Image::from_png(png);
+    Heuristic::ALL
+        .into_iter()
+        .map(|heuristic| match (heuristic, &image) {
+            (Heuristic::Chunks, _) => chunks(png),
+            (Heuristic::Entropy, _) => entropy(png),
+            (Heuristic::TrailingData, _) => trailing_data(png),
+            (Heuristic::Filters, _) => filters(png, image.as_ref()),
+            (_, Err(e)) => Finding::new(heuristic, 0.0, vec![format!("not decodable: {e}")]),
+            (Heuristic::ChiSquare, Ok(image)) => chi_square(image),
+            (Heuristic::Rs, Ok(image)) => rs(image),
+        })
+        .collect()
+}
+
+fn chunks(png: &Png) -> Finding {
+    let mut score: f64 = 0.0;
+    let mut evidence = Vec::new();
+    for chunk in png.chunks() {
+        let chunk_type = chunk.chunk_type();
+        if !chunk_type.is_public() {
+            score = score.max(if chunk.data_length() > 0 { 0.9 } else { 0.5 });
+            evidence.push(format!(
+                "private chunk {chunk_type}, {} bytes",
+                chunk.data_length()
+            ));
+        } else if !KNOWN_CHUNKS.contains(&chunk_type.to_string().as_str()) {
+            score = score.max(0.6);
+            evidence.push(format!(
+                "unknown public chunk {chunk_type}, {} bytes",
+                chunk.data_length()
+            ));
+        }
+    }
+    Finding::new(Heuristic::Chunks, score, evidence)
+}
+
+/// Shannon entropy in bits per byte.
+fn shannon(data: &[u8]) -> f64 {
+    let mut counts = [0u32; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+    let length = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / length;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Entropy random data of `length` bytes is expected to show; short samples fall short of 8
+/// bits per byte.
+fn random_entropy(length: usize) -> f64 {
+    8.0 - 255.0 / (2.0 * length as f64 * std::f64::consts::LN_2)
+}
+
+/// Entropy of the data of chunks that are not compressed by definition. Text and most metadata
+/// stay below 5 bits per byte, encrypted or compressed data comes close to 8.
+fn entropy(png: &Png) -> Finding {
+    let mut score: f64 = 0.0;
+    let mut evidence = Vec::new();
+    for chunk in png.chunks() {
+        let chunk_type = chunk.chunk_type().to_string();
+        if COMPRESSED_CHUNKS.contains(&chunk_type.as_str())
+            || chunk.data().len() < MIN_ENTROPY_LENGTH
+        {
+            continue;
+        }
+        let bits = shannon(chunk.data());
+        let chunk_score =
+            (bits - TEXT_ENTROPY) / (random_entropy(chunk.data().len()) - TEXT_ENTROPY);
+        if chunk_score > 0.0 {
+            score = score.max(chunk_score);
+            evidence.push(format!(
+                "{chunk_type}: {bits:.2} bits per byte over {} bytes",
+                chunk.data().len()
+            ));
+        }
+    }
+    Finding::new(Heuristic::Entropy, score, evidence)
+}
+
+fn trailing_data(png: &Png) -> Finding {
+    let trailing = png.trailing_data();
+    if trailing.is_empty() {
+        return Finding::new(Heuristic::TrailingData, 0.0, vec![]);
+    }
+    let mut evidence = vec![format!("{} bytes after IEND", trailing.len())];
+    let chunks = png.trailing_chunks();
+    if !chunks.is_empty() {
+        let types: Vec<String> = chunks.iter().map(|c| c.chunk_type().to_string()).collect();
+        evidence.push(format!("chunks after IEND: {}", types.join(", ")));
+    }
+    if trailing.len() >= MIN_ENTROPY_LENGTH {
+        evidence.push(format!("{:.2} bits per byte", shannon(trailing)));
+    }
+    Finding::new(Heuristic::TrailingData, 1.0, evidence)
+}
+
+/// Channels whose lowest bits the pixel statistics look at, with their names.
+fn sample_channels(image: &Image) -> Result<Vec<(usize, &'static str)>, String> {
+    let header = image.header();
+    if header.color_type() == ColorType::Indexed {
+        return Err("palette image, the order of the palette decides the statistics".to_string());
+    }
+    if header.bit_depth() < 8 {
+        return Err(format!("{} bit samples", header.bit_depth()));
+    }
+    Ok(match header.color_type() {
+        ColorType::Grayscale | ColorType::GrayscaleAlpha => vec![(0, "gray")],
+        _ => vec![(0, "red"), (1, "green"), (2, "blue")],
+    })
+}
+
+/// Every sample of `channel` reduced to its low 8 bits, row by row.
+fn samples(image: &Image, channel: usize) -> Vec<u8> {
+    (0..image.height())
+        .flat_map(|y| (0..image.width()).map(move |x| (x, y)))
+        .map(|(x, y)| image.sample(x, y, channel) as u8)
+        .collect()
+}
+
+/// LSB embedding of random bits makes the counts of 2k and 2k + 1 alike. The score is the
+/// chi-square probability that the pairs are this close by chance.
+fn chi_square(image: &Image) -> Finding {
+    let channels = match sample_channels(image) {
+        Ok(channels) => channels,
+        Err(e) => return Finding::new(Heuristic::ChiSquare, 0.0, vec![e]),
+    };
+
+    let mut score: f64 = 0.0;
+    let mut evidence = Vec::new();
+    for (channel, name) in channels {
+        let mut histogram = [0u32; 256];
+        for sample in samples(image, channel) {
+            histogram[sample as usize] += 1;
+        }
+        let mut statistic = 0.0;
+        let mut pairs = 0;
+        for pair in histogram.chunks_exact(2) {
+            let (even, odd) = (pair[0], pair[1]);
+            if even + odd < MIN_PAIR_COUNT {
+                continue;
+            }
+            let expected = (even + odd) as f64 / 2.0;
+            statistic += (even as f64 - expected).powi(2) / expected;
+            pairs += 1;
+        }
+        if pairs < 2 {
+            evidence.push(format!("{name}: too few distinct values"));
+            continue;
+        }
+        let p = 1.0 - gamma_p((pairs - 1) as f64 / 2.0, statistic / 2.0);
+        score = score.max(p);
+        evidence.push(format!(
+            "{name}: chi-square {statistic:.1} over {pairs} pairs, p = {p:.3}"
+        ));
+    }
+    Finding::new(Heuristic::ChiSquare, score, evidence)
+}
+
+/// Regularized lower incomplete gamma function P(a, x), the chi-square distribution function
+/// with 2a degrees of freedom at 2x.
+fn gamma_p(a: f64, x: f64) -> f64 {
+    const ITERATIONS: usize = 1000;
+    const EPSILON: f64 = 1e-14;
+    const TINY: f64 = 1e-300;
+
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let front = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        // series
+        let (mut term, mut sum, mut n) = (1.0 / a, 1.0 / a, a);
+        for _ in 0..ITERATIONS {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+            if term.abs() < sum.abs() * EPSILON {
+                break;
+            }
+        }
+        (sum * front).min(1.0)
+    } else {
+        // continued fraction for the upper function, by Lentz's method
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..ITERATIONS {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = b + an / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < EPSILON {
+                break;
+            }
+        }
+        (1.0 - front * h).max(0.0)
+    }
+}
+
+/// Natural logarithm of the gamma function, Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let mut tmp = x + 5.5;
+    tmp -= (x + 0.5) * tmp.ln();
+    let mut y = x;
+    let series = COEFFICIENTS.iter().fold(1.000000000190015, |series, c| {
+        y += 1.0;
+        series + c / y
+    });
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+/// Fractions of regular and singular groups under the mask and the negative mask.
+#[derive(Debug, Clone, Copy)]
+struct GroupCounts {
+    regular: f64,
+    singular: f64,
+    negative_regular: f64,
+    negative_singular: f64,
+}
+
+/// Flipping with the mask [0, 1, 1, 0] over horizontal groups of four samples.
+fn group_counts(samples: &[u8], width: usize, flip_all: bool) -> Option<GroupCounts> {
+    const MASK: [bool; 4] = [false, true, true, false];
+    let smoothness = |g: &[i16; 4]| -> i16 { g.windows(2).map(|w| (w[1] - w[0]).abs()).sum() };
+
+    let (mut groups, mut counts) = (0, [0u32; 4]);
+    for row in samples.chunks_exact(width) {
+        for group in row.chunks_exact(4) {
+            let group: [i16; 4] = std::array::from_fn(|i| (group[i] ^ flip_all as u8) as i16);
+            let before = smoothness(&group);
+            // flipping 2k <-> 2k + 1, and the shifted flipping 2k - 1 <-> 2k
+            let flipped = std::array::from_fn(|i| if MASK[i] { group[i] ^ 1 } else { group[i] });
+            let shifted = std::array::from_fn(|i| {
+                if MASK[i] {
+                    ((group[i] + 1) ^ 1) - 1
+                } else {
+                    group[i]
+                }
+            });
+            for (k, after) in [smoothness(&flipped), smoothness(&shifted)]
+                .into_iter()
+                .enumerate()
+            {
+                if after > before {
+                    counts[2 * k] += 1;
+                } else if after < before {
+                    counts[2 * k + 1] += 1;
+                }
+            }
+            groups += 1;
+        }
+    }
+    if groups == 0 {
+        return None;
+    }
+    let fraction = |count: u32| count as f64 / groups as f64;
+    Some(GroupCounts {
+        regular: fraction(counts[0]),
+        singular: fraction(counts[1]),
+        negative_regular: fraction(counts[2]),
+        negative_singular: fraction(counts[3]),
+    })
+}
+
+/// Estimated share of samples whose lowest bit carries a message, from the RS statistics of the
+/// samples as they are and with every lowest bit flipped.
+fn rs_estimate(samples: &[u8], width: usize) -> Option<f64> {
+    let before = group_counts(samples, width, false)?;
+    let after = group_counts(samples, width, true)?;
+    // flat areas change under flipping in only one way and say nothing
+    if before.regular + before.singular < MIN_TEXTURED_GROUPS {
+        return None;
+    }
+    let d0 = before.regular - before.singular;
+    let d1 = after.regular - after.singular;
+    let n0 = before.negative_regular - before.negative_singular;
+    let n1 = after.negative_regular - after.negative_singular;
+
+    let a = 2.0 * (d1 + d0);
+    let b = n0 - n1 - d1 - 3.0 * d0;
+    let c = d0 - n0;
+    let z = if a.abs() < 1e-12 {
+        if b.abs() < 1e-12 {
+            return None;
+        }
+        -c / b
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let roots = [
+            (-b + discriminant.sqrt()) / (2.0 * a),
+            (-b - discriminant.sqrt()) / (2.0 * a),
+        ];
+        if roots[0].abs() <= roots[1].abs() {
+            roots[0]
+        } else {
+            roots[1]
+        }
+    };
+    if (z - 0.5).abs() < 1e-9 {
+        return None;
+    }
+    Some(z / (z - 0.5))
+}
+
+fn rs(image: &Image) -> Finding {
+    let channels = match sample_channels(image) {
+        Ok(channels) => channels,
+        Err(e) => return Finding::new(Heuristic::Rs, 0.0, vec![e]),
+    };
+
+    let mut score: f64 = 0.0;
+    let mut evidence = Vec::new();
+    for (channel, name) in channels {
+        match rs_estimate(&samples(image, channel), image.width() as usize) {
+            Some(rate) => {
+                score = score.max(rate);
+                evidence.push(format!(
+                    "{name}: about {}% of the samples carry a message",
+                    (rate.clamp(0.0, 1.0) * 100.0).round() as u32
+                ));
+            }
+            None => evidence.push(format!("{name}: no estimate, too few textured areas")),
+        }
+    }
+    Finding::new(Heuristic::Rs, score, evidence)
+}
+
+/// Encoders use one filter type throughout or pick per scanline by a heuristic. Filter types
+/// that are spread evenly and rarely the ones the adaptive heuristic picks may encode data.
+fn filters(png: &Png, image: Result<&Image, &ImageError>) -> Finding {
+    let types = match Image::filter_types(png) {
+        Ok(types) => types,
+        Err(e) => return Finding::new(Heuristic::Filters, 0.0, vec![format!("{e}")]),
+    };
+    if types.is_empty() {
+        return Finding::new(Heuristic::Filters, 0.0, vec![]);
+    }
+
+    let counts = FilterType::ALL.map(|f| types.iter().filter(|&&t| t == f).count());
+    let distribution = FilterType::ALL
+        .iter()
+        .zip(counts)
+        .filter(|(_, count)| *count > 0)
+        .map(|(f, count)| format!("{f:?} {count}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut evidence = vec![format!("filter types: {distribution}")];
+    if counts.iter().filter(|&&c| c > 0).count() == 1 {
+        return Finding::new(Heuristic::Filters, 0.0, evidence);
+    }
+
+    // evenness of the distribution, 1 when all five types are equally common
+    let total = types.len() as f64;
+    let evenness: f64 = counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / total;
+            -p * p.log2()
+        })
+        .sum::<f64>()
+        / 5f64.log2();
+
+    // the adaptive choice can only be compared scanline by scanline without interlacing
+    let mismatch = match image {
+        Ok(image) if types.len() == image.height() as usize => {
+            let adaptive = image.adaptive_filter_types();
+            let differing = types.iter().zip(&adaptive).filter(|(a, b)| a != b).count();
+            let mismatch = differing as f64 / total;
+            evidence.push(format!(
+                "{:.0}% of the scanlines differ from the adaptive choice",
+                mismatch * 100.0
+            ));
+            mismatch
+        }
+        Ok(_) => {
+            evidence.push("interlaced, the scanlines cannot be compared".to_string());
+            0.5
+        }
+        Err(e) => {
+            evidence.push(format!(
+                "not decodable, the scanlines cannot be compared: {e}"
+            ));
+            0.5
+        }
+    };
+    Finding::new(Heuristic::Filters, evenness * mismatch, evidence)
+}
@@ -0,0 +1,139 @@
+use super::*;
+use crate::{fixture::png, lsb::Lsb};
+use flate2::{write::ZlibEncoder, Compression};
+use png_spec::{chunk::Chunk, chunk_type::ChunkType, image::Header};
+use std::{io::Write, str::FromStr};
+
+/// A smooth image whose samples are all even, like an image scaled up from 7 bits.
+fn image() -> Image {
+    let header = Header::new(128, 96, 8, ColorType::Rgb).unwrap();
+    let data = (0..96u32)
+        .flat_map(|y| {
+            (0..128u32).flat_map(move |x| {
+                let base = x + y + (x * y / 32) % 7;
+                [base, base / 2 + 40, 255 - base / 2].map(|v| (v.min(255) & !1) as u8)
+            })
+        })
+        .collect();
+    Image::new(header, data).unwrap()
+}
+
+fn finding(png: &Png, heuristic: Heuristic) -> Finding {
+    detect(png)
+        .into_iter()
+        .find(|f| f.heuristic() == heuristic)
+        .unwrap()
+}
+
+/// Fills the lowest bit of every red, green and blue sample with random bits.
+fn embedded() -> Png {
+    let mut image = image();
+    let lsb = Lsb::new(b"key", Lsb::DEFAULT_CHANNELS.to_vec()).unwrap();
+    let capacity = lsb.capacity(&image).unwrap();
+    let mut state = 1u32;
+    let payload: Vec<u8> = (0..capacity)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        })
+        .collect();
+    lsb.embed(&mut image, &payload).unwrap();
+    png(&image)
+}
+
+#[test]
+fn test_clean_image() {
+    let png = png(&image());
+    for finding in detect(&png) {
+        assert!(finding.score() < 0.2, "{finding:?}");
+    }
+}
+
+#[test]
+fn test_chunks() {
+    let mut png = png(&image());
+    png.insert_before_end(Chunk::new(
+        ChunkType::from_str("ruSt").unwrap(),
+        b"hi".to_vec(),
+    ));
+    png.insert_before_end(Chunk::new(ChunkType::from_str("aBCd").unwrap(), vec![]));
+    let finding = finding(&png, Heuristic::Chunks);
+    assert_eq!(finding.score(), 0.9);
+    assert_eq!(
+        finding.evidence(),
+        [
+            "private chunk ruSt, 2 bytes",
+            "unknown public chunk aBCd, 0 bytes"
+        ]
+    );
+}
+
+#[test]
+fn test_entropy() {
+    let mut png = png(&image());
+    let text = b"A perfectly ordinary comment about the picture. ".repeat(8);
+    png.insert_before_end(Chunk::new(ChunkType::from_str("tEXt").unwrap(), text));
+    assert_eq!(finding(&png, Heuristic::Entropy).score(), 0.0);
+
+    let random: Vec<u8> = (0..4096u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+        .collect();
+    png.insert_before_end(Chunk::new(ChunkType::from_str("tEXt").unwrap(), random));
+    assert!(finding(&png, Heuristic::Entropy).score() > 0.9);
+}
+
+#[test]
+fn test_trailing_data() {
+    let png = png(&image());
+    let mut bytes = png.as_bytes();
+    bytes.extend(b"appended");
+    let png = Png::try_from(bytes.as_slice()).unwrap();
+    let finding = finding(&png, Heuristic::TrailingData);
+    assert_eq!(finding.score(), 1.0);
+    assert_eq!(finding.evidence(), ["8 bytes after IEND"]);
+}
+
+#[test]
+fn test_lsb_statistics() {
+    let png = embedded();
+    assert!(finding(&png, Heuristic::ChiSquare).score() > 0.9);
+    assert!(finding(&png, Heuristic::Rs).score() > 0.5);
+}
+
+#[test]
+fn test_gamma_p() {
+    // chi-square distribution function with 2 degrees of freedom is 1 - e^(-x/2)
+    for x in [0.5, 2.0, 10.0] {
+        assert!((gamma_p(1.0, x / 2.0) - (1.0 - (-x / 2.0).exp())).abs() < 1e-9);
+    }
+    // the median of 10 degrees of freedom is about 9.342
+    assert!((gamma_p(5.0, 9.342 / 2.0) - 0.5).abs() < 1e-3);
+}
+
+#[test]
+fn test_filters() {
+    let image = image();
+    let mut png = png(&image);
+    assert!(finding(&png, Heuristic::Filters).score() < 0.2);
+
+    // None, Sub and Up by turns, as if each filter type byte carried data
+    let row_bytes = image.header().row_bytes(image.width());
+    let mut raw = Vec::new();
+    let mut previous = vec![0; row_bytes];
+    for (y, row) in image.data().chunks_exact(row_bytes).enumerate() {
+        let filter = [FilterType::None, FilterType::Sub, FilterType::Up][(y * 7 + y / 3) % 3];
+        raw.push(u8::from(filter));
+        raw.extend(row.iter().enumerate().map(|(i, &b)| match filter {
+            FilterType::Sub if i >= 3 => b.wrapping_sub(row[i - 3]),
+            FilterType::Up => b.wrapping_sub(previous[i]),
+            _ => b,
+        }));
+        previous = row.to_vec();
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&raw).unwrap();
+    png.set_image_data(&encoder.finish().unwrap(), 1 << 16)
+        .unwrap();
+    assert_eq!(Image::from_png(&png).unwrap(), image);
+    assert!(finding(&png, Heuristic::Filters).score() > 0.4);
+}
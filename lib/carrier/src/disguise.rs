@@ -0,0 +1,183 @@
+//! Disguised payloads: the payload rides inside a standard chunk that keeps its ordinary
+//! meaning, so a validator finds nothing wrong and no unusual chunk type gives it away.
+//!
+//! - [`Disguise::Comment`] adds a zTXt `Comment` entry with a short harmless text. The payload is
+//!   in the padding bits of empty stored blocks at the start of its compressed text, as
+//!   [`crate::zlib`] does for image data, so the text inflates the same.
+//! - [`Disguise::Profile`] stores the payload in a private tag of the iCCP color profile, which
+//!   color managed viewers skip. An image without a profile gets an sRGB one, or a gray one for
+//!   grayscale images, replacing its sRGB chunk if it has one, as the two must not both be
+//!   present.
+//!
+//! Neither takes a key: anyone looking inside the chunk finds the payload as given, so it should
+//! already be encrypted.
+
+use crate::{zero_width, zlib, CarrierError};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use icc::Profile;
+use png_spec::{
+    chunk::Chunk,
+    chunk_type::ChunkType,
+    image::{Header, ImageError},
+    png::Png,
+};
+use std::{
+    io::{Read, Write},
+    str::FromStr,
+};
+
+mod icc;
+#[cfg(test)]
+mod tests;
+
+/// The private tag holding the payload in a profile.
+const PRIVATE_TAG: [u8; 4] = *b"prvt";
+
+/// Type signature, reserved bytes and the binary flag in front of the payload in its tag.
+const DATA_TYPE: [u8; 12] = *b"data\0\0\0\0\0\0\0\x01";
+
+/// Largest profile written or read, so a small chunk cannot inflate to exhaust memory.
+const MAX_PROFILE_LENGTH: usize = 1 << 26;
+
+/// Names of the profiles added to color and grayscale images without one.
+const PROFILE_NAME: &str = "sRGB";
+const GRAY_PROFILE_NAME: &str = "Gray sRGB";
+
+/// A standard chunk a payload can hide in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disguise {
+    /// A zTXt comment.
+    Comment,
+    /// A private tag in the iCCP color profile.
+    Profile,
+}
+
+impl Disguise {
+    /// The disguise used for payloads stored in `chunk_type` chunks, if it is a standard chunk
+    /// that can carry one.
+    pub fn for_chunk_type(chunk_type: &ChunkType) -> Option<Disguise> {
+        match &chunk_type.bytes() {
+            b"zTXt" => Some(Disguise::Comment),
+            b"iCCP" => Some(Disguise::Profile),
+            _ => None,
+        }
+    }
+
+    pub fn chunk_type(self) -> ChunkType {
+        let chunk_type = match self {
+            Disguise::Comment => "zTXt",
+            Disguise::Profile => "iCCP",
+        };
+        ChunkType::from_str(chunk_type).unwrap()
+    }
+
+    /// Hides `payload` in `png`. Comments are added next to any already there, while the
+    /// profile's payload replaces the one it held before.
+    pub fn embed(self, png: &mut Png, payload: &[u8]) -> Result<(), CarrierError> {
+        match self {
+            Disguise::Comment => {
+                let stream = zlib::hide(&compress(zero_width::COVER_TEXT.as_bytes()), payload)?;
+                let data = [
+                    zero_width::DEFAULT_KEYWORD.as_bytes(),
+                    &[0, 0],
+                    stream.as_slice(),
+                ]
+                .concat();
+                png.insert_before_end(Chunk::new(self.chunk_type(), data));
+            }
+            Disguise::Profile => {
+                let index = png
+                    .chunks()
+                    .iter()
+                    .position(|c| c.chunk_type() == &self.chunk_type());
+                let (name, mut profile) = match index.map(|i| &png.chunks()[i]) {
+                    Some(chunk) => {
+                        let (name, profile) = split_compressed(chunk.data())
+                            .and_then(|(name, stream)| Some((name, inflate(stream)?)))
+                            .ok_or_else(|| invalid_profile("the chunk is malformed"))?;
+                        let profile = Profile::parse(&profile).map_err(invalid_profile)?;
+                        (name.to_vec(), profile)
+                    }
+                    None => {
+                        let ihdr = png.chunks().first();
+                        let ihdr = ihdr.ok_or(ImageError::MissingChunk(ChunkType::IHDR))?;
+                        // an RGB profile is not valid for grayscale images
+                        if Header::try_from(ihdr)?.color_type().is_grayscale() {
+                            (GRAY_PROFILE_NAME.as_bytes().to_vec(), Profile::gray())
+                        } else {
+                            (PROFILE_NAME.as_bytes().to_vec(), Profile::srgb())
+                        }
+                    }
+                };
+                profile.set_tag(PRIVATE_TAG, [&DATA_TYPE, payload].concat());
+                let profile = profile.to_bytes();
+                if profile.len() > MAX_PROFILE_LENGTH {
+                    return Err(invalid_profile(format!(
+                        "the profile would be {} bytes, more than the {MAX_PROFILE_LENGTH} allowed",
+                        profile.len()
+                    )));
+                }
+                let data = [&name, &[0, 0][..], &compress(&profile)].concat();
+                let chunk = Chunk::new(self.chunk_type(), data);
+                match index {
+                    Some(index) => {
+                        png.replace_chunk(index, chunk);
+                    }
+                    None => {
+                        png.remove_chunks_where(|c| c.chunk_type().bytes() == *b"sRGB");
+                        // right after IHDR is before PLTE and IDAT, as iCCP must be
+                        png.insert_chunk(1, chunk);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The payload hidden in `chunk`, or `None` if it holds none or is not this disguise.
+    pub fn reveal(self, chunk: &Chunk) -> Option<Vec<u8>> {
+        if chunk.chunk_type() != &self.chunk_type() {
+            return None;
+        }
+        let (_, compressed) = split_compressed(chunk.data())?;
+        match self {
+            Disguise::Comment => zlib::reveal(compressed).ok(),
+            Disguise::Profile => {
+                let profile = Profile::parse(&inflate(compressed)?).ok()?;
+                let data = profile.tag(PRIVATE_TAG)?;
+                data.strip_prefix(&DATA_TYPE[..]).map(<[u8]>::to_vec)
+            }
+        }
+    }
+}
+
+fn invalid_profile(e: impl ToString) -> CarrierError {
+    CarrierError::Profile(e.to_string())
+}
+
+/// The keyword or name of zTXt and iCCP chunk data, and the zlib stream after it.
+fn split_compressed(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let end = data.iter().position(|&b| b == 0)?;
+    match &data[end + 1..] {
+        [0, stream @ ..] => Some((&data[..end], stream)),
+        _ => None,
+    }
+}
+
+fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder
+        .write_all(data)
+        .expect("compressing in memory cannot fail");
+    encoder.finish().expect("compressing in memory cannot fail")
+}
+
+/// The inflated `stream`, or `None` if it is invalid or inflates past [`MAX_PROFILE_LENGTH`].
+fn inflate(stream: &[u8]) -> Option<Vec<u8>> {
+    let mut inflated = Vec::new();
+    ZlibDecoder::new(stream)
+        .take(MAX_PROFILE_LENGTH as u64 + 1)
+        .read_to_end(&mut inflated)
+        .ok()?;
+    (inflated.len() <= MAX_PROFILE_LENGTH).then_some(inflated)
+}
@@ -0,0 +1,222 @@
+//! Just enough of the ICC profile format to add a tag to any profile, and to make an sRGB or a
+//! gray one.
+//!
+//! A profile is a 128 byte header, a tag count, a table of 12 byte tag entries (signature,
+//! offset and size) and the tag data, each element starting on a 4 byte boundary. Tags may
+//! share their data, which is kept shared when the profile is written again.
+//!
+//! ['ICC.1:2001-04'](https://www.color.org/ICC_Minor_Revision_for_Web.pdf)
+
+use std::ops::Range;
+
+const HEADER_LENGTH: usize = 128;
+const ENTRY_LENGTH: usize = 12;
+
+/// Where the header has the `acsp` file signature.
+const FILE_SIGNATURE: Range<usize> = 36..40;
+
+/// Where version 4 headers have an MD5 of the profile. Zero means it was not computed.
+const PROFILE_ID: Range<usize> = 84..100;
+
+/// The PCS illuminant, D50, as used for the header and the white point.
+const D50: [f64; 3] = [0.9642, 1.0, 0.8249];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    header: Vec<u8>,
+    /// The signature of every tag with the index of its data in `elements`.
+    tags: Vec<([u8; 4], usize)>,
+    elements: Vec<Vec<u8>>,
+}
+
+impl Profile {
+    pub fn parse(bytes: &[u8]) -> Result<Profile, String> {
+        let read = |at: usize| {
+            bytes
+                .get(at..at + 4)
+                .map(|b| u32::from_be_bytes(b.try_into().unwrap()) as usize)
+        };
+        let size = read(0).ok_or("the profile is too short")?;
+        if size > bytes.len() || size < HEADER_LENGTH + 4 {
+            return Err(format!(
+                "the header says {size} bytes but there are {}",
+                bytes.len()
+            ));
+        }
+        let bytes = &bytes[..size];
+        if bytes[FILE_SIGNATURE] != *b"acsp" {
+            return Err("no acsp signature".to_string());
+        }
+
+        let count = read(HEADER_LENGTH).unwrap();
+        if HEADER_LENGTH + 4 + count.saturating_mul(ENTRY_LENGTH) > size {
+            return Err(format!("{count} tags do not fit in the tag table"));
+        }
+        let mut profile = Profile {
+            header: bytes[..HEADER_LENGTH].to_vec(),
+            tags: Vec::with_capacity(count),
+            elements: Vec::new(),
+        };
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for i in 0..count {
+            let entry = HEADER_LENGTH + 4 + i * ENTRY_LENGTH;
+            let signature: [u8; 4] = bytes[entry..entry + 4].try_into().unwrap();
+            let (offset, length) = (read(entry + 4).unwrap(), read(entry + 8).unwrap());
+            let range = offset..offset.saturating_add(length);
+            if range.end > size {
+                return Err(format!(
+                    "tag {} is outside the profile",
+                    String::from_utf8_lossy(&signature)
+                ));
+            }
+            let element = match ranges.iter().position(|r| *r == range) {
+                Some(element) => element,
+                None => {
+                    profile.elements.push(bytes[range.clone()].to_vec());
+                    ranges.push(range);
+                    ranges.len() - 1
+                }
+            };
+            profile.tags.push((signature, element));
+        }
+        Ok(profile)
+    }
+
+    /// A version 2 display profile for sRGB, with the colorants adapted to D50 and the full
+    /// sRGB tone curve.
+    pub fn srgb() -> Profile {
+        let mut profile = Profile::display(*b"RGB ", "sRGB");
+        profile.set_tag(*b"rXYZ", xyz([0.43607, 0.22249, 0.01392]));
+        profile.set_tag(*b"gXYZ", xyz([0.38515, 0.71687, 0.09708]));
+        profile.set_tag(*b"bXYZ", xyz([0.14307, 0.06061, 0.71410]));
+        profile.set_tag(*b"rTRC", srgb_curve());
+        profile.tags.push((*b"gTRC", profile.elements.len() - 1));
+        profile.tags.push((*b"bTRC", profile.elements.len() - 1));
+        profile
+    }
+
+    /// A version 2 display profile for gray with the sRGB tone curve, as sRGB images look
+    /// when all three channels are equal.
+    pub fn gray() -> Profile {
+        let mut profile = Profile::display(*b"GRAY", "Gray sRGB");
+        profile.set_tag(*b"kTRC", srgb_curve());
+        profile
+    }
+
+    /// A display profile for `color_space` with the tags every profile needs, and no others.
+    fn display(color_space: [u8; 4], description: &str) -> Profile {
+        let mut header = vec![0; HEADER_LENGTH];
+        header[8..12].copy_from_slice(&[2, 0x10, 0, 0]);
+        header[12..16].copy_from_slice(b"mntr");
+        header[16..20].copy_from_slice(&color_space);
+        header[20..24].copy_from_slice(b"XYZ ");
+        for (i, field) in [2020u16, 1, 1, 0, 0, 0].into_iter().enumerate() {
+            header[24 + 2 * i..26 + 2 * i].copy_from_slice(&field.to_be_bytes());
+        }
+        header[FILE_SIGNATURE].copy_from_slice(b"acsp");
+        header[68..80].copy_from_slice(&xyz_numbers(D50));
+
+        let mut profile = Profile {
+            header,
+            tags: Vec::new(),
+            elements: Vec::new(),
+        };
+        profile.set_tag(*b"desc", text_description(description));
+        profile.set_tag(*b"cprt", [&b"text\0\0\0\0No copyright"[..], &[0]].concat());
+        profile.set_tag(*b"wtpt", xyz(D50));
+        profile
+    }
+
+    /// Data of the tag with `signature`, type signature included.
+    pub fn tag(&self, signature: [u8; 4]) -> Option<&[u8]> {
+        self.tags
+            .iter()
+            .find(|(s, _)| *s == signature)
+            .map(|&(_, element)| self.elements[element].as_slice())
+    }
+
+    /// Adds a tag, or replaces the data of the tag with the same signature.
+    pub fn set_tag(&mut self, signature: [u8; 4], data: Vec<u8>) {
+        self.tags.retain(|(s, _)| *s != signature);
+        let used: Vec<bool> = (0..self.elements.len())
+            .map(|i| self.tags.iter().any(|&(_, element)| element == i))
+            .collect();
+        if let Some(unused) = used.iter().position(|&used| !used) {
+            self.elements.remove(unused);
+            for (_, element) in &mut self.tags {
+                if *element > unused {
+                    *element -= 1;
+                }
+            }
+        }
+        self.elements.push(data);
+        self.tags.push((signature, self.elements.len() - 1));
+    }
+
+    /// The profile with its size updated and, as the contents changed, no profile ID.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.header.clone();
+        bytes[PROFILE_ID].fill(0);
+        bytes.extend((self.tags.len() as u32).to_be_bytes());
+
+        let mut offset = bytes.len() + self.tags.len() * ENTRY_LENGTH;
+        let mut data = Vec::new();
+        let mut offsets = Vec::with_capacity(self.elements.len());
+        for element in &self.elements {
+            offsets.push(offset);
+            data.extend(element);
+            data.resize(data.len().next_multiple_of(4), 0);
+            offset = bytes.len() + self.tags.len() * ENTRY_LENGTH + data.len();
+        }
+        for &(signature, element) in &self.tags {
+            bytes.extend(signature);
+            bytes.extend((offsets[element] as u32).to_be_bytes());
+            bytes.extend((self.elements[element].len() as u32).to_be_bytes());
+        }
+        bytes.extend(data);
+
+        let size = bytes.len() as u32;
+        bytes[..4].copy_from_slice(&size.to_be_bytes());
+        bytes
+    }
+}
+
+/// The sRGB tone curve as a table of 1024 entries.
+fn srgb_curve() -> Vec<u8> {
+    let curve: Vec<u8> = (0..1024)
+        .flat_map(|i| {
+            let x = i as f64 / 1023.0;
+            let y = if x <= 0.04045 {
+                x / 12.92
+            } else {
+                ((x + 0.055) / 1.055).powf(2.4)
+            };
+            ((y * 65535.0).round() as u16).to_be_bytes()
+        })
+        .collect();
+    [&b"curv\0\0\0\0"[..], &1024u32.to_be_bytes(), &curve].concat()
+}
+
+/// Three s15Fixed16 numbers.
+fn xyz_numbers(xyz: [f64; 3]) -> Vec<u8> {
+    xyz.iter()
+        .flat_map(|v| ((v * 65536.0).round() as i32).to_be_bytes())
+        .collect()
+}
+
+fn xyz(value: [f64; 3]) -> Vec<u8> {
+    [&b"XYZ \0\0\0\0"[..], &xyz_numbers(value)].concat()
+}
+
+/// A version 2 `desc` tag with only the ASCII description.
+pub fn text_description(text: &str) -> Vec<u8> {
+    let mut data = b"desc\0\0\0\0".to_vec();
+    data.extend((text.len() as u32 + 1).to_be_bytes());
+    data.extend(text.as_bytes());
+    data.push(0);
+    // empty Unicode and ScriptCode descriptions, the latter a fixed 67 byte field
+    data.extend([0; 8]);
+    data.extend([0; 3]);
+    data.extend([0; 67]);
+    data
+}
@@ -0,0 +1,162 @@
+use super::*;
+use crate::fixture;
+use png_spec::image::{ColorType, Header, Image};
+
+fn png() -> Png {
+    let header = Header::new(8, 8, 8, ColorType::Rgb).unwrap();
+    let data = (0..8 * 8 * 3).map(|i| (i * 7) as u8).collect();
+    let mut png = fixture::png(&Image::new(header, data).unwrap());
+    png.insert_chunk(1, Chunk::new(ChunkType::from_str("sRGB").unwrap(), vec![0]));
+    png
+}
+
+fn chunks_of(png: &Png, disguise: Disguise) -> Vec<&Chunk> {
+    png.chunks()
+        .iter()
+        .filter(|c| c.chunk_type() == &disguise.chunk_type())
+        .collect()
+}
+
+fn profile_of(chunk: &Chunk) -> (Vec<u8>, Vec<u8>) {
+    let (name, stream) = split_compressed(chunk.data()).unwrap();
+    (name.to_vec(), inflate(stream).unwrap())
+}
+
+#[test]
+fn test_comment() {
+    let mut png = png();
+    Disguise::Comment
+        .embed(&mut png, b"in the comment")
+        .unwrap();
+
+    let chunks = chunks_of(&png, Disguise::Comment);
+    assert_eq!(chunks.len(), 1);
+    let (keyword, stream) = split_compressed(chunks[0].data()).unwrap();
+    assert_eq!(keyword, b"Comment");
+    assert_eq!(inflate(stream).unwrap(), zero_width::COVER_TEXT.as_bytes());
+    assert_eq!(
+        Disguise::Comment.reveal(chunks[0]).unwrap(),
+        b"in the comment"
+    );
+    assert_eq!(Disguise::Profile.reveal(chunks[0]), None);
+}
+
+#[test]
+fn test_plain_comment() {
+    let data = [&b"Comment\0\0"[..], &compress(b"just a comment")].concat();
+    let chunk = Chunk::new(Disguise::Comment.chunk_type(), data);
+    assert_eq!(Disguise::Comment.reveal(&chunk), None);
+}
+
+#[test]
+fn test_new_profile() {
+    let mut png = png();
+    Disguise::Profile
+        .embed(&mut png, b"in the profile")
+        .unwrap();
+
+    assert!(png.chunk_by_type("sRGB").is_none());
+    assert_eq!(
+        png.chunks()[1].chunk_type(),
+        &Disguise::Profile.chunk_type()
+    );
+    let (name, bytes) = profile_of(&png.chunks()[1]);
+    assert_eq!(name, PROFILE_NAME.as_bytes());
+
+    // the size is right and every tag is in bounds and aligned
+    assert_eq!(
+        u32::from_be_bytes(bytes[..4].try_into().unwrap()),
+        bytes.len() as u32
+    );
+    let count = u32::from_be_bytes(bytes[128..132].try_into().unwrap()) as usize;
+    assert_eq!(count, 10);
+    for entry in bytes[132..132 + count * 12].chunks(12) {
+        let offset = u32::from_be_bytes(entry[4..8].try_into().unwrap()) as usize;
+        let size = u32::from_be_bytes(entry[8..12].try_into().unwrap()) as usize;
+        assert!(offset.is_multiple_of(4));
+        assert!(offset + size <= bytes.len());
+    }
+
+    let profile = Profile::parse(&bytes).unwrap();
+    assert_eq!(profile.tag(*b"rTRC"), profile.tag(*b"bTRC"));
+    assert_eq!(profile.tag(*b"rTRC").unwrap().len(), 12 + 2048);
+    assert_eq!(
+        Disguise::Profile.reveal(&png.chunks()[1]).unwrap(),
+        b"in the profile"
+    );
+}
+
+#[test]
+fn test_existing_profile() {
+    let mut profile = Profile::srgb();
+    profile.set_tag(*b"desc", icc::text_description("Display"));
+    let data = [&b"Display\0\0"[..], &compress(&profile.to_bytes())].concat();
+    let mut png = png();
+    png.replace_chunk(1, Chunk::new(Disguise::Profile.chunk_type(), data));
+
+    Disguise::Profile.embed(&mut png, b"first").unwrap();
+    Disguise::Profile.embed(&mut png, b"second").unwrap();
+    let chunks = chunks_of(&png, Disguise::Profile);
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(Disguise::Profile.reveal(chunks[0]).unwrap(), b"second");
+
+    let (name, bytes) = profile_of(chunks[0]);
+    assert_eq!(name, b"Display");
+    let mut expected = profile;
+    expected.set_tag(PRIVATE_TAG, [&DATA_TYPE, &b"second"[..]].concat());
+    assert_eq!(bytes, expected.to_bytes());
+}
+
+#[test]
+fn test_invalid_profile() {
+    let data = [&b"Broken\0\0"[..], &compress(&[0; 200])].concat();
+    let mut png = png();
+    png.replace_chunk(1, Chunk::new(Disguise::Profile.chunk_type(), data));
+    assert_eq!(Disguise::Profile.reveal(&png.chunks()[1]), None);
+    assert!(matches!(
+        Disguise::Profile.embed(&mut png, b"payload"),
+        Err(CarrierError::Profile(_))
+    ));
+}
+
+#[test]
+fn test_for_chunk_type() {
+    for disguise in [Disguise::Comment, Disguise::Profile] {
+        assert_eq!(
+            Disguise::for_chunk_type(&disguise.chunk_type()),
+            Some(disguise)
+        );
+    }
+    assert_eq!(
+        Disguise::for_chunk_type(&ChunkType::from_str("RuSt").unwrap()),
+        None
+    );
+}
+
+#[test]
+fn test_gray_profile() {
+    for color_type in [ColorType::Grayscale, ColorType::GrayscaleAlpha] {
+        let header = Header::new(8, 8, 8, color_type).unwrap();
+        let data = vec![0x80; header.data_length().unwrap()];
+        let mut png = fixture::png(&Image::new(header, data).unwrap());
+        Disguise::Profile.embed(&mut png, b"gray").unwrap();
+
+        let (name, bytes) = profile_of(&png.chunks()[1]);
+        assert_eq!(name, GRAY_PROFILE_NAME.as_bytes());
+        assert_eq!(bytes[16..20], *b"GRAY");
+        let profile = Profile::parse(&bytes).unwrap();
+        assert!(profile.tag(*b"kTRC").is_some());
+        assert!(profile.tag(*b"rXYZ").is_none());
+        assert_eq!(Disguise::Profile.reveal(&png.chunks()[1]).unwrap(), b"gray");
+        assert!(Image::from_png(&png).is_ok());
+    }
+}
+
+#[test]
+fn test_inflate_limit() {
+    let bomb = compress(&vec![0; MAX_PROFILE_LENGTH + 1]);
+    assert_eq!(inflate(&bomb), None);
+    let data = [&b"Bomb\0\0"[..], &bomb].concat();
+    let chunk = Chunk::new(Disguise::Profile.chunk_type(), data);
+    assert_eq!(Disguise::Profile.reveal(&chunk), None);
+}
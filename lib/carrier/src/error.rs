@@ -0,0 +1,103 @@
+use png_spec::{
+    image::{ColorType, ImageError},
+    text::TextError,
+};
+use std::{error, fmt};
+
+#[derive(Debug)]
+pub enum CarrierError {
+    Image(ImageError),
+    Text(TextError),
+    /// The carrier cannot use palette images; they must be converted first.
+    Palette,
+    /// A tRNS color key makes one color transparent, so changing the pixels could show.
+    ColorKey,
+    /// Samples of fewer than 8 bits are too coarse to change unnoticed.
+    BitDepth(u8),
+    /// A channel is not part of the image's color type.
+    MissingChannel(String, ColorType),
+    /// The channel selection is empty, repeats a channel or uses too many bits.
+    Channels(String),
+    /// The payload does not fit, in bytes.
+    Capacity {
+        needed: usize,
+        available: usize,
+    },
+    /// The image cannot carry a payload in its palette order.
+    PaletteOrder(String),
+    /// The image data is not a zlib stream the carrier can use.
+    Stream(String),
+    /// The color profile cannot be read or changed.
+    Profile(String),
+    /// Nothing was found, or the key is wrong.
+    NoPayload,
+    /// Invalid error correction settings or data.
+    Fec(String),
+    /// A block of error corrected data has more errors than its parity can correct.
+    Uncorrectable {
+        block: usize,
+    },
+}
+
+impl fmt::Display for CarrierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CarrierError::Image(e) => write!(f, "{e}"),
+            CarrierError::Text(e) => write!(f, "{e}"),
+            CarrierError::Palette => write!(
+                f,
+                "palette images are not supported, convert the image to RGB or RGBA first"
+            ),
+            CarrierError::ColorKey => write!(
+                f,
+                "images with a transparent color key are not supported, changed pixels could \
+                 turn transparent or opaque"
+            ),
+            CarrierError::BitDepth(depth) => {
+                write!(
+                    f,
+                    "bit depth {depth} is not supported, 8 or 16 bits are needed"
+                )
+            }
+            CarrierError::MissingChannel(channel, color_type) => {
+                write!(f, "{color_type:?} images have no {channel} channel")
+            }
+            CarrierError::Channels(e) => write!(f, "invalid channels: {e}"),
+            CarrierError::Capacity { needed, available } => write!(
+                f,
+                "payload needs {needed} bytes but only {available} bytes are available"
+            ),
+            CarrierError::PaletteOrder(e) => write!(f, "cannot use the palette order: {e}"),
+            CarrierError::Stream(e) => write!(f, "image data stream: {e}"),
+            CarrierError::Profile(e) => write!(f, "invalid color profile: {e}"),
+            CarrierError::NoPayload => write!(f, "no payload found, or the key is wrong"),
+            CarrierError::Fec(e) => write!(f, "error correction: {e}"),
+            CarrierError::Uncorrectable { block } => write!(
+                f,
+                "block {block} has more errors than the error correction can repair"
+            ),
+        }
+    }
+}
+
+impl error::Error for CarrierError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CarrierError::Image(e) => Some(e),
+            CarrierError::Text(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ImageError> for CarrierError {
+    fn from(e: ImageError) -> Self {
+        CarrierError::Image(e)
+    }
+}
+
+impl From<TextError> for CarrierError {
+    fn from(e: TextError) -> Self {
+        CarrierError::Text(e)
+    }
+}
@@ -0,0 +1,228 @@
+//! Reed–Solomon forward error correction over GF(2^8), so a payload survives a few damaged bytes.
+//!
+//! Data is cut into blocks of up to `255 - parity` bytes and each block gets `parity` check
+//! bytes, correcting up to `parity / 2` wrong bytes anywhere in the block. The last block is
+//! shortened instead of padded. The field uses the polynomial x^8 + x^4 + x^3 + x^2 + 1 (0x11d)
+//! and the generator has the roots 2^0 to 2^(parity - 1).
+
+use crate::CarrierError;
+use gf256::{div, mul, pow2};
+
+#[cfg(test)]
+mod tests;
+
+/// Longest block, data and parity together.
+const BLOCK_LENGTH: usize = 255;
+
+// Polynomials are stored highest degree first.
+
+fn poly_mul(p: &[u8], q: &[u8]) -> Vec<u8> {
+    let mut product = vec![0; p.len() + q.len() - 1];
+    for (i, &a) in p.iter().enumerate() {
+        for (j, &b) in q.iter().enumerate() {
+            product[i + j] ^= mul(a, b);
+        }
+    }
+    product
+}
+
+fn poly_add(p: &[u8], q: &[u8]) -> Vec<u8> {
+    let mut sum = vec![0; p.len().max(q.len())];
+    let length = sum.len();
+    for (i, &a) in p.iter().enumerate() {
+        sum[i + length - p.len()] = a;
+    }
+    for (i, &b) in q.iter().enumerate() {
+        sum[i + length - q.len()] ^= b;
+    }
+    sum
+}
+
+fn poly_scale(p: &[u8], x: u8) -> Vec<u8> {
+    p.iter().map(|&a| mul(a, x)).collect()
+}
+
+fn poly_eval(p: &[u8], x: u8) -> u8 {
+    p.iter().fold(0, |y, &a| mul(y, x) ^ a)
+}
+
+/// Reed–Solomon coding with a fixed number of parity bytes per block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReedSolomon {
+    generator: Vec<u8>,
+}
+
+impl ReedSolomon {
+    /// Fewest and most parity bytes per block.
+    pub const PARITY: std::ops::RangeInclusive<u8> = 2..=128;
+
+    pub fn new(parity: u8) -> Result<ReedSolomon, CarrierError> {
+        if !Self::PARITY.contains(&parity) {
+            return Err(CarrierError::Fec(format!(
+                "{parity} parity bytes, expected {} to {}",
+                Self::PARITY.start(),
+                Self::PARITY.end()
+            )));
+        }
+        let generator = (0..parity as usize).fold(vec![1], |g, i| poly_mul(&g, &[1, pow2(i)]));
+        Ok(ReedSolomon { generator })
+    }
+
+    /// Parity bytes per block.
+    pub fn parity(&self) -> usize {
+        self.generator.len() - 1
+    }
+
+    /// Wrong bytes each block can correct.
+    pub fn correctable(&self) -> usize {
+        self.parity() / 2
+    }
+
+    /// Length of `length` bytes of data once encoded.
+    pub fn encoded_length(&self, length: usize) -> usize {
+        let data = BLOCK_LENGTH - self.parity();
+        length + length.div_ceil(data) * self.parity()
+    }
+
+    /// Most data that encodes to at most `length` bytes.
+    pub fn data_capacity(&self, length: usize) -> usize {
+        let data = BLOCK_LENGTH - self.parity();
+        let last = length % BLOCK_LENGTH;
+        length / BLOCK_LENGTH * data + last.saturating_sub(self.parity())
+    }
+
+    /// Appends parity bytes to every block of `data`.
+    pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(self.encoded_length(data.len()));
+        for block in data.chunks(BLOCK_LENGTH - self.parity()) {
+            encoded.extend(block);
+            encoded.extend(self.parity_of(block));
+        }
+        encoded
+    }
+
+    /// Remainder of the block shifted by the parity length, divided by the generator.
+    fn parity_of(&self, block: &[u8]) -> Vec<u8> {
+        let mut remainder = block.to_vec();
+        remainder.resize(block.len() + self.parity(), 0);
+        for i in 0..block.len() {
+            let coefficient = remainder[i];
+            if coefficient != 0 {
+                for (j, &g) in self.generator.iter().enumerate().skip(1) {
+                    remainder[i + j] ^= mul(g, coefficient);
+                }
+            }
+        }
+        remainder.split_off(block.len())
+    }
+
+    /// Corrects and strips the parity of data made by [`ReedSolomon::encode`], returning the
+    /// data and how many bytes were corrected.
+    pub fn decode(&self, encoded: &[u8]) -> Result<(Vec<u8>, usize), CarrierError> {
+        let mut data = Vec::with_capacity(self.data_capacity(encoded.len()));
+        let mut corrected = 0;
+        for (i, block) in encoded.chunks(BLOCK_LENGTH).enumerate() {
+            if block.len() <= self.parity() {
+                return Err(CarrierError::Fec(format!(
+                    "block {} is too short, {} bytes",
+                    i + 1,
+                    block.len()
+                )));
+            }
+            let mut block = block.to_vec();
+            corrected += self
+                .correct(&mut block)
+                .ok_or(CarrierError::Uncorrectable { block: i + 1 })?;
+            block.truncate(block.len() - self.parity());
+            data.extend(block);
+        }
+        Ok((data, corrected))
+    }
+
+    /// Corrects `block` in place, returning the number of corrected bytes, or `None` if it has
+    /// too many errors.
+    fn correct(&self, block: &mut [u8]) -> Option<usize> {
+        // a 0 in front makes the indices below line up with the textbook algorithms
+        let syndromes: Vec<u8> = std::iter::once(0)
+            .chain((0..self.parity()).map(|i| poly_eval(block, pow2(i))))
+            .collect();
+        if syndromes.iter().all(|&s| s == 0) {
+            return Some(0);
+        }
+
+        let locator = self.error_locator(&syndromes)?;
+        let errors = locator.len() - 1;
+        if errors * 2 > self.parity() {
+            return None;
+        }
+
+        // Chien search: the roots of the locator give the error positions
+        let reversed: Vec<u8> = locator.iter().rev().copied().collect();
+        let positions: Vec<usize> = (0..block.len())
+            .filter(|&i| poly_eval(&reversed, pow2(i)) == 0)
+            .map(|i| block.len() - 1 - i)
+            .collect();
+        if positions.len() != errors {
+            return None;
+        }
+
+        self.forney(block, &syndromes, &positions)?;
+        // a block that still has errors was damaged beyond what the parity can tell apart
+        if (0..self.parity()).any(|i| poly_eval(block, pow2(i)) != 0) {
+            return None;
+        }
+        Some(errors)
+    }
+
+    /// Berlekamp–Massey: the shortest polynomial whose roots locate the errors.
+    fn error_locator(&self, syndromes: &[u8]) -> Option<Vec<u8>> {
+        let mut locator: Vec<u8> = vec![1];
+        let mut old = vec![1];
+        for k in 1..=self.parity() {
+            let mut delta = syndromes[k];
+            for j in 1..locator.len() {
+                delta ^= mul(locator[locator.len() - 1 - j], syndromes[k - j]);
+            }
+            old.push(0);
+            if delta != 0 {
+                if old.len() > locator.len() {
+                    let new = poly_scale(&old, delta);
+                    old = poly_scale(&locator, div(1, delta));
+                    locator = new;
+                }
+                locator = poly_add(&locator, &poly_scale(&old, delta));
+            }
+        }
+        let leading = locator.iter().take_while(|&&c| c == 0).count();
+        let locator = locator.split_off(leading);
+        (!locator.is_empty()).then_some(locator)
+    }
+
+    /// Forney: computes the error values at `positions` and removes them from `block`.
+    fn forney(&self, block: &mut [u8], syndromes: &[u8], positions: &[usize]) -> Option<()> {
+        let exponents: Vec<usize> = positions.iter().map(|&p| block.len() - 1 - p).collect();
+        let locator = exponents.iter().fold(vec![1], |l, &e| {
+            poly_mul(&l, &poly_add(&[1], &[pow2(e), 0]))
+        });
+
+        let reversed: Vec<u8> = syndromes.iter().rev().copied().collect();
+        let product = poly_mul(&reversed, &locator);
+        let evaluator = &product[product.len() - locator.len()..];
+
+        let roots: Vec<u8> = exponents.iter().map(|&e| pow2(e)).collect();
+        for (i, (&x, &position)) in roots.iter().zip(positions).enumerate() {
+            let x_inverse = div(1, x);
+            let derivative = roots
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .fold(1, |d, (_, &other)| mul(d, 1 ^ mul(x_inverse, other)));
+            if derivative == 0 {
+                return None;
+            }
+            let y = mul(x, poly_eval(evaluator, x_inverse));
+            block[position] ^= div(y, derivative);
+        }
+        Some(())
+    }
+}
@@ -0,0 +1,91 @@
+use super::*;
+use rand_chacha::{
+    rand_core::{RngCore, SeedableRng},
+    ChaCha20Rng,
+};
+
+fn data(length: usize) -> Vec<u8> {
+    (0..length).map(|i| (i * 7 + 3) as u8).collect()
+}
+
+/// Flips one random bit in each of `count` distinct random bytes of every block.
+fn damage(encoded: &mut [u8], count: usize, rng: &mut impl RngCore) {
+    for block in encoded.chunks_mut(BLOCK_LENGTH) {
+        let mut positions: Vec<usize> = (0..block.len()).collect();
+        crate::shuffle(&mut positions, rng);
+        for &position in &positions[..count] {
+            block[position] ^= 1 << (rng.next_u32() % 8);
+        }
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    let rs = ReedSolomon::new(16).unwrap();
+    for length in [0, 1, 238, 239, 240, 1000] {
+        let encoded = rs.encode(&data(length));
+        assert_eq!(encoded.len(), rs.encoded_length(length));
+        assert_eq!(rs.decode(&encoded).unwrap(), (data(length), 0));
+    }
+}
+
+#[test]
+fn test_data_capacity() {
+    let rs = ReedSolomon::new(16).unwrap();
+    for length in 0..800 {
+        let capacity = rs.data_capacity(length);
+        assert!(rs.encoded_length(capacity) <= length);
+        assert!(rs.encoded_length(capacity + 1) > length);
+    }
+}
+
+#[test]
+fn test_corrects_up_to_limit() {
+    let mut rng = ChaCha20Rng::seed_from_u64(40);
+    for parity in [2, 4, 16, 32, 128] {
+        let rs = ReedSolomon::new(parity).unwrap();
+        let data = data(700);
+        for errors in 0..=rs.correctable() {
+            let mut encoded = rs.encode(&data);
+            damage(&mut encoded, errors, &mut rng);
+            let blocks = encoded.len().div_ceil(BLOCK_LENGTH);
+            assert_eq!(
+                rs.decode(&encoded).unwrap(),
+                (data.clone(), errors * blocks),
+                "{errors} errors with {parity} parity bytes"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_errors_in_parity() {
+    let rs = ReedSolomon::new(8).unwrap();
+    let mut encoded = rs.encode(b"hello");
+    let length = encoded.len();
+    encoded[length - 1] ^= 0xff;
+    encoded[length - 8] ^= 0x01;
+    assert_eq!(rs.decode(&encoded).unwrap(), (b"hello".to_vec(), 2));
+}
+
+#[test]
+fn test_beyond_limit() {
+    let mut rng = ChaCha20Rng::seed_from_u64(41);
+    let rs = ReedSolomon::new(16).unwrap();
+    let mut encoded = rs.encode(&data(100));
+    damage(&mut encoded, 20, &mut rng);
+    assert!(matches!(
+        rs.decode(&encoded),
+        Err(CarrierError::Uncorrectable { block: 1 })
+    ));
+}
+
+#[test]
+fn test_invalid_parity() {
+    assert!(ReedSolomon::new(1).is_err());
+    assert!(ReedSolomon::new(129).is_err());
+    assert!(matches!(
+        ReedSolomon::new(4).unwrap().decode(&[0; 3]),
+        Err(CarrierError::Fec(_))
+    ));
+}
@@ -0,0 +1,85 @@
+//! Scanline filter embedding: the payload becomes the filter type of every scanline, one of the
+//! five types of filter method 0. The pixels are filtered again to match, so they decode the same.
+//!
+//! The filter types read as base 5 digits in blocks of [`BLOCK_ROWS`] scanlines, the last block
+//! possibly shorter. Each block is one number, its first scanline most significant, holding
+//! `log2(5^rows)` bits rounded down to whole bytes, and the bytes of every block in turn hold the
+//! framed payload. Blocks keep converting between bytes and digits linear in the height. The
+//! bytes after the payload are noise derived from it. The filters no longer follow the adaptive
+//! heuristic, which [`detect`](crate::detect) looks for, and the compressed image data usually
+//! grows a little. Interlaced images are written without interlacing.
+
+use crate::{
+    from_digits, padded_frame, payload_capacity, radix_bytes, to_digits, unframe, CarrierError,
+};
+use png_spec::{
+    image::{EncodeOptions, FilterType, Image},
+    png::Png,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Keeps the padding noise apart from other uses of the payload bytes.
+const DOMAIN: &str = "png-message filters v1";
+
+/// Choices for every scanline.
+const RADIX: u32 = FilterType::ALL.len() as u32;
+
+/// Scanlines read as one number. Each full block holds 297 bytes, wasting under 2 bits.
+pub const BLOCK_ROWS: usize = 1024;
+
+/// Largest payload in bytes that fits in the scanline filters of `image`.
+pub fn capacity(image: &Image) -> Result<usize, CarrierError> {
+    Ok(payload_capacity(bytes(image.height() as usize), None))
+}
+
+/// Hides `payload` in the scanline filters of the image in `png`.
+pub fn embed(png: &mut Png, payload: &[u8]) -> Result<(), CarrierError> {
+    let image = Image::from_png(png)?;
+    let rows = image.height() as usize;
+    let number = padded_frame(DOMAIN, payload, bytes(rows))?;
+
+    let mut filters = Vec::with_capacity(rows);
+    let mut rest = number.as_slice();
+    for block in blocks(rows) {
+        let (number, after) = rest.split_at(block_bytes(block));
+        rest = after;
+        let digits = to_digits(number.to_vec(), &vec![RADIX; block]);
+        filters.extend(digits.into_iter().map(|d| FilterType::ALL[d as usize]));
+    }
+    image.write_filtered_to(png, &filters, &EncodeOptions::default())?;
+    Ok(())
+}
+
+/// Reads back a payload hidden in the scanline filters of `png`.
+pub fn extract(png: &Png) -> Result<Vec<u8>, CarrierError> {
+    let filters = Image::filter_types(png)?;
+    let mut number = Vec::with_capacity(bytes(filters.len()));
+    let mut rest = filters.as_slice();
+    for block in blocks(filters.len()) {
+        let (filters, after) = rest.split_at(block);
+        rest = after;
+        let digits = filters.iter().map(|&f| (RADIX, u8::from(f) as u32));
+        // filters past the largest number of whole bytes never come from embedding
+        let block = from_digits(digits, block_bytes(block)).ok_or(CarrierError::NoPayload)?;
+        number.extend(block);
+    }
+    unframe(&number)
+}
+
+/// Scanlines in every block of an image `rows` scanlines tall.
+fn blocks(rows: usize) -> impl Iterator<Item = usize> {
+    let full = std::iter::repeat_n(BLOCK_ROWS, rows / BLOCK_ROWS);
+    full.chain(Some(rows % BLOCK_ROWS).filter(|&rest| rest > 0))
+}
+
+/// Whole bytes a block of `rows` scanlines holds.
+fn block_bytes(rows: usize) -> usize {
+    radix_bytes(std::iter::repeat_n(RADIX, rows))
+}
+
+/// Whole bytes the filters of `rows` scanlines hold.
+fn bytes(rows: usize) -> usize {
+    rows / BLOCK_ROWS * block_bytes(BLOCK_ROWS) + block_bytes(rows % BLOCK_ROWS)
+}
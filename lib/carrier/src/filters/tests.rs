@@ -0,0 +1,71 @@
+use super::*;
+use crate::{fixture, LENGTH_PREFIX};
+use png_spec::image::{ColorType, Header};
+
+fn png(height: u32) -> Png {
+    let header = Header::new(24, height, 8, ColorType::Rgb).unwrap();
+    let data = (0..header.row_bytes(24) * height as usize)
+        .map(|i| (i * 7 % 251) as u8)
+        .collect();
+    fixture::png(&Image::new(header, data).unwrap())
+}
+
+#[test]
+fn test_embed_extract() {
+    let mut png = png(100);
+    let original = Image::from_png(&png).unwrap();
+    let filters = Image::filter_types(&png).unwrap();
+
+    embed(&mut png, b"in the filters").unwrap();
+    assert_ne!(Image::filter_types(&png).unwrap(), filters);
+    assert_eq!(Image::from_png(&png).unwrap(), original);
+    assert_eq!(extract(&png).unwrap(), b"in the filters");
+}
+
+#[test]
+fn test_capacity() {
+    let mut png = png(100);
+    let image = Image::from_png(&png).unwrap();
+    // log2(5^100) is just over 232 bits
+    assert_eq!(capacity(&image).unwrap(), 29 - LENGTH_PREFIX);
+
+    embed(&mut png, &[0x5a; 25]).unwrap();
+    assert_eq!(extract(&png).unwrap(), [0x5a; 25]);
+    assert!(matches!(
+        embed(&mut png, &[0; 26]),
+        Err(CarrierError::Capacity {
+            needed: 26,
+            available: 25
+        })
+    ));
+
+    // too short for even the length
+    let image = Image::from_png(&self::png(7)).unwrap();
+    assert_eq!(capacity(&image).unwrap(), 0);
+}
+
+#[test]
+fn test_blocks() {
+    assert_eq!(block_bytes(BLOCK_ROWS), 297);
+    assert_eq!(bytes(3 * BLOCK_ROWS + 100), 3 * 297 + 29);
+
+    // a payload spanning blocks, in a narrow image so the test stays quick
+    let header = Header::new(1, 2 * BLOCK_ROWS as u32 + 9, 8, ColorType::Grayscale).unwrap();
+    let data = (0..header.data_length().unwrap())
+        .map(|i| (i * 7 % 251) as u8)
+        .collect();
+    let mut png = fixture::png(&Image::new(header, data).unwrap());
+    let payload: Vec<u8> = (0..=255).cycle().take(500).collect();
+    embed(&mut png, &payload).unwrap();
+    assert_eq!(extract(&png).unwrap(), payload);
+}
+
+#[test]
+fn test_every_filter_type_used() {
+    let mut png = png(100);
+    embed(&mut png, b"").unwrap();
+    let filters = Image::filter_types(&png).unwrap();
+    for filter in FilterType::ALL {
+        assert!(filters.contains(&filter), "{filter:?}");
+    }
+}
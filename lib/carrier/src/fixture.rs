@@ -0,0 +1,21 @@
+//! Test fixtures shared by the carriers.
+
+use png_spec::{
+    chunk::Chunk,
+    chunk_type::ChunkType,
+    image::{ColorType, EncodeOptions, Image},
+    png::Png,
+};
+
+/// A PNG file holding `image` and no other chunks.
+pub(crate) fn png(image: &Image) -> Png {
+    let mut chunks = vec![image.header().to_chunk()];
+    if image.header().color_type() == ColorType::Indexed {
+        chunks.push(Chunk::new(ChunkType::PLTE, image.palette().concat()));
+    }
+    chunks.push(Chunk::new(ChunkType::IDAT, Vec::new()));
+    chunks.push(Chunk::new(ChunkType::IEND, Vec::new()));
+    let mut png = Png::from_chunks(chunks);
+    image.write_to(&mut png, &EncodeOptions::default()).unwrap();
+    png
+}
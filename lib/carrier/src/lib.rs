@@ -0,0 +1,216 @@
+//! Carriers hide a payload in a PNG file somewhere other than a chunk of its own.
+//!
+//! Every carrier stores the payload with a 4 byte big endian length in front, so it knows where
+//! the payload ends when extracting it. With error correction the length and the payload are
+//! each encoded with [`fec::ReedSolomon`], the length first.
+
+use fec::ReedSolomon;
+use rand_chacha::{
+    rand_core::{RngCore, SeedableRng},
+    ChaCha20Rng,
+};
+use sha2::{Digest, Sha256};
+
+pub use error::CarrierError;
+
+pub mod detect;
+pub mod disguise;
+mod error;
+pub mod fec;
+pub mod filters;
+#[cfg(test)]
+mod fixture;
+pub mod lsb;
+pub mod palette;
+pub mod transparent;
+pub mod watermark;
+pub mod zero_width;
+pub mod zlib;
+
+/// Bytes in front of every payload holding its length.
+pub const LENGTH_PREFIX: usize = 4;
+
+/// Bytes the length takes in front of the payload.
+fn prefix_length(fec: Option<&ReedSolomon>) -> usize {
+    fec.map_or(LENGTH_PREFIX, |rs| rs.encoded_length(LENGTH_PREFIX))
+}
+
+/// Bytes a payload of `length` bytes takes after the length.
+fn body_length(length: usize, fec: Option<&ReedSolomon>) -> usize {
+    fec.map_or(length, |rs| rs.encoded_length(length))
+}
+
+/// Largest payload that fits in `bytes` once framed.
+fn payload_capacity(bytes: usize, fec: Option<&ReedSolomon>) -> usize {
+    let rest = bytes.saturating_sub(prefix_length(fec));
+    fec.map_or(rest, |rs| rs.data_capacity(rest))
+}
+
+/// The payload with its length in front.
+fn frame(payload: &[u8], fec: Option<&ReedSolomon>) -> Result<Vec<u8>, CarrierError> {
+    let length = u32::try_from(payload.len()).map_err(|_| CarrierError::Capacity {
+        needed: payload.len(),
+        available: u32::MAX as usize,
+    })?;
+    Ok(match fec {
+        None => [&length.to_be_bytes(), payload].concat(),
+        Some(rs) => [rs.encode(&length.to_be_bytes()), rs.encode(payload)].concat(),
+    })
+}
+
+/// The payload length read from its prefix, and how many bytes were corrected.
+fn read_prefix(prefix: &[u8], fec: Option<&ReedSolomon>) -> Result<(usize, usize), CarrierError> {
+    let (length, corrected) = match fec {
+        None => (prefix.to_vec(), 0),
+        // a prefix beyond repair is more likely a wrong key than damage
+        Some(rs) => rs.decode(prefix).map_err(|_| CarrierError::NoPayload)?,
+    };
+    let length = length.try_into().map_err(|_| CarrierError::NoPayload)?;
+    Ok((u32::from_be_bytes(length) as usize, corrected))
+}
+
+/// The payload read from the bytes after its length, and how many bytes were corrected.
+fn read_body(body: Vec<u8>, fec: Option<&ReedSolomon>) -> Result<(Vec<u8>, usize), CarrierError> {
+    match fec {
+        None => Ok((body, 0)),
+        Some(rs) => rs.decode(&body),
+    }
+}
+
+/// The payload framed without error correction and filled up to `bytes` with noise derived from
+/// it, for carriers where every possible state reads as some number of `bytes` bytes.
+fn padded_frame(domain: &str, payload: &[u8], bytes: usize) -> Result<Vec<u8>, CarrierError> {
+    if LENGTH_PREFIX + payload.len() > bytes {
+        return Err(CarrierError::Capacity {
+            needed: payload.len(),
+            available: payload_capacity(bytes, None),
+        });
+    }
+    let mut framed = frame(payload, None)?;
+    let mut noise = vec![0; bytes - framed.len()];
+    seeded_rng(domain, &framed).fill_bytes(&mut noise);
+    framed.extend(noise);
+    Ok(framed)
+}
+
+/// The payload framed without error correction at the start of `bytes`, which may go on after
+/// it, as made by [`frame`] or [`padded_frame`].
+fn unframe(bytes: &[u8]) -> Result<Vec<u8>, CarrierError> {
+    if bytes.len() < LENGTH_PREFIX {
+        return Err(CarrierError::NoPayload);
+    }
+    let (length, _) = read_prefix(&bytes[..LENGTH_PREFIX], None)?;
+    let payload = bytes[LENGTH_PREFIX..]
+        .get(..length)
+        .ok_or(CarrierError::NoPayload)?;
+    Ok(payload.to_vec())
+}
+
+/// Whole bytes any number below the product of `radices` can hold, `floor(log2(product) / 8)`.
+fn radix_bytes(radices: impl IntoIterator<Item = u32>) -> usize {
+    let mut product = vec![1];
+    for radix in radices {
+        let mut carry = mul_add(&mut product, radix, 0);
+        while carry != 0 {
+            product.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    let bits = product.len() * 8 - product[0].leading_zeros() as usize;
+    bits.saturating_sub(1) / 8
+}
+
+/// Digits of the big endian `number` in the mixed radix `radices`, most significant first.
+fn to_digits(mut number: Vec<u8>, radices: &[u32]) -> Vec<u32> {
+    let mut digits = vec![0; radices.len()];
+    for (digit, &radix) in digits.iter_mut().zip(radices).rev() {
+        *digit = div_rem(&mut number, radix);
+    }
+    digits
+}
+
+/// The number with the mixed radix `(radix, digit)` pairs, most significant first, as `bytes` big
+/// endian bytes, or `None` if it does not fit.
+fn from_digits(digits: impl IntoIterator<Item = (u32, u32)>, bytes: usize) -> Option<Vec<u8>> {
+    let mut number = vec![0; bytes];
+    for (radix, digit) in digits {
+        if mul_add(&mut number, radix, digit) != 0 {
+            return None;
+        }
+    }
+    Some(number)
+}
+
+/// Sets the big endian `number` to `number * factor + add`, returning what overflows its length.
+fn mul_add(number: &mut [u8], factor: u32, add: u32) -> u32 {
+    let mut carry = add as u64;
+    for byte in number.iter_mut().rev() {
+        let value = *byte as u64 * factor as u64 + carry;
+        *byte = value as u8;
+        carry = value >> 8;
+    }
+    carry as u32
+}
+
+/// Divides the big endian `number` by `divisor` in place, returning the remainder.
+fn div_rem(number: &mut [u8], divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+    for byte in number.iter_mut() {
+        let value = remainder << 8 | *byte as u64;
+        *byte = (value / divisor as u64) as u8;
+        remainder = value % divisor as u64;
+    }
+    remainder as u32
+}
+
+/// A random number generator seeded from `key`, separate for each carrier `domain`.
+fn seeded_rng(domain: &str, key: &[u8]) -> ChaCha20Rng {
+    let seed = Sha256::new()
+        .chain_update(domain)
+        .chain_update([0])
+        .chain_update(key)
+        .finalize();
+    ChaCha20Rng::from_seed(seed.into())
+}
+
+/// A uniformly distributed number below `n`.
+fn below(rng: &mut impl RngCore, n: u64) -> u64 {
+    // rejects the values that would make the low numbers more likely
+    let zone = u64::MAX - u64::MAX % n;
+    loop {
+        let value = rng.next_u64();
+        if value < zone {
+            return value % n;
+        }
+    }
+}
+
+/// Fisher–Yates shuffle. Implemented here so the order for a key never depends on a dependency's
+/// version.
+fn shuffle<T>(items: &mut [T], rng: &mut impl RngCore) {
+    for i in (1..items.len()).rev() {
+        let j = below(rng, i as u64 + 1) as usize;
+        items.swap(i, j);
+    }
+}
+
+/// The bits of `bytes`, most significant first.
+fn bits(bytes: &[u8]) -> impl Iterator<Item = bool> + '_ {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| byte >> i & 1 == 1))
+}
+
+/// Packs bits, most significant first, into bytes. Missing bits at the end are 0.
+fn pack(bits: impl IntoIterator<Item = bool>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for (i, bit) in bits.into_iter().enumerate() {
+        if i % 8 == 0 {
+            bytes.push(0);
+        }
+        if bit {
+            *bytes.last_mut().expect("pushed above") |= 0x80 >> (i % 8);
+        }
+    }
+    bytes
+}
@@ -0,0 +1,288 @@
+//! Least significant bit embedding: the payload replaces the lowest bits of chosen color
+//! channels, visiting the pixels in an order derived from a key.
+//!
+//! Each pixel contributes the selected bits of every selected channel, in the order the channels
+//! were given. Grayscale images expose their gray samples as the red channel. Images with a
+//! tRNS color key are refused, as a changed pixel could turn transparent or opaque.
+
+use crate::{
+    bits, body_length, fec::ReedSolomon, frame, pack, payload_capacity, prefix_length, read_body,
+    read_prefix, seeded_rng, shuffle, CarrierError,
+};
+use png_spec::image::{ColorType, Image};
+use std::{fmt, str::FromStr};
+
+#[cfg(test)]
+mod tests;
+
+/// Keeps the pixel order of LSB embedding apart from other uses of the same key.
+const DOMAIN: &str = "png-message lsb v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl Channel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Channel::Red => "red",
+            Channel::Green => "green",
+            Channel::Blue => "blue",
+            Channel::Alpha => "alpha",
+        }
+    }
+
+    /// Index of the channel in the samples of a pixel.
+    fn index(&self, color_type: ColorType) -> Result<usize, CarrierError> {
+        let index = match (color_type, self) {
+            (ColorType::Grayscale | ColorType::GrayscaleAlpha, Channel::Red) => Some(0),
+            (ColorType::GrayscaleAlpha, Channel::Alpha) => Some(1),
+            (ColorType::Rgb | ColorType::Rgba, Channel::Red) => Some(0),
+            (ColorType::Rgb | ColorType::Rgba, Channel::Green) => Some(1),
+            (ColorType::Rgb | ColorType::Rgba, Channel::Blue) => Some(2),
+            (ColorType::Rgba, Channel::Alpha) => Some(3),
+            (ColorType::Indexed, _) => return Err(CarrierError::Palette),
+            _ => None,
+        };
+        index.ok_or_else(|| CarrierError::MissingChannel(self.name().to_string(), color_type))
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Channel {
+    type Err = CarrierError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "r" | "red" => Ok(Channel::Red),
+            "g" | "green" => Ok(Channel::Green),
+            "b" | "blue" => Ok(Channel::Blue),
+            "a" | "alpha" => Ok(Channel::Alpha),
+            _ => Err(CarrierError::Channels(format!(
+                "unknown channel '{s}', expected r, g, b or a"
+            ))),
+        }
+    }
+}
+
+/// A channel and how many of its lowest bits carry the payload, 1 or 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelBits {
+    channel: Channel,
+    bits: u8,
+}
+
+impl ChannelBits {
+    pub fn new(channel: Channel, bits: u8) -> Result<ChannelBits, CarrierError> {
+        if !(1..=2).contains(&bits) {
+            return Err(CarrierError::Channels(format!(
+                "{bits} bits of {channel}, expected 1 or 2"
+            )));
+        }
+        Ok(ChannelBits { channel, bits })
+    }
+
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+}
+
+impl fmt::Display for ChannelBits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.channel, self.bits)
+    }
+}
+
+impl FromStr for ChannelBits {
+    type Err = CarrierError;
+
+    /// `r` for one bit of red, `r:2` for two.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (channel, bits) = s.split_once(':').unwrap_or((s, "1"));
+        let bits = bits
+            .parse()
+            .map_err(|_| CarrierError::Channels(format!("invalid bit count in '{s}'")))?;
+        ChannelBits::new(channel.parse()?, bits)
+    }
+}
+
+/// LSB embedding with a key, a channel selection and optional error correction. Extracting needs
+/// the same settings as embedding.
+#[derive(Debug, Clone)]
+pub struct Lsb {
+    key: Vec<u8>,
+    channels: Vec<ChannelBits>,
+    fec: Option<ReedSolomon>,
+}
+
+impl Lsb {
+    /// One bit of red, green and blue.
+    pub const DEFAULT_CHANNELS: [ChannelBits; 3] = [
+        ChannelBits {
+            channel: Channel::Red,
+            bits: 1,
+        },
+        ChannelBits {
+            channel: Channel::Green,
+            bits: 1,
+        },
+        ChannelBits {
+            channel: Channel::Blue,
+            bits: 1,
+        },
+    ];
+
+    /// [`Lsb::DEFAULT_CHANNELS`], or one bit of gray for grayscale images, which have no green
+    /// or blue.
+    pub fn default_channels(color_type: ColorType) -> Vec<ChannelBits> {
+        if color_type.is_grayscale() {
+            Lsb::DEFAULT_CHANNELS[..1].to_vec()
+        } else {
+            Lsb::DEFAULT_CHANNELS.to_vec()
+        }
+    }
+
+    pub fn new(key: &[u8], channels: Vec<ChannelBits>) -> Result<Lsb, CarrierError> {
+        if channels.is_empty() {
+            return Err(CarrierError::Channels("no channels selected".to_string()));
+        }
+        for (i, c) in channels.iter().enumerate() {
+            if channels[..i].iter().any(|other| other.channel == c.channel) {
+                return Err(CarrierError::Channels(format!(
+                    "{} is selected twice",
+                    c.channel
+                )));
+            }
+        }
+        Ok(Lsb {
+            key: key.to_vec(),
+            channels,
+            fec: None,
+        })
+    }
+
+    pub fn channels(&self) -> &[ChannelBits] {
+        &self.channels
+    }
+
+    pub fn fec(&self) -> Option<&ReedSolomon> {
+        self.fec.as_ref()
+    }
+
+    pub fn set_fec(&mut self, fec: Option<ReedSolomon>) {
+        self.fec = fec;
+    }
+
+    /// Sample index and bit count of every selected channel, if the image supports them.
+    fn layout(&self, image: &Image) -> Result<Vec<(usize, u8)>, CarrierError> {
+        let header = image.header();
+        if header.color_type() == ColorType::Indexed {
+            return Err(CarrierError::Palette);
+        }
+        if !image.transparency().is_empty() {
+            return Err(CarrierError::ColorKey);
+        }
+        if header.bit_depth() < 8 {
+            return Err(CarrierError::BitDepth(header.bit_depth()));
+        }
+        self.channels
+            .iter()
+            .map(|c| Ok((c.channel.index(header.color_type())?, c.bits)))
+            .collect()
+    }
+
+    /// Largest payload in bytes that fits in `image`.
+    pub fn capacity(&self, image: &Image) -> Result<usize, CarrierError> {
+        let layout = self.layout(image)?;
+        let bits_per_pixel: usize = layout.iter().map(|(_, bits)| *bits as usize).sum();
+        let pixels = image.width() as usize * image.height() as usize;
+        Ok(payload_capacity(
+            pixels * bits_per_pixel / 8,
+            self.fec.as_ref(),
+        ))
+    }
+
+    /// Every place a payload bit goes: pixel, sample index and bit, in embedding order.
+    fn slots(
+        &self,
+        image: &Image,
+    ) -> Result<impl Iterator<Item = (u32, u32, usize, u8)>, CarrierError> {
+        let layout = self.layout(image)?;
+        let width = image.width();
+        let pixels = width as usize * image.height() as usize;
+        let pixels = u32::try_from(pixels).map_err(|_| CarrierError::Capacity {
+            needed: pixels,
+            available: u32::MAX as usize,
+        })?;
+
+        let mut order: Vec<u32> = (0..pixels).collect();
+        shuffle(&mut order, &mut seeded_rng(DOMAIN, &self.key));
+
+        Ok(order.into_iter().flat_map(move |i| {
+            let (x, y) = (i % width, i / width);
+            layout.clone().into_iter().flat_map(move |(channel, bits)| {
+                (0..bits).rev().map(move |bit| (x, y, channel, bit))
+            })
+        }))
+    }
+
+    /// Hides `payload` in the lowest bits of `image`.
+    pub fn embed(&self, image: &mut Image, payload: &[u8]) -> Result<(), CarrierError> {
+        let available = self.capacity(image)?;
+        if payload.len() > available {
+            return Err(CarrierError::Capacity {
+                needed: payload.len(),
+                available,
+            });
+        }
+
+        let framed = frame(payload, self.fec.as_ref())?;
+        let slots: Vec<_> = self.slots(image)?.take(framed.len() * 8).collect();
+        for ((x, y, channel, bit), value) in slots.into_iter().zip(bits(&framed)) {
+            let sample = image.sample(x, y, channel) & !(1 << bit);
+            image.set_sample(x, y, channel, sample | (value as u16) << bit);
+        }
+        Ok(())
+    }
+
+    /// Reads back a payload hidden with the same settings.
+    pub fn extract(&self, image: &Image) -> Result<Vec<u8>, CarrierError> {
+        self.extract_corrected(image).map(|(payload, _)| payload)
+    }
+
+    /// Reads back a payload hidden with the same settings, and how many bytes error correction
+    /// repaired.
+    pub fn extract_corrected(&self, image: &Image) -> Result<(Vec<u8>, usize), CarrierError> {
+        let fec = self.fec.as_ref();
+        let available = self.capacity(image)?;
+        let mut slots = self.slots(image)?;
+        let mut read = |bytes: usize| {
+            pack(
+                slots
+                    .by_ref()
+                    .take(bytes * 8)
+                    .map(|(x, y, channel, bit)| image.sample(x, y, channel) >> bit & 1 == 1),
+            )
+        };
+
+        let (length, prefix_corrected) = read_prefix(&read(prefix_length(fec)), fec)?;
+        if length > available {
+            return Err(CarrierError::NoPayload);
+        }
+        let (payload, corrected) = read_body(read(body_length(length, fec)), fec)?;
+        Ok((payload, prefix_corrected + corrected))
+    }
+}
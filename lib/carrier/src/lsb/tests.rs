@@ -0,0 +1,189 @@
+use super::*;
+use crate::LENGTH_PREFIX;
+use png_spec::image::Header;
+
+fn image(color_type: ColorType, bit_depth: u8) -> Image {
+    let header = Header::new(40, 30, bit_depth, color_type).unwrap();
+    let data = (0..header.row_bytes(40) * 30)
+        .map(|i| (i * 7 % 251) as u8)
+        .collect();
+    Image::new(header, data).unwrap()
+}
+
+fn channels(s: &str) -> Vec<ChannelBits> {
+    s.split(',').map(|c| c.parse().unwrap()).collect()
+}
+
+#[test]
+fn test_embed_extract() {
+    let lsb = Lsb::new(b"key", Lsb::DEFAULT_CHANNELS.to_vec()).unwrap();
+    let mut image = image(ColorType::Rgb, 8);
+    let original = image.clone();
+
+    lsb.embed(&mut image, b"hidden in plain sight").unwrap();
+    assert_ne!(image, original);
+    assert_eq!(lsb.extract(&image).unwrap(), b"hidden in plain sight");
+
+    // only the lowest bit of each sample changed
+    for (a, b) in image.data().iter().zip(original.data()) {
+        assert!(a ^ b <= 1);
+    }
+}
+
+#[test]
+fn test_default_channels() {
+    for color_type in [
+        ColorType::Grayscale,
+        ColorType::GrayscaleAlpha,
+        ColorType::Rgba,
+    ] {
+        let lsb = Lsb::new(b"key", Lsb::default_channels(color_type)).unwrap();
+        let mut image = image(color_type, 8);
+        lsb.embed(&mut image, b"gray").unwrap();
+        assert_eq!(lsb.extract(&image).unwrap(), b"gray");
+    }
+    assert_eq!(Lsb::default_channels(ColorType::Grayscale), channels("r"));
+}
+
+#[test]
+fn test_every_format() {
+    let formats = [
+        (ColorType::Grayscale, 8, "r:2"),
+        (ColorType::GrayscaleAlpha, 16, "r,a"),
+        (ColorType::Rgb, 16, "g:2,b"),
+        (ColorType::Rgba, 8, "r:2,g:2,b:2,a:2"),
+    ];
+    for (color_type, depth, selection) in formats {
+        let lsb = Lsb::new(b"key", channels(selection)).unwrap();
+        let mut image = image(color_type, depth);
+        let payload = vec![0xa5; lsb.capacity(&image).unwrap()];
+        lsb.embed(&mut image, &payload).unwrap();
+        assert_eq!(lsb.extract(&image).unwrap(), payload, "{color_type:?}");
+    }
+}
+
+#[test]
+fn test_error_correction() {
+    let mut lsb = Lsb::new(b"key", Lsb::DEFAULT_CHANNELS.to_vec()).unwrap();
+    lsb.set_fec(Some(ReedSolomon::new(16).unwrap()));
+    let mut image = image(ColorType::Rgb, 8);
+    lsb.embed(&mut image, b"survives small edits").unwrap();
+
+    // one flipped bit in each of bytes 0, 5, 10, 15 of the length and byte 0 of the payload
+    let slots: Vec<_> = lsb.slots(&image).unwrap().step_by(40).take(5).collect();
+    for (x, y, channel, bit) in slots {
+        let sample = image.sample(x, y, channel) ^ 1 << bit;
+        image.set_sample(x, y, channel, sample);
+    }
+    assert_eq!(
+        lsb.extract_corrected(&image).unwrap(),
+        (b"survives small edits".to_vec(), 5)
+    );
+
+    // without error correction the length is already wrong
+    let plain = Lsb::new(b"key", Lsb::DEFAULT_CHANNELS.to_vec()).unwrap();
+    assert_ne!(
+        plain.extract(&image).ok(),
+        Some(b"survives small edits".to_vec())
+    );
+}
+
+#[test]
+fn test_capacity() {
+    let image = image(ColorType::Rgba, 8);
+    // 1200 pixels, 3 bits each
+    let lsb = Lsb::new(b"", Lsb::DEFAULT_CHANNELS.to_vec()).unwrap();
+    assert_eq!(lsb.capacity(&image).unwrap(), 450 - LENGTH_PREFIX);
+    let lsb = Lsb::new(b"", channels("r:2,g:2,b:2,a:2")).unwrap();
+    assert_eq!(lsb.capacity(&image).unwrap(), 1200 - LENGTH_PREFIX);
+    // a 20 byte length, a full block of 239 bytes and 159 of the remaining 175
+    let mut lsb = Lsb::new(b"", Lsb::DEFAULT_CHANNELS.to_vec()).unwrap();
+    lsb.set_fec(Some(ReedSolomon::new(16).unwrap()));
+    assert_eq!(lsb.capacity(&image).unwrap(), 239 + 159);
+
+    let mut image = image;
+    assert!(matches!(
+        Lsb::new(b"", channels("r"))
+            .unwrap()
+            .embed(&mut image, &[0; 200]),
+        Err(CarrierError::Capacity {
+            needed: 200,
+            available: 146
+        })
+    ));
+}
+
+#[test]
+fn test_wrong_key() {
+    let lsb = Lsb::new(b"key", Lsb::DEFAULT_CHANNELS.to_vec()).unwrap();
+    let mut image = image(ColorType::Rgb, 8);
+    lsb.embed(&mut image, b"secret").unwrap();
+
+    let other = Lsb::new(b"other key", Lsb::DEFAULT_CHANNELS.to_vec()).unwrap();
+    // a wrong key reads noise, which is almost never a plausible length
+    match other.extract(&image) {
+        Err(CarrierError::NoPayload) => {}
+        Ok(payload) => assert_ne!(payload, b"secret"),
+        Err(e) => panic!("{e}"),
+    }
+}
+
+#[test]
+fn test_key_order_is_stable() {
+    // the pixel order is part of the format
+    let lsb = Lsb::new(b"key", channels("r")).unwrap();
+    let image = image(ColorType::Rgb, 8);
+    let first: Vec<_> = lsb.slots(&image).unwrap().take(4).collect();
+    assert_eq!(
+        first,
+        [(18, 24, 0, 0), (7, 12, 0, 0), (2, 2, 0, 0), (5, 19, 0, 0)]
+    );
+}
+
+#[test]
+fn test_refuses_palette_and_low_depth() {
+    let lsb = Lsb::new(b"key", Lsb::DEFAULT_CHANNELS.to_vec()).unwrap();
+    let indexed = image(ColorType::Indexed, 8).with_palette(vec![[0; 3]; 256], vec![]);
+    assert!(matches!(lsb.capacity(&indexed), Err(CarrierError::Palette)));
+    assert!(matches!(
+        Lsb::new(b"", channels("r"))
+            .unwrap()
+            .capacity(&image(ColorType::Grayscale, 4)),
+        Err(CarrierError::BitDepth(4))
+    ));
+}
+
+#[test]
+fn test_refuses_color_key() {
+    let lsb = Lsb::new(b"key", Lsb::DEFAULT_CHANNELS.to_vec()).unwrap();
+    let mut keyed = image(ColorType::Rgb, 8).with_palette(vec![], vec![0, 7, 0, 14, 0, 21]);
+    assert!(matches!(lsb.capacity(&keyed), Err(CarrierError::ColorKey)));
+    assert!(matches!(
+        lsb.embed(&mut keyed, b"x"),
+        Err(CarrierError::ColorKey)
+    ));
+}
+
+#[test]
+fn test_missing_channel() {
+    let lsb = Lsb::new(b"key", channels("r,a")).unwrap();
+    assert!(matches!(
+        lsb.capacity(&image(ColorType::Rgb, 8)),
+        Err(CarrierError::MissingChannel(..))
+    ));
+}
+
+#[test]
+fn test_channel_selection() {
+    assert_eq!(
+        channels("R:2,b"),
+        [
+            ChannelBits::new(Channel::Red, 2).unwrap(),
+            ChannelBits::new(Channel::Blue, 1).unwrap(),
+        ]
+    );
+    assert!("r:3".parse::<ChannelBits>().is_err());
+    assert!("x".parse::<ChannelBits>().is_err());
+    assert!(Lsb::new(b"", channels("r,r:2")).is_err());
+    assert!(Lsb::new(b"", vec![]).is_err());
+}
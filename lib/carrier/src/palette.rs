@@ -0,0 +1,141 @@
+//! Palette order embedding: the payload becomes the order of the PLTE entries of an indexed
+//! image. Pixels, tRNS, bKGD and hIST follow their entries, so the image looks the same.
+//!
+//! The entries sorted by color and alpha are the starting point, and the framed payload, read as
+//! one big endian number, picks each entry in turn from those left (a Lehmer code). A palette of
+//! `n` distinct entries has `n!` orders, so it holds `log2(n!)` bits, rounded down to whole
+//! bytes. The bytes after the payload are filled with noise derived from it, so the end of the
+//! palette is not left sorted.
+
+use crate::{
+    from_digits, padded_frame, payload_capacity, radix_bytes, to_digits, unframe, CarrierError,
+};
+use png_spec::{
+    chunk::Chunk,
+    chunk_type::ChunkType,
+    image::{ColorType, EncodeOptions, Image},
+    png::Png,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Keeps the padding noise apart from other uses of the payload bytes.
+const DOMAIN: &str = "png-message palette v1";
+
+const BKGD: &str = "bKGD";
+const HIST: &str = "hIST";
+
+/// Largest payload in bytes that fits in the palette order of `image`.
+pub fn capacity(image: &Image) -> Result<usize, CarrierError> {
+    let bytes = radix_bytes(radices(sorted_keys(image)?.len()));
+    Ok(payload_capacity(bytes, None))
+}
+
+/// Hides `payload` in the palette order of the image in `png`.
+pub fn embed(png: &mut Png, payload: &[u8]) -> Result<(), CarrierError> {
+    let image = Image::from_png(png)?;
+    let keys = sorted_keys(&image)?;
+    image.validate_palette()?;
+    let radices: Vec<u32> = radices(keys.len()).collect();
+    let number = padded_frame(DOMAIN, payload, radix_bytes(radices.iter().copied()))?;
+    let mut remaining: Vec<usize> = keys.iter().map(|&(_, index)| index).collect();
+    let order: Vec<usize> = to_digits(number, &radices)
+        .into_iter()
+        .map(|d| remaining.remove(d as usize))
+        .collect();
+
+    let permuted = image
+        .permute_palette(&order)
+        .expect("the order is a permutation of the palette");
+    let ancillary = remap_ancillary(png, &order);
+    permuted.write_to(png, &EncodeOptions::default())?;
+
+    // bKGD is matched by color when rewriting, which is ambiguous when only alpha differs
+    png.remove_chunks_where(|c| matches!(c.chunk_type().to_string().as_str(), BKGD | HIST));
+    let index = png
+        .chunks()
+        .iter()
+        .position(|c| c.chunk_type() == &ChunkType::IDAT)
+        .unwrap_or(png.chunks().len());
+    for (i, chunk) in ancillary.into_iter().enumerate() {
+        png.insert_chunk(index + i, chunk);
+    }
+    Ok(())
+}
+
+/// Reads back a payload hidden in the palette order of `image`.
+pub fn extract(image: &Image) -> Result<Vec<u8>, CarrierError> {
+    let mut remaining = sorted_keys(image)?;
+    let n = remaining.len();
+    let digits = palette_keys(image).enumerate().map(|(j, key)| {
+        let digit = remaining
+            .binary_search(&(key, j))
+            .expect("every entry is sorted once");
+        remaining.remove(digit);
+        ((n - j) as u32, digit as u32)
+    });
+    // orders past the largest number of whole bytes never come from embedding
+    let number = from_digits(digits, radix_bytes(radices(n))).ok_or(CarrierError::NoPayload)?;
+    unframe(&number)
+}
+
+/// Choices left for each entry in turn: any of the `n` entries first, then any of the rest.
+fn radices(n: usize) -> impl Iterator<Item = u32> {
+    (1..=n as u32).rev()
+}
+
+/// Color and alpha of every palette entry, in palette order.
+fn palette_keys(image: &Image) -> impl Iterator<Item = [u8; 4]> + '_ {
+    image.palette().iter().enumerate().map(|(i, &[r, g, b])| {
+        let alpha = image.transparency().get(i).copied().unwrap_or(u8::MAX);
+        [r, g, b, alpha]
+    })
+}
+
+/// The palette entries with their index, sorted by color and alpha. Entries must be distinct,
+/// otherwise their order could not be told apart.
+fn sorted_keys(image: &Image) -> Result<Vec<([u8; 4], usize)>, CarrierError> {
+    if image.header().color_type() != ColorType::Indexed {
+        return Err(CarrierError::PaletteOrder(format!(
+            "{:?} images have no palette",
+            image.header().color_type()
+        )));
+    }
+    let mut keys: Vec<_> = palette_keys(image).zip(0..).collect();
+    keys.sort();
+    if let Some(pair) = keys.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        return Err(CarrierError::PaletteOrder(format!(
+            "entries {} and {} are the same color",
+            pair[0].1, pair[1].1
+        )));
+    }
+    Ok(keys)
+}
+
+/// bKGD and hIST of `png` rewritten for the palette `order`, where they are valid.
+fn remap_ancillary(png: &Png, order: &[usize]) -> Vec<Chunk> {
+    let mut new_index = vec![0; order.len()];
+    for (i, &old) in order.iter().enumerate() {
+        new_index[old] = i;
+    }
+
+    let mut chunks = Vec::new();
+    if let Some(bkgd) = png.chunk_by_type(BKGD) {
+        if let [old] = bkgd.data() {
+            if let Some(&new) = new_index.get(*old as usize) {
+                chunks.push(Chunk::new(*bkgd.chunk_type(), vec![new as u8]));
+            }
+        }
+    }
+    if let Some(hist) = png.chunk_by_type(HIST) {
+        if hist.data().len() == order.len() * 2 {
+            let data = order
+                .iter()
+                .flat_map(|&old| [hist.data()[2 * old], hist.data()[2 * old + 1]])
+                .collect();
+            chunks.push(Chunk::new(*hist.chunk_type(), data));
+        }
+    }
+    chunks
+}
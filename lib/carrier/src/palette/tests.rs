@@ -0,0 +1,147 @@
+use super::*;
+use crate::{fixture, LENGTH_PREFIX};
+use png_spec::image::{Header, ImageError};
+use std::str::FromStr;
+
+/// A 64 color image whose first entries are translucent, with a background and histogram.
+fn png() -> Png {
+    let header = Header::new(32, 16, 8, ColorType::Indexed).unwrap();
+    let data = (0..32 * 16).map(|i| (i * 5 % 64) as u8).collect();
+    let palette: Vec<[u8; 3]> = (0..64u8)
+        .map(|i| [i * 4, 255 - i * 3, i % 5 * 50])
+        .collect();
+    let image = Image::new(header, data)
+        .unwrap()
+        .with_palette(palette, vec![0, 64, 128]);
+    let mut png = fixture::png(&image);
+    let index = png.chunks().len() - 2;
+    let hist = (0..64u16).flat_map(|i| (i * 10).to_be_bytes()).collect();
+    png.insert_chunk(index, Chunk::new(ChunkType::from_str(HIST).unwrap(), hist));
+    png.insert_chunk(
+        index,
+        Chunk::new(ChunkType::from_str(BKGD).unwrap(), vec![2]),
+    );
+    png
+}
+
+fn chunk_data(png: &Png, chunk_type: &str) -> Vec<u8> {
+    png.chunk_by_type(chunk_type).unwrap().data().to_vec()
+}
+
+#[test]
+fn test_embed_extract() {
+    let mut png = png();
+    let original = Image::from_png(&png).unwrap();
+
+    embed(&mut png, b"palette order").unwrap();
+    let image = Image::from_png(&png).unwrap();
+    assert_ne!(image.palette(), original.palette());
+    assert_eq!(image.to_rgba16(), original.to_rgba16());
+    assert_eq!(extract(&image).unwrap(), b"palette order");
+}
+
+#[test]
+fn test_ancillary_chunks_follow() {
+    let mut png = png();
+    let original = Image::from_png(&png).unwrap();
+    embed(&mut png, b"").unwrap();
+    let image = Image::from_png(&png).unwrap();
+
+    let background = chunk_data(&png, BKGD)[0] as usize;
+    assert_eq!(image.palette()[background], original.palette()[2]);
+    assert_eq!(image.transparency()[background], 128);
+
+    let hist = chunk_data(&png, HIST);
+    for (i, color) in image.palette().iter().enumerate() {
+        let old = original.palette().iter().position(|c| c == color).unwrap();
+        let count = u16::from_be_bytes([hist[2 * i], hist[2 * i + 1]]);
+        assert_eq!(count as usize, old * 10);
+    }
+
+    let idat = png
+        .chunks()
+        .iter()
+        .position(|c| c.chunk_type() == &ChunkType::IDAT)
+        .unwrap();
+    let hist = png
+        .chunks()
+        .iter()
+        .position(|c| c.chunk_type().to_string() == HIST)
+        .unwrap();
+    assert!(hist < idat);
+}
+
+#[test]
+fn test_capacity() {
+    let png = png();
+    let image = Image::from_png(&png).unwrap();
+    // log2(64!) is just under 296 bits
+    assert_eq!(capacity(&image).unwrap(), 36 - LENGTH_PREFIX);
+
+    let mut full = png;
+    embed(&mut full, &[0xa5; 32]).unwrap();
+    assert_eq!(
+        extract(&Image::from_png(&full).unwrap()).unwrap(),
+        [0xa5; 32]
+    );
+
+    let mut png = full;
+    assert!(matches!(
+        embed(&mut png, &[0; 33]),
+        Err(CarrierError::Capacity {
+            needed: 33,
+            available: 32
+        })
+    ));
+}
+
+#[test]
+fn test_order_bytes() {
+    let bytes = |n| radix_bytes(radices(n));
+    assert_eq!(bytes(0), 0);
+    assert_eq!(bytes(2), 0);
+    assert_eq!(bytes(16), 5);
+    assert_eq!(bytes(256), 210);
+}
+
+#[test]
+fn test_unsuitable_images() {
+    let header = Header::new(4, 4, 8, ColorType::Rgb).unwrap();
+    let rgb = Image::new(header, vec![0; 48]).unwrap();
+    assert!(matches!(capacity(&rgb), Err(CarrierError::PaletteOrder(_))));
+
+    let header = Header::new(4, 4, 8, ColorType::Indexed).unwrap();
+    let duplicates = Image::new(header, vec![0; 16])
+        .unwrap()
+        .with_palette(vec![[1, 2, 3], [9, 9, 9], [1, 2, 3]], Vec::new());
+    assert!(matches!(
+        extract(&duplicates),
+        Err(CarrierError::PaletteOrder(_))
+    ));
+}
+
+#[test]
+fn test_index_past_palette() {
+    let mut png = png();
+    let plte = png.remove_chunk(&ChunkType::PLTE).unwrap();
+    png.insert_chunk(
+        1,
+        Chunk::new(ChunkType::PLTE, plte.data()[..32 * 3].to_vec()),
+    );
+    assert!(matches!(
+        embed(&mut png, b"x"),
+        Err(CarrierError::Image(ImageError::PaletteIndex(35)))
+    ));
+}
+
+#[test]
+fn test_palette_without_payload() {
+    let png = png();
+    let image = Image::from_png(&png).unwrap();
+    // the palette is already in color order, which reads as a zero length
+    assert_eq!(extract(&image).unwrap(), b"");
+
+    let reversed: Vec<usize> = (0..64).rev().collect();
+    let image = image.permute_palette(&reversed).unwrap();
+    assert!(matches!(extract(&image), Err(CarrierError::NoPayload)));
+}
@@ -0,0 +1,79 @@
+//! Transparent pixel embedding: the payload replaces the color samples of fully transparent
+//! pixels, whose color never shows. Only images with an alpha channel qualify.
+//!
+//! The transparent pixels are used row by row, every color sample of a pixel in turn, 8 or 16
+//! bits at a time. Alpha is left alone, so extracting finds the same pixels. The color samples
+//! after the payload keep their values.
+
+use crate::{bits, frame, pack, payload_capacity, unframe, CarrierError};
+use png_spec::image::{ColorType, Image};
+
+#[cfg(test)]
+mod tests;
+
+/// Index of the alpha sample and the number of color samples before it.
+fn alpha_channel(image: &Image) -> Result<usize, CarrierError> {
+    match image.header().color_type() {
+        ColorType::GrayscaleAlpha => Ok(1),
+        ColorType::Rgba => Ok(3),
+        color_type => Err(CarrierError::MissingChannel(
+            "alpha".to_string(),
+            color_type,
+        )),
+    }
+}
+
+/// Every color sample of the fully transparent pixels, in embedding order.
+fn slots(image: &Image) -> Result<Vec<(u32, u32, usize)>, CarrierError> {
+    let alpha = alpha_channel(image)?;
+    let mut slots = Vec::new();
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            if image.sample(x, y, alpha) == 0 {
+                slots.extend((0..alpha).map(|channel| (x, y, channel)));
+            }
+        }
+    }
+    Ok(slots)
+}
+
+/// Fully transparent pixels of `image`.
+pub fn transparent_pixels(image: &Image) -> Result<usize, CarrierError> {
+    Ok(slots(image)?.len() / alpha_channel(image)?)
+}
+
+/// Largest payload in bytes that fits in the transparent pixels of `image`.
+pub fn capacity(image: &Image) -> Result<usize, CarrierError> {
+    let bytes = slots(image)?.len() * image.header().bit_depth() as usize / 8;
+    Ok(payload_capacity(bytes, None))
+}
+
+/// Hides `payload` in the color of the transparent pixels of `image`.
+pub fn embed(image: &mut Image, payload: &[u8]) -> Result<(), CarrierError> {
+    let available = capacity(image)?;
+    if payload.len() > available {
+        return Err(CarrierError::Capacity {
+            needed: payload.len(),
+            available,
+        });
+    }
+
+    let depth = image.header().bit_depth() as usize;
+    let framed: Vec<bool> = bits(&frame(payload, None)?).collect();
+    for ((x, y, channel), sample) in slots(image)?.into_iter().zip(framed.chunks(depth)) {
+        let value = sample.iter().fold(0, |value, &bit| value << 1 | bit as u16);
+        // the last byte of a 16-bit image may fill only the high half of a sample
+        image.set_sample(x, y, channel, value << (depth - sample.len()));
+    }
+    Ok(())
+}
+
+/// Reads back a payload hidden in the transparent pixels of `image`.
+pub fn extract(image: &Image) -> Result<Vec<u8>, CarrierError> {
+    let depth = image.header().bit_depth() as usize;
+    let bytes = pack(slots(image)?.into_iter().flat_map(|(x, y, channel)| {
+        let sample = image.sample(x, y, channel);
+        (0..depth).rev().map(move |bit| sample >> bit & 1 == 1)
+    }));
+    unframe(&bytes)
+}
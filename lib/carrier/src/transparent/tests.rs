@@ -0,0 +1,78 @@
+use super::*;
+use crate::LENGTH_PREFIX;
+use png_spec::image::Header;
+
+/// An image whose left half is fully transparent.
+fn image(color_type: ColorType, bit_depth: u8) -> Image {
+    let header = Header::new(20, 10, bit_depth, color_type).unwrap();
+    let data = vec![0; header.row_bytes(20) * 10];
+    let mut image = Image::new(header, data).unwrap();
+    let channels = color_type.channels();
+    for y in 0..10 {
+        for x in 0..20 {
+            for channel in 0..channels - 1 {
+                image.set_sample(x, y, channel, (x * 11 + y * 3) as u16);
+            }
+            let alpha = if x < 10 { 0 } else { 200 + x as u16 };
+            image.set_sample(x, y, channels - 1, alpha);
+        }
+    }
+    image
+}
+
+#[test]
+fn test_embed_extract() {
+    for (color_type, depth) in [
+        (ColorType::Rgba, 8),
+        (ColorType::Rgba, 16),
+        (ColorType::GrayscaleAlpha, 8),
+        (ColorType::GrayscaleAlpha, 16),
+    ] {
+        let mut image = image(color_type, depth);
+        let original = image.clone();
+        embed(&mut image, b"invisible ink").unwrap();
+        assert_ne!(image, original);
+        assert!(image.looks_like(&original), "{color_type:?} {depth}");
+        assert_eq!(extract(&image).unwrap(), b"invisible ink");
+    }
+}
+
+#[test]
+fn test_capacity() {
+    let image = image(ColorType::Rgba, 8);
+    assert_eq!(transparent_pixels(&image).unwrap(), 100);
+    assert_eq!(capacity(&image).unwrap(), 300 - LENGTH_PREFIX);
+    let image = self::image(ColorType::GrayscaleAlpha, 16);
+    assert_eq!(capacity(&image).unwrap(), 200 - LENGTH_PREFIX);
+
+    // an odd number of bytes in 16-bit samples
+    let mut image = image;
+    embed(&mut image, &[0xc3; 195]).unwrap();
+    assert_eq!(extract(&image).unwrap(), [0xc3; 195]);
+    assert!(matches!(
+        embed(&mut image, &[0; 197]),
+        Err(CarrierError::Capacity {
+            needed: 197,
+            available: 196
+        })
+    ));
+}
+
+#[test]
+fn test_unsuitable_images() {
+    let header = Header::new(4, 4, 8, ColorType::Rgb).unwrap();
+    let rgb = Image::new(header, vec![0; 48]).unwrap();
+    assert!(matches!(
+        capacity(&rgb),
+        Err(CarrierError::MissingChannel(_, ColorType::Rgb))
+    ));
+
+    let mut opaque = image(ColorType::Rgba, 8);
+    for y in 0..10 {
+        for x in 0..10 {
+            opaque.set_sample(x, y, 3, 1);
+        }
+    }
+    assert_eq!(capacity(&opaque).unwrap(), 0);
+    assert!(matches!(extract(&opaque), Err(CarrierError::NoPayload)));
+}
@@ -0,0 +1,270 @@
+//! Robust watermarking: a 32 bit ID spread over the pixels as faint noise, which survives
+//! anything that keeps the pixels roughly as they are, such as re-encoding, stripping metadata,
+//! reducing to a palette or cropping a few rows and columns.
+//!
+//! A key lays out a tile of [`TILE`] by [`TILE`] cells, repeated over the whole image. Each cell
+//! has a random sign and belongs either to the sync pattern or to one bit of the ID. Every pixel
+//! is lightened or darkened by the strength, following the sign of its cell, flipped for cells of
+//! the bits that are 0.
+//!
+//! Detection removes the image itself by subtracting from every pixel the average of its four
+//! neighbors, and folds what is left into a single tile. A crop shifts the tile, so the sync
+//! pattern is looked for at every shift, and the bits are read at the best one. The confidence
+//! is the chance that the sync peak is not noise, times the chance that every bit is right.
+//! Every cell is read from all the pixels it covers, so small or busy images need a stronger mark.
+
+use crate::{seeded_rng, shuffle, CarrierError};
+use png_spec::image::{ColorType, Image};
+use rand_chacha::rand_core::RngCore;
+
+#[cfg(test)]
+mod tests;
+
+/// Keeps the layout of the tile apart from other uses of the same key.
+const DOMAIN: &str = "png-message watermark v1";
+
+/// Side of the tile in pixels.
+pub const TILE: usize = 32;
+
+/// Bits of the ID.
+pub const ID_BITS: usize = 32;
+
+/// Cells of the tile given to the sync pattern; the others are shared out among the bits.
+const SYNC_CELLS: usize = 256;
+
+/// Change in 8-bit levels used when none is given.
+pub const DEFAULT_STRENGTH: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Sync,
+    Bit(usize),
+}
+
+/// The tile layout for one key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watermark {
+    /// Role and sign of every cell, row by row.
+    cells: Vec<(Role, i8)>,
+}
+
+/// What detection found: the ID read at the best shift and how likely it is to be right.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Detection {
+    id: u32,
+    confidence: f64,
+    shift: (usize, usize),
+}
+
+impl Detection {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// From 0 to 1.
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+
+    /// Columns and rows the tile is shifted by, which is where a crop started modulo [`TILE`].
+    pub fn shift(&self) -> (usize, usize) {
+        self.shift
+    }
+}
+
+impl Watermark {
+    pub fn new(key: &[u8]) -> Watermark {
+        let mut rng = seeded_rng(DOMAIN, key);
+        let mut roles: Vec<Role> = (0..TILE * TILE)
+            .map(|i| match i.checked_sub(SYNC_CELLS) {
+                None => Role::Sync,
+                Some(i) => Role::Bit(i % ID_BITS),
+            })
+            .collect();
+        shuffle(&mut roles, &mut rng);
+        let cells = roles
+            .into_iter()
+            .map(|role| (role, if rng.next_u32() & 1 == 0 { 1 } else { -1 }))
+            .collect();
+        Watermark { cells }
+    }
+
+    /// Adds the watermark for `id` to the color channels of `image`, changing each sample by
+    /// `strength` 8-bit levels. Alpha is left alone.
+    pub fn embed(&self, image: &mut Image, id: u32, strength: u8) -> Result<(), CarrierError> {
+        let header = *image.header();
+        let channels = match header.color_type() {
+            ColorType::Indexed => return Err(CarrierError::Palette),
+            ColorType::Grayscale | ColorType::GrayscaleAlpha => 1,
+            ColorType::Rgb | ColorType::Rgba => 3,
+        };
+        let (step, max) = match header.bit_depth() {
+            8 => (strength as i32, u8::MAX as i32),
+            16 => (strength as i32 * 257, u16::MAX as i32),
+            depth => return Err(CarrierError::BitDepth(depth)),
+        };
+
+        for y in 0..image.height() {
+            for x in 0..image.width() {
+                let (role, sign) = self.cells[cell(x as usize, y as usize)];
+                let flip = match role {
+                    Role::Bit(bit) if id >> (ID_BITS - 1 - bit) & 1 == 0 => -1,
+                    _ => 1,
+                };
+                let change = step * (sign * flip) as i32;
+                for channel in 0..channels {
+                    let value = image.sample(x, y, channel) as i32 + change;
+                    image.set_sample(x, y, channel, value.clamp(0, max) as u16);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Looks for a watermark made with this key in `image`, whatever its color type.
+    pub fn detect(&self, image: &Image) -> Detection {
+        let folded = fold(image);
+
+        // the sync pattern at every shift of the tile
+        let scores: Vec<f64> = (0..TILE * TILE)
+            .map(|shift| {
+                self.correlations(&folded, shift)
+                    .filter(|(role, _)| *role == Role::Sync)
+                    .map(|(_, value)| value)
+                    .sum()
+            })
+            .collect();
+        let best = (0..scores.len())
+            .max_by(|&a, &b| scores[a].total_cmp(&scores[b]))
+            .expect("the tile has cells");
+        let others = scores
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != best)
+            .map(|(_, &s)| s);
+        let (mean, deviation) = mean_deviation(others);
+        let sync_z = if deviation > 0.0 {
+            (scores[best] - mean) / deviation
+        } else {
+            0.0
+        };
+        // the best of that many shifts standing out by chance
+        let mut confidence = (1.0 - (TILE * TILE) as f64 * upper_tail(sync_z)).max(0.0);
+
+        // each bit is the sum over its cells, with the noise measured around the bit values
+        let mut sums = [0.0; ID_BITS + 1];
+        let mut counts = [0usize; ID_BITS + 1];
+        let slot = |role| match role {
+            Role::Sync => ID_BITS,
+            Role::Bit(bit) => bit,
+        };
+        for (role, value) in self.correlations(&folded, best) {
+            sums[slot(role)] += value;
+            counts[slot(role)] += 1;
+        }
+        let noise = {
+            let squares: f64 = self
+                .correlations(&folded, best)
+                .map(|(role, value)| {
+                    let mean = sums[slot(role)] / counts[slot(role)] as f64;
+                    (value - mean).powi(2)
+                })
+                .sum();
+            (squares / (TILE * TILE) as f64).sqrt()
+        };
+
+        let mut id = 0;
+        for bit in 0..ID_BITS {
+            id = id << 1 | (sums[bit] > 0.0) as u32;
+            let z = sums[bit].abs() / (noise * (counts[bit] as f64).sqrt());
+            confidence *= if z.is_finite() {
+                1.0 - upper_tail(z)
+            } else {
+                0.5
+            };
+        }
+        Detection {
+            id,
+            confidence,
+            shift: (best % TILE, best / TILE),
+        }
+    }
+
+    /// Every cell's role and its folded value times its sign, with the tile shifted by `shift`.
+    fn correlations<'a>(
+        &'a self,
+        folded: &'a [f64],
+        shift: usize,
+    ) -> impl Iterator<Item = (Role, f64)> + 'a {
+        let (dx, dy) = (shift % TILE, shift / TILE);
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, &(role, sign))| {
+                let (x, y) = (i % TILE, i / TILE);
+                let shifted = cell(x + TILE - dx, y + TILE - dy);
+                (role, folded[shifted] * sign as f64)
+            })
+    }
+}
+
+fn cell(x: usize, y: usize) -> usize {
+    (y % TILE) * TILE + x % TILE
+}
+
+/// The median, for every cell of the tile, of how much brighter the pixels are than their
+/// neighbors, in 8-bit levels. The median keeps the few pixels along edges in the image from
+/// swamping the watermark. Transparent pixels and their neighbors are left out.
+fn fold(image: &Image) -> Vec<f64> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let luma: Vec<Option<f64>> = image
+        .to_rgba16()
+        .iter()
+        .map(|&[r, g, b, a]| (a != 0).then(|| (r as f64 + g as f64 + b as f64) / (3.0 * 257.0)))
+        .collect();
+
+    let mut residuals = vec![Vec::new(); TILE * TILE];
+    for y in 1..height.saturating_sub(1) {
+        for x in 1..width.saturating_sub(1) {
+            let at = |x: usize, y: usize| luma[y * width + x];
+            let neighbors = [at(x - 1, y), at(x + 1, y), at(x, y - 1), at(x, y + 1)];
+            if let (Some(center), Some(neighbors)) =
+                (at(x, y), neighbors.into_iter().sum::<Option<f64>>())
+            {
+                residuals[cell(x, y)].push(center - neighbors / 4.0);
+            }
+        }
+    }
+    residuals
+        .into_iter()
+        .map(|mut values| {
+            values.sort_by(f64::total_cmp);
+            match values.len() {
+                0 => 0.0,
+                n if n % 2 == 1 => values[n / 2],
+                n => (values[n / 2 - 1] + values[n / 2]) / 2.0,
+            }
+        })
+        .collect()
+}
+
+fn mean_deviation(values: impl Iterator<Item = f64> + Clone) -> (f64, f64) {
+    let count = values.clone().count() as f64;
+    let mean = values.clone().sum::<f64>() / count;
+    let variance = values.map(|v| (v - mean).powi(2)).sum::<f64>() / count;
+    (mean, variance.sqrt())
+}
+
+/// The chance that a standard normal variable is above `z`.
+fn upper_tail(z: f64) -> f64 {
+    if z < 0.0 {
+        return 1.0 - upper_tail(-z);
+    }
+    // Abramowitz and Stegun 7.1.26 for erfc(z / sqrt 2), accurate to 1.5e-7
+    let x = z / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    poly * (-x * x).exp() / 2.0
+}
@@ -0,0 +1,109 @@
+use super::*;
+use png_spec::image::Header;
+
+const ID: u32 = 0xC0FF_EE42;
+
+/// A gradient with some texture, like a photo.
+fn photo(width: u32, height: u32) -> Image {
+    let header = Header::new(width, height, 8, ColorType::Rgb).unwrap();
+    let data = (0..width * height * 3)
+        .map(|i| {
+            let (pixel, channel) = (i / 3, i % 3);
+            let (x, y) = (pixel % width, pixel / width);
+            let texture = (pixel.wrapping_mul(2_654_435_761) >> 24) % 13;
+            ((x + 2 * y) / 3 + 40 * channel + texture) as u8
+        })
+        .collect();
+    Image::new(header, data).unwrap()
+}
+
+fn crop(image: &Image, left: u32, top: u32, width: u32, height: u32) -> Image {
+    let header = Header::new(width, height, 8, ColorType::Rgb).unwrap();
+    let row = image.width() as usize * 3;
+    let data = (top..top + height)
+        .flat_map(|y| {
+            let start = y as usize * row + left as usize * 3;
+            image.data()[start..start + width as usize * 3].to_vec()
+        })
+        .collect();
+    Image::new(header, data).unwrap()
+}
+
+#[test]
+fn test_embed_detect() {
+    let watermark = Watermark::new(b"key");
+    let mut image = photo(128, 96);
+    let original = image.clone();
+    watermark.embed(&mut image, ID, DEFAULT_STRENGTH).unwrap();
+
+    for (a, b) in image.data().iter().zip(original.data()) {
+        assert!(a.abs_diff(*b) <= DEFAULT_STRENGTH);
+    }
+    let detection = watermark.detect(&image);
+    assert_eq!(detection.id(), ID);
+    assert_eq!(detection.shift(), (0, 0));
+    assert!(detection.confidence() > 0.99, "{detection:?}");
+}
+
+#[test]
+fn test_survives_crop() {
+    let watermark = Watermark::new(b"key");
+    let mut image = photo(128, 96);
+    watermark.embed(&mut image, ID, DEFAULT_STRENGTH).unwrap();
+
+    let cropped = crop(&image, 5, 11, 100, 70);
+    let detection = watermark.detect(&cropped);
+    assert_eq!(detection.id(), ID);
+    assert_eq!(detection.shift(), (5, 11));
+    assert!(detection.confidence() > 0.99, "{detection:?}");
+}
+
+#[test]
+fn test_survives_palette_reduction() {
+    let header = Header::new(96, 96, 16, ColorType::Rgba).unwrap();
+    // four flat colors, so the marked image still fits in a palette
+    let data = (0..96 * 96)
+        .flat_map(|i| {
+            let shade = [60u16, 110, 160, 210][(i % 96 / 48 + i / 96 / 48 * 2) as usize] * 257;
+            [shade, shade, shade, u16::MAX]
+                .into_iter()
+                .flat_map(u16::to_be_bytes)
+        })
+        .collect();
+    let mut image = Image::new(header, data).unwrap();
+    let watermark = Watermark::new(b"key");
+    watermark.embed(&mut image, ID, 2).unwrap();
+
+    let reduced = image
+        .reductions()
+        .into_iter()
+        .find(|i| i.header().color_type() == ColorType::Indexed)
+        .expect("few enough colors for a palette");
+    assert!(matches!(
+        watermark.embed(&mut reduced.clone(), ID, 2),
+        Err(CarrierError::Palette)
+    ));
+    let detection = watermark.detect(&reduced);
+    assert_eq!(detection.id(), ID);
+    assert!(detection.confidence() > 0.99, "{detection:?}");
+}
+
+#[test]
+fn test_unmarked_or_wrong_key() {
+    let watermark = Watermark::new(b"key");
+    assert!(watermark.detect(&photo(128, 96)).confidence() < 0.5);
+
+    let mut image = photo(128, 96);
+    watermark.embed(&mut image, ID, DEFAULT_STRENGTH).unwrap();
+    assert!(Watermark::new(b"other").detect(&image).confidence() < 0.5);
+}
+
+#[test]
+fn test_bit_depth() {
+    let header = Header::new(8, 8, 4, ColorType::Grayscale).unwrap();
+    let mut image = Image::new(header, vec![0; 32]).unwrap();
+    assert!(matches!(
+        Watermark::new(b"key").embed(&mut image, ID, 1),
+        Err(CarrierError::BitDepth(4))
+    ));
+}
@@ -0,0 +1,103 @@
+//! Zero-width text embedding: the payload becomes invisible characters interleaved into the text
+//! of an iTXt entry, so the entry reads the same and no unusual chunk appears.
+//!
+//! Every character stands for two bits, out of zero width space, non-joiner, joiner and word
+//! joiner. They only go right after whitespace and at the end of the text, where they cannot
+//! break a ligature or join two emoji. Zero-width characters already in the text are removed
+//! first, so embedding again replaces the payload.
+
+use crate::{bits, frame, pack, unframe, CarrierError, LENGTH_PREFIX};
+use png_spec::{chunk_type::ChunkType, png::Png, text::InternationalText};
+
+#[cfg(test)]
+mod tests;
+
+/// The entry used when none is given.
+pub const DEFAULT_KEYWORD: &str = "Comment";
+
+/// Text of the entry created when the image has none with the keyword.
+pub const COVER_TEXT: &str = "Exported with default settings";
+
+/// The characters standing for 0, 1, 2 and 3.
+const ALPHABET: [char; 4] = ['\u{200b}', '\u{200c}', '\u{200d}', '\u{2060}'];
+
+/// Bytes the text grows by to carry a payload of `length` bytes.
+pub fn hidden_length(length: usize) -> usize {
+    // four characters of three UTF-8 bytes each for every byte
+    (LENGTH_PREFIX + length) * 4 * ALPHABET[0].len_utf8()
+}
+
+/// `text` with `payload` interleaved as zero-width characters, replacing any already in it.
+pub fn hide(text: &str, payload: &[u8]) -> Result<String, CarrierError> {
+    let framed: Vec<bool> = bits(&frame(payload, None)?).collect();
+    let symbols: Vec<char> = framed
+        .chunks(2)
+        .map(|pair| ALPHABET[(pair[0] as usize) << 1 | pair[1] as usize])
+        .collect();
+
+    let visible = visible(text);
+    let gaps = visible.chars().filter(|c| c.is_whitespace()).count() + 1;
+    let mut hidden = String::with_capacity(visible.len() + symbols.len() * 3);
+    let mut gap = 0;
+    let take = |gap: usize| &symbols[gap * symbols.len() / gaps..(gap + 1) * symbols.len() / gaps];
+    for c in visible.chars() {
+        hidden.push(c);
+        if c.is_whitespace() {
+            hidden.extend(take(gap));
+            gap += 1;
+        }
+    }
+    hidden.extend(take(gap));
+    Ok(hidden)
+}
+
+/// The payload hidden in `text` by [`hide`].
+pub fn reveal(text: &str) -> Result<Vec<u8>, CarrierError> {
+    let bits = text
+        .chars()
+        .filter_map(|c| ALPHABET.iter().position(|&a| a == c))
+        .flat_map(|symbol| [symbol & 2 != 0, symbol & 1 != 0]);
+    let mut bits: Vec<bool> = bits.collect();
+    bits.truncate(bits.len() / 8 * 8);
+    unframe(&pack(bits))
+}
+
+/// `text` without the zero-width characters used by [`hide`].
+pub fn visible(text: &str) -> String {
+    text.chars().filter(|c| !ALPHABET.contains(c)).collect()
+}
+
+/// Hides `payload` in the text of the first iTXt entry with `keyword`, creating the entry with
+/// [`COVER_TEXT`] if there is none.
+pub fn embed(png: &mut Png, keyword: &str, payload: &[u8]) -> Result<(), CarrierError> {
+    match find(png, keyword)? {
+        Some((index, mut text)) => {
+            text.set_text(hide(text.text(), payload)?);
+            png.replace_chunk(index, text.to_chunk()?);
+        }
+        None => {
+            let text = InternationalText::new(keyword, &hide(COVER_TEXT, payload)?)?;
+            png.insert_before_end(text.to_chunk()?);
+        }
+    }
+    Ok(())
+}
+
+/// Reads back a payload hidden in the first iTXt entry with `keyword`.
+pub fn extract(png: &Png, keyword: &str) -> Result<Vec<u8>, CarrierError> {
+    let (_, text) = find(png, keyword)?.ok_or(CarrierError::NoPayload)?;
+    reveal(text.text())
+}
+
+/// Index and contents of the first iTXt chunk with `keyword`.
+fn find(png: &Png, keyword: &str) -> Result<Option<(usize, InternationalText)>, CarrierError> {
+    for (index, chunk) in png.chunks().iter().enumerate() {
+        if chunk.chunk_type() == &ChunkType::ITXT {
+            let text = InternationalText::try_from(chunk)?;
+            if text.keyword() == keyword {
+                return Ok(Some((index, text)));
+            }
+        }
+    }
+    Ok(None)
+}
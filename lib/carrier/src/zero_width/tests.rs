@@ -0,0 +1,95 @@
+use super::*;
+use png_spec::chunk::Chunk;
+use std::str::FromStr;
+
+fn png() -> Png {
+    let chunk = |t: &str| Chunk::new(ChunkType::from_str(t).unwrap(), Vec::new());
+    Png::from_chunks(vec![chunk("IHDR"), chunk("IDAT"), chunk("IEND")])
+}
+
+fn texts(png: &Png) -> Vec<InternationalText> {
+    png.chunks()
+        .iter()
+        .filter(|c| c.chunk_type() == &ChunkType::ITXT)
+        .map(|c| InternationalText::try_from(c).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_hide_reveal() {
+    let text = "A quiet afternoon by the lake 🌅";
+    let hidden = hide(text, b"meet at noon").unwrap();
+    assert_ne!(hidden, text);
+    assert_eq!(visible(&hidden), text);
+    assert_eq!(reveal(&hidden).unwrap(), b"meet at noon");
+    assert_eq!(hidden.len(), text.len() + hidden_length(12));
+
+    // only after whitespace and at the end
+    let end = hidden.trim_end_matches(ALPHABET);
+    assert!(end.len() < hidden.len());
+    let mut previous = ' ';
+    for c in end.chars() {
+        if ALPHABET.contains(&c) {
+            assert!(previous.is_whitespace() || ALPHABET.contains(&previous));
+        }
+        previous = c;
+    }
+
+    let again = hide(&hidden, b"changed").unwrap();
+    assert_eq!(visible(&again), text);
+    assert_eq!(reveal(&again).unwrap(), b"changed");
+    assert_eq!(reveal(&hide("", b"x").unwrap()).unwrap(), b"x");
+}
+
+#[test]
+fn test_existing_entry() {
+    let mut png = png();
+    let mut title = InternationalText::new("Title", "Lake").unwrap();
+    title.set_compressed(true);
+    let comment = InternationalText::new("Comment", "Taken on holiday").unwrap();
+    png.insert_before_end(title.to_chunk().unwrap());
+    png.insert_before_end(comment.to_chunk().unwrap());
+
+    embed(&mut png, "Comment", b"payload").unwrap();
+    let texts = texts(&png);
+    assert_eq!(texts.len(), 2);
+    assert_eq!(texts[0], title);
+    assert_eq!(texts[1].keyword(), "Comment");
+    assert_eq!(visible(texts[1].text()), "Taken on holiday");
+    assert_eq!(extract(&png, "Comment").unwrap(), b"payload");
+
+    embed(&mut png, "Title", b"compressed").unwrap();
+    let texts = self::texts(&png);
+    assert!(texts[0].is_compressed());
+    assert_eq!(extract(&png, "Title").unwrap(), b"compressed");
+}
+
+#[test]
+fn test_new_entry() {
+    let mut png = png();
+    embed(&mut png, DEFAULT_KEYWORD, &[0xee; 300]).unwrap();
+    let texts = texts(&png);
+    assert_eq!(texts.len(), 1);
+    assert_eq!(visible(texts[0].text()), COVER_TEXT);
+    assert_eq!(extract(&png, DEFAULT_KEYWORD).unwrap(), [0xee; 300]);
+    assert_eq!(png.chunks().last().unwrap().chunk_type(), &ChunkType::IEND);
+}
+
+#[test]
+fn test_no_payload() {
+    let mut png = png();
+    assert!(matches!(
+        extract(&png, "Comment"),
+        Err(CarrierError::NoPayload)
+    ));
+    let comment = InternationalText::new("Comment", "Nothing hidden").unwrap();
+    png.insert_before_end(comment.to_chunk().unwrap());
+    assert!(matches!(
+        extract(&png, "Comment"),
+        Err(CarrierError::NoPayload)
+    ));
+    assert!(matches!(
+        embed(&mut png, "", b""),
+        Err(CarrierError::Text(_))
+    ));
+}
@@ -0,0 +1,117 @@
+//! Image data stream embedding: the payload goes in the padding bits of empty stored deflate
+//! blocks at the start of the zlib stream. The stream inflates to the same bytes, so the pixels,
+//! their filters and their compression stay as they are; only the stream grows.
+//!
+//! A stored block starts with three header bits and then skips to the next byte boundary before
+//! its length. An empty block starting on a boundary is five bytes with five ignored bits, which
+//! is where the payload goes. Deflate allows any number of such blocks anywhere in the stream.
+//!
+//! ['Non-compressed blocks'](https://www.rfc-editor.org/rfc/rfc1951#section-3.2.4)
+
+use crate::{bits, frame, pack, unframe, CarrierError, LENGTH_PREFIX};
+use png_spec::{chunk_type::ChunkType, image::ImageError, png::Png};
+
+#[cfg(test)]
+mod tests;
+
+/// Payload bits in each block, above the three header bits.
+const BLOCK_BITS: usize = 5;
+
+/// An empty, non-final stored block: the header byte, LEN 0 and NLEN, its complement.
+const EMPTY_BLOCK: [u8; 5] = [0, 0x00, 0x00, 0xff, 0xff];
+
+/// Bytes the image data grows by to carry a payload of `length` bytes.
+pub fn stream_growth(length: usize) -> usize {
+    ((LENGTH_PREFIX + length) * 8).div_ceil(BLOCK_BITS) * EMPTY_BLOCK.len()
+}
+
+/// Hides `payload` in the image data stream of `png`, replacing any payload hidden there before.
+pub fn embed(png: &mut Png, payload: &[u8]) -> Result<(), CarrierError> {
+    let embedded = hide(&png.image_data(), payload)?;
+
+    // keep the chunk size the file already uses
+    let size = png
+        .chunks()
+        .iter()
+        .filter(|c| c.chunk_type() == &ChunkType::IDAT)
+        .map(|c| c.data_length())
+        .max()
+        .unwrap_or(Png::MAX_CHUNK_DATA_LENGTH)
+        .clamp(1, Png::MAX_CHUNK_DATA_LENGTH);
+    png.set_image_data(&embedded, size)
+        .map_err(ImageError::from)?;
+    Ok(())
+}
+
+/// Reads back a payload hidden in the image data stream of `png`.
+pub fn extract(png: &Png) -> Result<Vec<u8>, CarrierError> {
+    reveal(&png.image_data())
+}
+
+/// `stream` with `payload` hidden in it, replacing any payload hidden there before. Works on
+/// any zlib stream, not only image data.
+pub fn hide(stream: &[u8], payload: &[u8]) -> Result<Vec<u8>, CarrierError> {
+    let Stream { header, rest, .. } = Stream::split(stream)?;
+
+    let framed = bits(&frame(payload, None)?).collect::<Vec<bool>>();
+    let mut embedded = header.to_vec();
+    for group in framed.chunks(BLOCK_BITS) {
+        let padding = group
+            .iter()
+            .enumerate()
+            .fold(0, |byte, (i, &bit)| byte | (bit as u8) << (7 - i));
+        embedded.push(padding);
+        embedded.extend(&EMPTY_BLOCK[1..]);
+    }
+    embedded.extend(rest);
+    Ok(embedded)
+}
+
+/// Reads back a payload hidden in the zlib `stream` by [`hide`].
+pub fn reveal(stream: &[u8]) -> Result<Vec<u8>, CarrierError> {
+    let mut padding = Stream::split(stream)?.padding;
+    padding.truncate(padding.len() / 8 * 8);
+    unframe(&pack(padding))
+}
+
+/// A zlib stream split around the empty stored blocks at its start.
+struct Stream<'a> {
+    header: &'a [u8],
+    /// Padding bits of the empty stored blocks.
+    padding: Vec<bool>,
+    /// The rest of the stream, from the first other block to the checksum.
+    rest: &'a [u8],
+}
+
+impl<'a> Stream<'a> {
+    fn split(stream: &'a [u8]) -> Result<Stream<'a>, CarrierError> {
+        let (header, mut rest) = match stream {
+            [cmf, flg, ..]
+                if cmf & 0x0f == 8 && (*cmf as u16 * 256 + *flg as u16).is_multiple_of(31) =>
+            {
+                if flg & 0x20 != 0 {
+                    return Err(CarrierError::Stream(
+                        "preset dictionaries are not supported".to_string(),
+                    ));
+                }
+                stream.split_at(2)
+            }
+            _ => return Err(CarrierError::Stream("not a zlib stream".to_string())),
+        };
+
+        let mut padding = Vec::new();
+        while let [first, 0x00, 0x00, 0xff, 0xff, ..] = rest {
+            // bit 0 is the final block flag and bits 1 and 2 the block type, stored is 0
+            if first & 0b111 != 0 {
+                break;
+            }
+            padding.extend((0..BLOCK_BITS).map(|i| first >> (7 - i) & 1 == 1));
+            rest = &rest[EMPTY_BLOCK.len()..];
+        }
+        Ok(Stream {
+            header,
+            padding,
+            rest,
+        })
+    }
+}
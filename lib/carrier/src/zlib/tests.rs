@@ -0,0 +1,76 @@
+use super::*;
+use crate::fixture;
+use flate2::read::ZlibDecoder;
+use png_spec::image::{ColorType, Header, Image};
+use std::io::Read;
+
+fn png() -> Png {
+    let header = Header::new(32, 20, 8, ColorType::Rgba).unwrap();
+    let data = (0..header.row_bytes(32) * 20)
+        .map(|i| (i * 13 % 241) as u8)
+        .collect();
+    fixture::png(&Image::new(header, data).unwrap())
+}
+
+fn inflate(png: &Png) -> Vec<u8> {
+    let mut inflated = Vec::new();
+    ZlibDecoder::new(png.image_data().as_slice())
+        .read_to_end(&mut inflated)
+        .unwrap();
+    inflated
+}
+
+#[test]
+fn test_embed_extract() {
+    let mut png = png();
+    let original = png.image_data();
+    let inflated = inflate(&png);
+
+    embed(&mut png, b"between the blocks").unwrap();
+    assert_eq!(inflate(&png), inflated);
+    assert_eq!(
+        Image::from_png(&png).unwrap(),
+        Image::from_png(&self::png()).unwrap()
+    );
+    assert_eq!(
+        png.image_data().len(),
+        original.len() + stream_growth(b"between the blocks".len())
+    );
+    assert!(png.image_data().ends_with(&original[2..]));
+    assert_eq!(extract(&png).unwrap(), b"between the blocks");
+}
+
+#[test]
+fn test_replaces_previous_payload() {
+    let mut png = png();
+    let original = png.image_data().len();
+    embed(&mut png, &[7; 100]).unwrap();
+    embed(&mut png, b"second").unwrap();
+    assert_eq!(extract(&png).unwrap(), b"second");
+    assert_eq!(png.image_data().len(), original + stream_growth(6));
+}
+
+#[test]
+fn test_keeps_chunk_size() {
+    let mut png = png();
+    png.split_idat(100).unwrap();
+    embed(&mut png, &[1; 50]).unwrap();
+    assert!(png.idat_count() > 1);
+    for chunk in png.chunks() {
+        if chunk.chunk_type() == &ChunkType::IDAT {
+            assert!(chunk.data_length() <= 100);
+        }
+    }
+}
+
+#[test]
+fn test_no_payload() {
+    let png = png();
+    assert!(matches!(extract(&png), Err(CarrierError::NoPayload)));
+
+    let mut png = png;
+    png.set_image_data(&[0x78, 0x00, 0x01], 100).unwrap();
+    assert!(matches!(extract(&png), Err(CarrierError::Stream(_))));
+    png.set_image_data(&[0x78, 0xbb, 0x01], 100).unwrap();
+    assert!(matches!(embed(&mut png, b""), Err(CarrierError::Stream(_))));
+}
@@ -0,0 +1,54 @@
+//! Arithmetic in GF(2^8) with the polynomial x^8 + x^4 + x^3 + x^2 + 1 (0x11d) and generator 2,
+//! shared by the Reed–Solomon code of the carriers and the secret sharing of the payloads.
+//! Addition and subtraction are both XOR.
+
+#[cfg(test)]
+mod tests;
+
+/// Exponent and logarithm tables. The exponents repeat so products of two logarithms need no
+/// reduction.
+struct Field {
+    exp: [u8; 512],
+    log: [u8; 256],
+}
+
+const FIELD: Field = {
+    let mut exp = [0; 512];
+    let mut log = [0; 256];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= 0x11d;
+        }
+        i += 1;
+    }
+    while i < 512 {
+        exp[i] = exp[i - 255];
+        i += 1;
+    }
+    Field { exp, log }
+};
+
+pub fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    FIELD.exp[FIELD.log[a as usize] as usize + FIELD.log[b as usize] as usize]
+}
+
+pub fn div(a: u8, b: u8) -> u8 {
+    debug_assert!(b != 0, "division by zero");
+    if a == 0 {
+        return 0;
+    }
+    FIELD.exp[FIELD.log[a as usize] as usize + 255 - FIELD.log[b as usize] as usize]
+}
+
+/// 2 to the power `n`.
+pub fn pow2(n: usize) -> u8 {
+    FIELD.exp[n % 255]
+}
@@ -0,0 +1,11 @@
+use super::*;
+
+#[test]
+fn test_field() {
+    for a in 1..=255u8 {
+        assert_eq!(mul(a, div(1, a)), 1);
+        assert_eq!(div(mul(a, 0x53), 0x53), a);
+    }
+    assert_eq!(mul(0, 9), 0);
+    assert_eq!(pow2(8), 0x1d);
+}
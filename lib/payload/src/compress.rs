@@ -0,0 +1,180 @@
+//! Compression of a payload.
+//!
+//! | Field         | Size    |                               |
+//! |---------------|---------|-------------------------------|
+//! | Magic         | 4 bytes | `PMcz`                        |
+//! | Version       | 1 byte  | 1                             |
+//! | Algorithm     | 1 byte  | 1 zlib, 2 zstd, 3 Brotli      |
+//! | Original size | 8 bytes |                               |
+//! | Data          | rest    |                               |
+//!
+//! The original size comes from the payload and is not authenticated, so payloads larger than
+//! [`MAX_SIZE`] are refused both ways, and decompression stops one byte past the original size
+//! instead of expanding a crafted payload without bound.
+
+use crate::{read_array, PayloadError};
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    str::FromStr,
+};
+
+#[cfg(test)]
+mod tests;
+
+const MAGIC: [u8; 4] = *b"PMcz";
+const VERSION: u8 = 1;
+/// Bytes compression adds in front of the compressed data.
+pub const HEADER_LENGTH: usize = 4 + 1 + 1 + 8;
+/// Largest payload compression takes, and so the most memory decompression uses.
+pub const MAX_SIZE: u64 = 1 << 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Zlib,
+    Zstd,
+    Brotli,
+}
+
+impl Compression {
+    pub const ALL: [Compression; 3] = [Compression::Zlib, Compression::Zstd, Compression::Brotli];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::Zlib => "zlib",
+            Compression::Zstd => "zstd",
+            Compression::Brotli => "brotli",
+        }
+    }
+
+    fn id(&self) -> u8 {
+        match self {
+            Compression::Zlib => 1,
+            Compression::Zstd => 2,
+            Compression::Brotli => 3,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Compression, PayloadError> {
+        Compression::ALL
+            .into_iter()
+            .find(|c| c.id() == id)
+            .ok_or(PayloadError::UnknownCompression(id))
+    }
+
+    /// Compresses with the best ratio each algorithm offers; payloads are small.
+    fn compress(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::Zlib => {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Compression::Zstd => zstd::encode_all(data, 19),
+            Compression::Brotli => {
+                let mut compressed = Vec::new();
+                let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+                encoder.write_all(data)?;
+                drop(encoder);
+                Ok(compressed)
+            }
+        }
+    }
+
+    fn decoder<'a>(&self, data: &'a [u8]) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Compression::Zlib => Box::new(flate2::read::ZlibDecoder::new(data)),
+            Compression::Zstd => Box::new(zstd::Decoder::new(data)?),
+            Compression::Brotli => Box::new(brotli::Decompressor::new(data, 4096)),
+        })
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Compression {
+    type Err = PayloadError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Compression::ALL
+            .into_iter()
+            .find(|c| c.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| PayloadError::UnknownCompressionName(s.to_string()))
+    }
+}
+
+/// Whether `data` starts like a compressed payload.
+pub fn is_compressed(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// Compresses `data` with `compression`, even if that makes it larger.
+pub fn compress(data: &[u8], compression: Compression) -> Result<Vec<u8>, PayloadError> {
+    if data.len() as u64 > MAX_SIZE {
+        return Err(PayloadError::CompressedSize(data.len() as u64));
+    }
+    let compressed = compression.compress(data)?;
+    let mut payload = Vec::with_capacity(HEADER_LENGTH + compressed.len());
+    payload.extend(MAGIC);
+    payload.push(VERSION);
+    payload.push(compression.id());
+    payload.extend((data.len() as u64).to_be_bytes());
+    payload.extend(compressed);
+    Ok(payload)
+}
+
+/// Compresses `data` with whichever algorithm makes it smallest, or returns it unchanged if
+/// none shrinks it or it is larger than [`MAX_SIZE`].
+pub fn compress_auto(data: &[u8]) -> Result<Vec<u8>, PayloadError> {
+    let mut smallest = data.to_vec();
+    if data.len() as u64 > MAX_SIZE {
+        return Ok(smallest);
+    }
+    for compression in Compression::ALL {
+        let compressed = compress(data, compression)?;
+        if compressed.len() < smallest.len() {
+            smallest = compressed;
+        }
+    }
+    Ok(smallest)
+}
+
+/// Algorithm of a compressed payload.
+pub fn compression(data: &[u8]) -> Result<Compression, PayloadError> {
+    Compression::from_id(*data.get(5).ok_or(PayloadError::Truncated)?)
+}
+
+/// Decompresses a payload made by [`compress`].
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, PayloadError> {
+    if !is_compressed(data) || data.len() < HEADER_LENGTH {
+        return Err(PayloadError::Truncated);
+    }
+    let version = data[4];
+    if version != VERSION {
+        return Err(PayloadError::UnsupportedVersion(version));
+    }
+    let compression = compression(data)?;
+    let size = u64::from_be_bytes(read_array(data, 6)?);
+    if size > MAX_SIZE {
+        return Err(PayloadError::CompressedSize(size));
+    }
+
+    // one byte more than expected is enough to tell the size is wrong
+    let mut decompressed = Vec::new();
+    compression
+        .decoder(&data[HEADER_LENGTH..])?
+        .take(size + 1)
+        .read_to_end(&mut decompressed)?;
+    if decompressed.len() as u64 != size {
+        return Err(PayloadError::SizeMismatch {
+            expected: size,
+            actual: decompressed.len() as u64,
+        });
+    }
+    Ok(decompressed)
+}
@@ -0,0 +1,85 @@
+use super::*;
+
+fn text() -> Vec<u8> {
+    br#"{"message": "meet at the usual place", "time": "noon"}"#.repeat(20)
+}
+
+#[test]
+fn test_round_trip() {
+    let text = text();
+    for compression in Compression::ALL {
+        let compressed = compress(&text, compression).unwrap();
+        assert!(is_compressed(&compressed));
+        assert!(compressed.len() < text.len());
+        assert_eq!(super::compression(&compressed).unwrap(), compression);
+        assert_eq!(decompress(&compressed).unwrap(), text);
+    }
+}
+
+#[test]
+fn test_auto_compresses_text() {
+    let text = text();
+    let compressed = compress_auto(&text).unwrap();
+    assert!(is_compressed(&compressed));
+    assert_eq!(decompress(&compressed).unwrap(), text);
+}
+
+#[test]
+fn test_auto_keeps_incompressible() {
+    // short and high entropy, so the header alone makes compression lose
+    let data: Vec<u8> = (0..32u32).map(|i| (i * 151 + 7) as u8).collect();
+    assert_eq!(compress_auto(&data).unwrap(), data);
+}
+
+#[test]
+fn test_size_mismatch() {
+    let mut compressed = compress(&text(), Compression::Zstd).unwrap();
+    compressed[6..14].copy_from_slice(&10u64.to_be_bytes());
+    assert!(matches!(
+        decompress(&compressed),
+        Err(PayloadError::SizeMismatch {
+            expected: 10,
+            actual: 11
+        })
+    ));
+}
+
+#[test]
+fn test_size_limit() {
+    let mut compressed = compress(&text(), Compression::Zlib).unwrap();
+    for size in [MAX_SIZE + 1, u64::MAX] {
+        compressed[6..14].copy_from_slice(&size.to_be_bytes());
+        assert!(matches!(
+            decompress(&compressed),
+            Err(PayloadError::CompressedSize(s)) if s == size
+        ));
+    }
+}
+
+#[test]
+fn test_corrupt_data() {
+    let mut compressed = compress(&text(), Compression::Zlib).unwrap();
+    compressed.truncate(HEADER_LENGTH + 5);
+    assert!(matches!(decompress(&compressed), Err(PayloadError::Io(_))));
+}
+
+#[test]
+fn test_unknown_algorithm() {
+    let mut compressed = compress(b"data", Compression::Brotli).unwrap();
+    compressed[5] = 9;
+    assert!(matches!(
+        decompress(&compressed),
+        Err(PayloadError::UnknownCompression(9))
+    ));
+}
+
+#[test]
+fn test_compression_from_str() {
+    for compression in Compression::ALL {
+        assert_eq!(
+            compression.to_string().parse::<Compression>().unwrap(),
+            compression
+        );
+    }
+    assert!("lzma".parse::<Compression>().is_err());
+}
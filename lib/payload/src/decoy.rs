@@ -0,0 +1,186 @@
+//! Decoy encryption: several messages, each sealed under its own password, in slots of one size.
+//! A password opens only its own slot, found by trying its key on each one, and slots without a
+//! message are random bytes, so the payload does not tell how many messages it holds.
+//!
+//! | Field         | Size     |                                    |
+//! |---------------|----------|------------------------------------|
+//! | Magic         | 4 bytes  | `PMdc`                             |
+//! | Version       | 1 byte   | 1                                  |
+//! | Cipher        | 1 byte   | 1 ChaCha20-Poly1305, 2 AES-256-GCM |
+//! | Memory cost   | 4 bytes  | Argon2id, KiB                      |
+//! | Time cost     | 4 bytes  | Argon2id, iterations               |
+//! | Parallelism   | 4 bytes  | Argon2id, lanes                    |
+//! | Salt          | 16 bytes |                                    |
+//! | Slot count    | 1 byte   | 1 to 255                           |
+//! | Slot length   | 4 bytes  |                                    |
+//! | Slots         | rest     |                                    |
+//!
+//! Every password derives its key from the one salt, so opening costs a single derivation
+//! whatever the slot count. Each slot is a nonce and the ciphertext of the message length as 4
+//! big endian bytes, the message and zero padding. The padding makes every message as long as the
+//! longest, rounded up to [`PADDING_BLOCK`]. The header, followed by any associated data the caller gives, is
+//! authenticated as associated data of every slot.
+
+use crate::{
+    passphrase::{Cipher, KdfParams, NONCE_LENGTH, SALT_LENGTH, TAG_LENGTH},
+    read_array, PayloadError,
+};
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng, Payload};
+
+#[cfg(test)]
+mod tests;
+
+const MAGIC: [u8; 4] = *b"PMdc";
+const VERSION: u8 = 1;
+const HEADER_LENGTH: usize = 4 + 1 + 1 + 4 * 3 + SALT_LENGTH + 1 + 4;
+
+/// Slots used when the count is not chosen. Keeping to one count keeps payloads alike.
+pub const DEFAULT_SLOTS: usize = 4;
+
+/// Messages are padded to a multiple of this many bytes, so lengths only show roughly.
+pub const PADDING_BLOCK: usize = 64;
+
+/// Whether `data` starts like a payload with decoys.
+pub fn is_decoy(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// Length of a sealed payload with `slots` slots for messages of at most `length` bytes.
+pub fn sealed_length(slots: usize, length: usize) -> usize {
+    HEADER_LENGTH + slots * slot_length(length)
+}
+
+fn slot_length(length: usize) -> usize {
+    NONCE_LENGTH + (4 + length).next_multiple_of(PADDING_BLOCK) + TAG_LENGTH
+}
+
+/// Seals every message under its password, each in a slot picked at random out of `slots`.
+/// The slots left over are filled with random bytes. `associated_data` is authenticated but not
+/// stored, and must be given again to open any slot.
+pub fn seal(
+    messages: &[(&[u8], &[u8])],
+    associated_data: &[u8],
+    slots: usize,
+    cipher: Cipher,
+    params: KdfParams,
+) -> Result<Vec<u8>, PayloadError> {
+    if messages.is_empty() || slots < messages.len() || slots > u8::MAX as usize {
+        return Err(PayloadError::SlotCount {
+            messages: messages.len(),
+            slots,
+        });
+    }
+    for (i, (_, password)) in messages.iter().enumerate() {
+        if messages[..i].iter().any(|(_, other)| other == password) {
+            return Err(PayloadError::DuplicatePassword);
+        }
+    }
+    let longest = messages.iter().map(|(m, _)| m.len()).max().unwrap_or(0);
+    let length = slot_length(longest);
+    let plaintext_length = length - NONCE_LENGTH - TAG_LENGTH;
+    let mut salt = [0; SALT_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+
+    let mut sealed = Vec::with_capacity(sealed_length(slots, longest));
+    sealed.extend(MAGIC);
+    sealed.push(VERSION);
+    sealed.push(cipher.id());
+    sealed.extend(params.memory_cost.to_be_bytes());
+    sealed.extend(params.time_cost.to_be_bytes());
+    sealed.extend(params.parallelism.to_be_bytes());
+    sealed.extend(salt);
+    sealed.push(slots as u8);
+    sealed.extend((length as u32).to_be_bytes());
+    let aad = [&sealed, associated_data].concat();
+
+    let mut order: Vec<usize> = (0..slots).collect();
+    for i in (1..slots).rev() {
+        order.swap(i, below(i as u32 + 1) as usize);
+    }
+    let mut contents = vec![None; slots];
+    for (&slot, message) in order.iter().zip(messages) {
+        contents[slot] = Some(message);
+    }
+
+    for content in contents {
+        let mut slot = vec![0; length];
+        OsRng.fill_bytes(&mut slot);
+        if let Some((message, password)) = content {
+            let nonce: [u8; NONCE_LENGTH] = slot[..NONCE_LENGTH].try_into().unwrap();
+            let key = params.derive(password, &salt)?;
+            let mut plaintext = Vec::with_capacity(plaintext_length);
+            plaintext.extend((message.len() as u32).to_be_bytes());
+            plaintext.extend(*message);
+            plaintext.resize(plaintext_length, 0);
+            let payload = Payload {
+                msg: &plaintext,
+                aad: &aad,
+            };
+            let ciphertext = cipher.apply(&key, &nonce, payload, true)?;
+            slot.truncate(NONCE_LENGTH);
+            slot.extend(ciphertext);
+        }
+        sealed.extend(slot);
+    }
+    Ok(sealed)
+}
+
+/// A uniformly distributed random number below `n`.
+fn below(n: u32) -> u32 {
+    // rejects the values that would make the low numbers more likely
+    let zone = u32::MAX - u32::MAX % n;
+    loop {
+        let value = OsRng.next_u32();
+        if value < zone {
+            return value % n;
+        }
+    }
+}
+
+/// Opens the slot sealed under `password` by [`seal`] with the same `associated_data`. A
+/// password matching no slot, or other associated data, is reported as [`PayloadError::Decrypt`].
+pub fn open(
+    sealed: &[u8],
+    associated_data: &[u8],
+    password: &[u8],
+) -> Result<Vec<u8>, PayloadError> {
+    if !is_decoy(sealed) || sealed.len() < HEADER_LENGTH {
+        return Err(PayloadError::Truncated);
+    }
+    let version = sealed[4];
+    if version != VERSION {
+        return Err(PayloadError::UnsupportedVersion(version));
+    }
+    let cipher = Cipher::from_id(sealed[5])?;
+    let params = KdfParams {
+        memory_cost: u32::from_be_bytes(read_array(sealed, 6)?),
+        time_cost: u32::from_be_bytes(read_array(sealed, 10)?),
+        parallelism: u32::from_be_bytes(read_array(sealed, 14)?),
+    };
+    let salt = &sealed[18..18 + SALT_LENGTH];
+    let count = sealed[34] as usize;
+    let length = u32::from_be_bytes(read_array(sealed, 35)?) as usize;
+    if length < NONCE_LENGTH + 4 + TAG_LENGTH || sealed.len() < HEADER_LENGTH + count * length {
+        return Err(PayloadError::Truncated);
+    }
+
+    let (header, slots) = sealed.split_at(HEADER_LENGTH);
+    let aad = [header, associated_data].concat();
+    let key = params.derive(password, salt)?;
+    for slot in slots[..count * length].chunks_exact(length) {
+        let (nonce, ciphertext) = slot.split_at(NONCE_LENGTH);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: &aad,
+        };
+        let Ok(plaintext) = cipher.apply(&key, nonce.try_into().unwrap(), payload, false) else {
+            continue;
+        };
+        let message_length = u32::from_be_bytes(read_array(&plaintext, 0)?) as usize;
+        return plaintext
+            .get(4..4 + message_length)
+            .map(<[u8]>::to_vec)
+            .ok_or(PayloadError::Truncated);
+    }
+    Err(PayloadError::Decrypt)
+}
@@ -0,0 +1,141 @@
+use super::*;
+
+/// Cheap parameters so the tests stay fast
+const PARAMS: KdfParams = KdfParams {
+    memory_cost: 64,
+    time_cost: 1,
+    parallelism: 1,
+};
+
+fn seal_two(slots: usize) -> Vec<u8> {
+    let messages: [(&[u8], &[u8]); 2] = [
+        (b"the real plans", b"correct horse"),
+        (b"grocery list", b"hunter2"),
+    ];
+    seal(&messages, b"", slots, Cipher::default(), PARAMS).unwrap()
+}
+
+#[test]
+fn test_each_password_opens_its_message() {
+    for cipher in Cipher::ALL {
+        let messages: [(&[u8], &[u8]); 3] = [(b"one", b"a"), (b"two", b"b"), (b"", b"c")];
+        let sealed = seal(&messages, b"", 5, cipher, PARAMS).unwrap();
+        assert!(is_decoy(&sealed));
+        for (message, password) in messages {
+            assert_eq!(open(&sealed, b"", password).unwrap(), message);
+        }
+    }
+}
+
+#[test]
+fn test_associated_data() {
+    let messages: [(&[u8], &[u8]); 1] = [(b"secret", b"pw")];
+    let sealed = seal(&messages, b"header", 2, Cipher::default(), PARAMS).unwrap();
+    assert_eq!(open(&sealed, b"header", b"pw").unwrap(), b"secret");
+    assert!(matches!(
+        open(&sealed, b"headed", b"pw"),
+        Err(PayloadError::Decrypt)
+    ));
+}
+
+#[test]
+fn test_wrong_password() {
+    let sealed = seal_two(DEFAULT_SLOTS);
+    assert!(matches!(
+        open(&sealed, b"", b"guess"),
+        Err(PayloadError::Decrypt)
+    ));
+}
+
+#[test]
+fn test_length_does_not_tell_the_count() {
+    let one = seal(
+        &[(&b"the real plans"[..], &b"pw"[..])],
+        b"",
+        DEFAULT_SLOTS,
+        Cipher::default(),
+        PARAMS,
+    )
+    .unwrap();
+    let two = seal_two(DEFAULT_SLOTS);
+    assert_eq!(one.len(), two.len());
+    // the headers differ only in the salt
+    assert_eq!(one[..18], two[..18]);
+    assert_eq!(one[34..HEADER_LENGTH], two[34..HEADER_LENGTH]);
+    assert_eq!(one.len(), sealed_length(DEFAULT_SLOTS, 14));
+    assert_eq!(sealed_length(4, 60), HEADER_LENGTH + 4 * (12 + 64 + 16));
+}
+
+#[test]
+fn test_slot_count() {
+    let messages: [(&[u8], &[u8]); 2] = [(b"one", b"a"), (b"two", b"b")];
+    for slots in [0, 1, 256] {
+        assert!(matches!(
+            seal(&messages, b"", slots, Cipher::default(), PARAMS),
+            Err(PayloadError::SlotCount { messages: 2, .. })
+        ));
+    }
+    assert!(matches!(
+        seal(&[], b"", 4, Cipher::default(), PARAMS),
+        Err(PayloadError::SlotCount { messages: 0, .. })
+    ));
+}
+
+#[test]
+fn test_duplicate_password() {
+    let messages: [(&[u8], &[u8]); 2] = [(b"one", b"same"), (b"two", b"same")];
+    assert!(matches!(
+        seal(&messages, b"", 4, Cipher::default(), PARAMS),
+        Err(PayloadError::DuplicatePassword)
+    ));
+}
+
+#[test]
+fn test_tampered_header() {
+    let mut sealed = seal_two(3);
+    // one more iteration still parses, but no longer matches the authenticated header
+    sealed[13] += 1;
+    assert!(matches!(
+        open(&sealed, b"", b"hunter2"),
+        Err(PayloadError::Decrypt)
+    ));
+
+    let sealed = seal_two(3);
+    assert!(matches!(
+        open(&sealed[..sealed.len() - 1], b"", b"hunter2"),
+        Err(PayloadError::Truncated)
+    ));
+}
+
+#[test]
+fn test_one_derivation() {
+    // a derivation per slot at these costs would take far longer than the test is allowed
+    let mut sealed = seal_two(255);
+    sealed[6..10].copy_from_slice(&(1u32 << 14).to_be_bytes());
+    let start = std::time::Instant::now();
+    assert!(matches!(
+        open(&sealed, b"", b"guess"),
+        Err(PayloadError::Decrypt)
+    ));
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[test]
+fn test_excessive_costs() {
+    // time cost and parallelism are checked before any slot is tried
+    for offset in [10, 14] {
+        let mut sealed = seal_two(3);
+        sealed[offset..offset + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            open(&sealed, b"", b"hunter2"),
+            Err(PayloadError::KdfParams(_))
+        ));
+    }
+}
+
+#[test]
+fn test_below() {
+    for n in [1, 2, 3, 255] {
+        assert!((0..100).all(|_| below(n) < n));
+    }
+}
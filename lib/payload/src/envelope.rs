@@ -0,0 +1,280 @@
+//! The envelope every payload chunk starts with, describing its body.
+//!
+//! | Field          | Size     |                                               |
+//! |----------------|----------|-----------------------------------------------|
+//! | Magic          | 4 bytes  | `PNGm`                                        |
+//! | Version        | 1 byte   | 1                                             |
+//! | Flags          | 1 byte   | see [`Flags`], other bits must be 0           |
+//! | Content type   | 1 byte   | see [`ContentType`]                           |
+//! | Timestamp      | 8 bytes  | seconds since the Unix epoch, 0 if not kept   |
+//! | Label length   | 2 bytes  | 0 if there is no label                        |
+//! | Label          | variable | UTF-8                                         |
+//! | Body           | rest     |                                               |
+//!
+//! The flags list the layers wrapped around the content, outermost first:
+//!
+//! 1. multipart: the body is one part of the payload, see [`crate::multipart`]. The
+//!    payload is joined from the bodies of every part and is described by the remaining flags.
+//! 2. shared: the body is one Shamir share of the payload, see [`crate::share`]. The payload is
+//!    rebuilt from enough shares, usually kept in different files.
+//! 3. signed: see [`crate::signature`]
+//! 4. encrypted: with a passphrase, with decoys or to recipients, see [`crate::passphrase`],
+//!    [`crate::decoy`] and [`crate::recipient`]
+//! 5. compressed: see [`crate::compress`]
+//!
+//! Each layer has its own header, so the flags only say which layers to expect.
+//!
+//! The envelope itself is in the clear. Its header, without the body and without the flags of
+//! the signed, multipart and shared layers added around the content later, is the
+//! [`Envelope::associated_data`] of the encryption and signature layers, so changing the content
+//! type, timestamp, label or the compressed and encrypted flags breaks them.
+
+use crate::{read_array, PayloadError};
+use std::{fmt, ops::BitOr};
+
+#[cfg(test)]
+mod tests;
+
+const MAGIC: [u8; 4] = *b"PNGm";
+const VERSION: u8 = 1;
+
+/// Bytes the envelope adds in front of the body, not counting the label.
+pub const HEADER_LENGTH: usize = 4 + 1 + 1 + 1 + 8 + 2;
+
+/// Layers wrapped around the content of an envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Flags(u8);
+
+impl Flags {
+    pub const NONE: Flags = Flags(0);
+    pub const COMPRESSED: Flags = Flags(1);
+    pub const ENCRYPTED: Flags = Flags(1 << 1);
+    pub const SIGNED: Flags = Flags(1 << 2);
+    pub const MULTIPART: Flags = Flags(1 << 3);
+    pub const SHARED: Flags = Flags(1 << 4);
+
+    const ALL: Flags = Flags(0b1_1111);
+    /// Layers that are in place when the payload is encrypted or signed.
+    const AUTHENTICATED: Flags = Flags(Flags::COMPRESSED.0 | Flags::ENCRYPTED.0);
+
+    pub fn contains(&self, other: Flags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: Flags) {
+        self.0 |= other.0
+    }
+
+    pub fn remove(&mut self, other: Flags) {
+        self.0 &= !other.0
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+}
+
+impl BitOr for Flags {
+    type Output = Flags;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Flags(self.0 | rhs.0)
+    }
+}
+
+impl fmt::Display for Flags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = [
+            (Flags::COMPRESSED, "compressed"),
+            (Flags::ENCRYPTED, "encrypted"),
+            (Flags::SIGNED, "signed"),
+            (Flags::MULTIPART, "multipart"),
+            (Flags::SHARED, "shared"),
+        ];
+        let set: Vec<_> = names
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| *name)
+            .collect();
+        if set.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&set.join(", "))
+        }
+    }
+}
+
+/// What the content of an envelope is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentType {
+    /// Uninterpreted bytes
+    Binary,
+    /// A UTF-8 text message
+    Text,
+    /// A file with its name and MIME type, see [`crate::file`]
+    File,
+}
+
+impl ContentType {
+    fn id(&self) -> u8 {
+        match self {
+            ContentType::Binary => 0,
+            ContentType::Text => 1,
+            ContentType::File => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<ContentType, PayloadError> {
+        match id {
+            0 => Ok(ContentType::Binary),
+            1 => Ok(ContentType::Text),
+            2 => Ok(ContentType::File),
+            id => Err(PayloadError::UnknownContentType(id)),
+        }
+    }
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ContentType::Binary => "binary",
+            ContentType::Text => "text",
+            ContentType::File => "file",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    flags: Flags,
+    content_type: ContentType,
+    timestamp: u64,
+    label: Option<String>,
+    body: Vec<u8>,
+}
+
+impl Envelope {
+    /// An envelope with no flags, timestamp or label.
+    pub fn new(content_type: ContentType, body: Vec<u8>) -> Envelope {
+        Envelope {
+            flags: Flags::NONE,
+            content_type,
+            timestamp: 0,
+            label: None,
+            body,
+        }
+    }
+
+    pub fn flags(&self) -> Flags {
+        self.flags
+    }
+
+    pub fn set_flags(&mut self, flags: Flags) {
+        self.flags = flags
+    }
+
+    pub fn content_type(&self) -> ContentType {
+        self.content_type
+    }
+
+    /// Seconds since the Unix epoch, 0 if not kept.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn set_timestamp(&mut self, timestamp: u64) {
+        self.timestamp = timestamp
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Labels are at most 65535 bytes. An empty label is the same as none.
+    pub fn set_label(&mut self, label: Option<String>) -> Result<(), PayloadError> {
+        if let Some(label) = &label {
+            if label.len() > u16::MAX as usize {
+                return Err(PayloadError::FieldLength("label", label.len()));
+            }
+        }
+        self.label = label.filter(|l| !l.is_empty());
+        Ok(())
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn set_body(&mut self, body: Vec<u8>) {
+        self.body = body
+    }
+
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+
+    /// The header with only the compressed and encrypted flags, for the encryption and
+    /// signature layers to authenticate. Set everything else before sealing the body.
+    pub fn associated_data(&self) -> Vec<u8> {
+        self.header(Flags(self.flags.0 & Flags::AUTHENTICATED.0))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.header(self.flags);
+        bytes.extend(&self.body);
+        bytes
+    }
+
+    fn header(&self, flags: Flags) -> Vec<u8> {
+        let label = self.label.as_deref().unwrap_or_default();
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + label.len());
+        bytes.extend(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(flags.bits());
+        bytes.push(self.content_type.id());
+        bytes.extend(self.timestamp.to_be_bytes());
+        bytes.extend((label.len() as u16).to_be_bytes());
+        bytes.extend(label.as_bytes());
+        bytes
+    }
+}
+
+impl TryFrom<&[u8]> for Envelope {
+    type Error = PayloadError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if !is_envelope(value) {
+            return Err(PayloadError::NotAnEnvelope);
+        }
+        let version = *value.get(4).ok_or(PayloadError::Truncated)?;
+        if version != VERSION {
+            return Err(PayloadError::UnsupportedVersion(version));
+        }
+        let flags = *value.get(5).ok_or(PayloadError::Truncated)?;
+        if flags & !Flags::ALL.0 != 0 {
+            return Err(PayloadError::UnknownFlags(flags));
+        }
+        let content_type = ContentType::from_id(*value.get(6).ok_or(PayloadError::Truncated)?)?;
+        let timestamp = u64::from_be_bytes(read_array(value, 7)?);
+
+        let label_length = u16::from_be_bytes(read_array(value, 15)?) as usize;
+        let label = value
+            .get(17..17 + label_length)
+            .ok_or(PayloadError::Truncated)?;
+        let label =
+            String::from_utf8(label.to_vec()).map_err(|_| PayloadError::Encoding("label"))?;
+
+        Ok(Envelope {
+            flags: Flags(flags),
+            content_type,
+            timestamp,
+            label: Some(label).filter(|l| !l.is_empty()),
+            body: value[17 + label_length..].to_vec(),
+        })
+    }
+}
+
+/// Whether `data` starts with an envelope. Chunks that do not are legacy payloads without one.
+pub fn is_envelope(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
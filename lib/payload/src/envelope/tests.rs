@@ -0,0 +1,109 @@
+use super::*;
+
+fn envelope() -> Envelope {
+    let mut envelope = Envelope::new(ContentType::Text, b"hello".to_vec());
+    envelope.set_flags(Flags::COMPRESSED | Flags::SIGNED);
+    envelope.set_timestamp(1_700_000_000);
+    envelope
+        .set_label(Some("status report".to_string()))
+        .unwrap();
+    envelope
+}
+
+#[test]
+fn test_round_trip() {
+    let envelope = envelope();
+    let bytes = envelope.to_bytes();
+    assert!(is_envelope(&bytes));
+    assert_eq!(bytes.len(), HEADER_LENGTH + 13 + 5);
+
+    let parsed = Envelope::try_from(bytes.as_slice()).unwrap();
+    assert_eq!(parsed, envelope);
+    assert_eq!(parsed.label(), Some("status report"));
+    assert_eq!(parsed.timestamp(), 1_700_000_000);
+    assert!(parsed.flags().contains(Flags::SIGNED));
+    assert!(!parsed.flags().contains(Flags::ENCRYPTED));
+}
+
+#[test]
+fn test_associated_data() {
+    let mut envelope = envelope();
+    let data = envelope.associated_data();
+    assert_eq!(data.len(), HEADER_LENGTH + 13);
+    assert_eq!(data[5], Flags::COMPRESSED.bits());
+
+    // layers added after sealing leave it alone, the rest of the header does not
+    envelope.set_flags(envelope.flags() | Flags::MULTIPART | Flags::SHARED);
+    envelope.set_body(b"part".to_vec());
+    assert_eq!(envelope.associated_data(), data);
+    envelope.set_timestamp(0);
+    assert_ne!(envelope.associated_data(), data);
+}
+
+#[test]
+fn test_no_label() {
+    let mut envelope = Envelope::new(ContentType::File, vec![]);
+    envelope.set_label(Some(String::new())).unwrap();
+    assert_eq!(envelope.label(), None);
+
+    let parsed = Envelope::try_from(envelope.to_bytes().as_slice()).unwrap();
+    assert_eq!(parsed.label(), None);
+    assert!(parsed.body().is_empty());
+}
+
+#[test]
+fn test_unsupported_version() {
+    let mut bytes = envelope().to_bytes();
+    bytes[4] = 2;
+    assert!(matches!(
+        Envelope::try_from(bytes.as_slice()),
+        Err(PayloadError::UnsupportedVersion(2))
+    ));
+}
+
+#[test]
+fn test_unknown_flags() {
+    let mut bytes = envelope().to_bytes();
+    bytes[5] |= 0x80;
+    assert!(matches!(
+        Envelope::try_from(bytes.as_slice()),
+        Err(PayloadError::UnknownFlags(_))
+    ));
+}
+
+#[test]
+fn test_unknown_content_type() {
+    let mut bytes = envelope().to_bytes();
+    bytes[6] = 42;
+    assert!(matches!(
+        Envelope::try_from(bytes.as_slice()),
+        Err(PayloadError::UnknownContentType(42))
+    ));
+}
+
+#[test]
+fn test_truncated_label() {
+    let bytes = envelope().to_bytes();
+    assert!(matches!(
+        Envelope::try_from(&bytes[..20]),
+        Err(PayloadError::Truncated)
+    ));
+}
+
+#[test]
+fn test_legacy_payload() {
+    assert!(!is_envelope(b"plain message"));
+    assert!(matches!(
+        Envelope::try_from(&b"plain message"[..]),
+        Err(PayloadError::NotAnEnvelope)
+    ));
+}
+
+#[test]
+fn test_flags_display() {
+    assert_eq!(Flags::NONE.to_string(), "none");
+    assert_eq!(
+        (Flags::ENCRYPTED | Flags::MULTIPART).to_string(),
+        "encrypted, multipart"
+    );
+}
@@ -0,0 +1,202 @@
+use std::{error, fmt, io};
+
+#[derive(Debug)]
+pub enum PayloadError {
+    /// The payload ends before its header does.
+    Truncated,
+    /// The payload does not start with an envelope.
+    NotAnEnvelope,
+    /// Envelope flags this library does not know.
+    UnknownFlags(u8),
+    UnknownContentType(u8),
+    /// The payload format version is newer than this library.
+    UnsupportedVersion(u8),
+    UnknownCipher(u8),
+    UnknownCipherName(String),
+    /// Key derivation parameters are invalid or too expensive to accept.
+    KdfParams(String),
+    /// A header field is too long, with the field name and its length.
+    FieldLength(&'static str, usize),
+    /// A header field is not valid UTF-8, or not ASCII where required.
+    Encoding(&'static str),
+    /// The header size of an embedded file does not match its contents.
+    SizeMismatch {
+        expected: u64,
+        actual: u64,
+    },
+    /// Parts must hold at least one byte, and a payload can have at most 2^32 - 1 parts.
+    PartSize(usize),
+    /// Sequence numbers of the first missing parts, how many are missing in all, and the number
+    /// of parts expected.
+    MissingParts {
+        missing: Vec<u32>,
+        count: u32,
+        total: u32,
+    },
+    /// Sequence numbers found more than once.
+    DuplicateParts(Vec<u32>),
+    /// Parts do not belong to the same payload.
+    InconsistentParts(String),
+    /// Shamir sharing needs 1 to 255 shares and a threshold between 1 and the share count.
+    ShareCount {
+        threshold: usize,
+        shares: usize,
+    },
+    /// The share with this index does not match its checksum.
+    CorruptShare(u8),
+    /// Shares do not belong to the same payload.
+    InconsistentShares(String),
+    /// Fewer distinct shares than the threshold.
+    NotEnoughShares {
+        needed: usize,
+        found: usize,
+    },
+    UnknownCompression(u8),
+    UnknownCompressionName(String),
+    /// A compressed payload is, or claims to be, larger than compression allows.
+    CompressedSize(u64),
+    /// Compressing or decompressing failed.
+    Io(io::Error),
+    /// A key could not be parsed.
+    Key(String),
+    /// Public key encryption needs between 1 and 255 recipients.
+    RecipientCount(usize),
+    /// Decoy encryption needs 1 to 255 slots, and a slot for every message.
+    SlotCount {
+        messages: usize,
+        slots: usize,
+    },
+    /// Two decoy messages have the same password, so one could never be opened.
+    DuplicatePassword,
+    /// None of the identities is a recipient of the payload.
+    NoMatchingIdentity,
+    /// The payload is signed by another key, given as a string.
+    SignerMismatch(String),
+    BadSignature,
+    /// The critical image data changed since the payload was signed.
+    ImageModified,
+    /// Authenticated decryption failed.
+    Decrypt,
+}
+
+impl fmt::Display for PayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayloadError::Truncated => write!(f, "payload is truncated"),
+            PayloadError::NotAnEnvelope => write!(f, "payload does not start with an envelope"),
+            PayloadError::UnknownFlags(flags) => write!(f, "unknown envelope flags {flags:#010b}"),
+            PayloadError::UnknownContentType(c) => write!(f, "unknown content type '{c}'"),
+            PayloadError::UnsupportedVersion(v) => {
+                write!(f, "unsupported payload version '{v}'")
+            }
+            PayloadError::UnknownCipher(c) => write!(f, "unknown cipher '{c}'"),
+            PayloadError::UnknownCipherName(name) => write!(
+                f,
+                "unknown cipher '{name}', expected chacha20-poly1305 or aes-256-gcm"
+            ),
+            PayloadError::KdfParams(e) => write!(f, "invalid key derivation parameters: {e}"),
+            PayloadError::FieldLength(field, length) => {
+                write!(f, "{field} is too long ({length} bytes)")
+            }
+            PayloadError::Encoding(field) => write!(f, "{field} has an invalid encoding"),
+            PayloadError::SizeMismatch { expected, actual } => write!(
+                f,
+                "embedded file should be {expected} bytes but {actual} bytes were found"
+            ),
+            PayloadError::PartSize(size) => write!(f, "invalid part size {size}"),
+            PayloadError::CompressedSize(size) => {
+                write!(f, "payload of {size} bytes is too large for compression")
+            }
+            PayloadError::MissingParts { missing, .. } if missing.is_empty() => {
+                write!(f, "no parts found")
+            }
+            PayloadError::MissingParts {
+                missing,
+                count,
+                total,
+            } => {
+                let more = if *count as usize > missing.len() {
+                    ", ..."
+                } else {
+                    ""
+                };
+                write!(
+                    f,
+                    "missing {count} of {total} parts: {}{more}",
+                    join(missing)
+                )
+            }
+            PayloadError::DuplicateParts(parts) => write!(f, "duplicate parts {}", join(parts)),
+            PayloadError::InconsistentParts(e) => write!(f, "inconsistent parts: {e}"),
+            PayloadError::ShareCount { threshold, shares } => write!(
+                f,
+                "cannot split into {shares} shares with a threshold of {threshold}, expected \
+                 1 to 255 shares and a threshold of at least 1 and at most the shares"
+            ),
+            PayloadError::CorruptShare(index) => write!(f, "share {index} is corrupted"),
+            PayloadError::InconsistentShares(e) => write!(f, "inconsistent shares: {e}"),
+            PayloadError::NotEnoughShares { needed, found } => write!(
+                f,
+                "{needed} shares are needed to rebuild the payload, found {found}"
+            ),
+            PayloadError::UnknownCompression(c) => write!(f, "unknown compression '{c}'"),
+            PayloadError::UnknownCompressionName(name) => write!(
+                f,
+                "unknown compression '{name}', expected zlib, zstd or brotli"
+            ),
+            PayloadError::Io(e) => write!(f, "{e}"),
+            PayloadError::Key(e) => write!(f, "invalid key: {e}"),
+            PayloadError::RecipientCount(n) => {
+                write!(f, "expected between 1 and 255 recipients, got {n}")
+            }
+            PayloadError::SlotCount { messages, slots } => write!(
+                f,
+                "{messages} messages do not fit in {slots} slots, expected 1 to 255 slots and \
+                 at least one per message"
+            ),
+            PayloadError::DuplicatePassword => {
+                write!(f, "every message needs a different password")
+            }
+            PayloadError::NoMatchingIdentity => {
+                write!(
+                    f,
+                    "the message is not encrypted to any of the given identities"
+                )
+            }
+            PayloadError::SignerMismatch(key) => write!(f, "signed by a different key: {key}"),
+            PayloadError::BadSignature => {
+                write!(f, "signature does not match the message")
+            }
+            PayloadError::ImageModified => {
+                write!(f, "image data has been modified since it was signed")
+            }
+            PayloadError::Decrypt => write!(
+                f,
+                "decryption failed: wrong password or the message has been modified"
+            ),
+        }
+    }
+}
+
+impl error::Error for PayloadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            PayloadError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PayloadError {
+    fn from(e: io::Error) -> Self {
+        PayloadError::Io(e)
+    }
+}
+
+fn join(numbers: &[u32]) -> String {
+    numbers
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,171 @@
+//! A file with its name, size and MIME type, so it can be extracted byte for byte.
+//!
+//! | Field       | Size           |                         |
+//! |-------------|----------------|-------------------------|
+//! | Magic       | 4 bytes        | `PMfi`                  |
+//! | Version     | 1 byte         | 1                       |
+//! | Name length | 2 bytes        |                         |
+//! | Name        | variable       | UTF-8, may be empty     |
+//! | MIME length | 1 byte         |                         |
+//! | MIME type   | variable       | ASCII                   |
+//! | Size        | 8 bytes        | length of the contents  |
+//! | Contents    | rest           |                         |
+
+use crate::{read_array, PayloadError};
+
+#[cfg(test)]
+mod tests;
+
+const MAGIC: [u8; 4] = *b"PMfi";
+const VERSION: u8 = 1;
+
+/// Bytes the file adds in front of the contents, not counting the name and MIME type.
+pub const HEADER_LENGTH: usize = 4 + 1 + 2 + 1 + 8;
+
+/// Used when the type of the contents is unknown.
+pub const DEFAULT_MIME: &str = "application/octet-stream";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedFile {
+    name: String,
+    mime: String,
+    contents: Vec<u8>,
+}
+
+impl EmbeddedFile {
+    /// `name` is at most 65535 bytes and `mime` at most 255 ASCII bytes.
+    pub fn new(name: String, mime: String, contents: Vec<u8>) -> Result<Self, PayloadError> {
+        if name.len() > u16::MAX as usize {
+            return Err(PayloadError::FieldLength("file name", name.len()));
+        }
+        if mime.len() > u8::MAX as usize {
+            return Err(PayloadError::FieldLength("MIME type", mime.len()));
+        }
+        if !mime.is_ascii() {
+            return Err(PayloadError::Encoding("MIME type"));
+        }
+        Ok(EmbeddedFile {
+            name,
+            mime,
+            contents,
+        })
+    }
+
+    /// Original file name, empty when the contents came from a stream.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn mime(&self) -> &str {
+        &self.mime
+    }
+
+    pub fn contents(&self) -> &[u8] {
+        &self.contents
+    }
+
+    pub fn into_contents(self) -> Vec<u8> {
+        self.contents
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(
+            HEADER_LENGTH + self.name.len() + self.mime.len() + self.contents.len(),
+        );
+        bytes.extend(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend((self.name.len() as u16).to_be_bytes());
+        bytes.extend(self.name.as_bytes());
+        bytes.push(self.mime.len() as u8);
+        bytes.extend(self.mime.as_bytes());
+        bytes.extend((self.contents.len() as u64).to_be_bytes());
+        bytes.extend(&self.contents);
+        bytes
+    }
+}
+
+impl TryFrom<&[u8]> for EmbeddedFile {
+    type Error = PayloadError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if !is_file(value) {
+            return Err(PayloadError::Truncated);
+        }
+        let version = *value.get(4).ok_or(PayloadError::Truncated)?;
+        if version != VERSION {
+            return Err(PayloadError::UnsupportedVersion(version));
+        }
+
+        let name_length = u16::from_be_bytes(read_array(value, 5)?) as usize;
+        let name = value
+            .get(7..7 + name_length)
+            .ok_or(PayloadError::Truncated)?;
+        let name =
+            String::from_utf8(name.to_vec()).map_err(|_| PayloadError::Encoding("file name"))?;
+
+        let mut offset = 7 + name_length;
+        let mime_length = *value.get(offset).ok_or(PayloadError::Truncated)? as usize;
+        offset += 1;
+        let mime = value
+            .get(offset..offset + mime_length)
+            .ok_or(PayloadError::Truncated)?;
+        let mime =
+            String::from_utf8(mime.to_vec()).map_err(|_| PayloadError::Encoding("MIME type"))?;
+        offset += mime_length;
+
+        let size = u64::from_be_bytes(read_array(value, offset)?);
+        let contents = &value[offset + 8..];
+        if contents.len() as u64 != size {
+            return Err(PayloadError::SizeMismatch {
+                expected: size,
+                actual: contents.len() as u64,
+            });
+        }
+
+        EmbeddedFile::new(name, mime, contents.to_vec())
+    }
+}
+
+/// Whether `data` starts like an embedded file.
+pub fn is_file(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// Guesses a MIME type from the leading bytes of `contents`, then from the extension of `name`.
+pub fn guess_mime(name: &str, contents: &[u8]) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\x28\xb5\x2f\xfd", "application/zstd"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    ];
+    const EXTENSIONS: &[(&str, &str)] = &[
+        ("txt", "text/plain"),
+        ("md", "text/markdown"),
+        ("html", "text/html"),
+        ("htm", "text/html"),
+        ("css", "text/css"),
+        ("csv", "text/csv"),
+        ("json", "application/json"),
+        ("xml", "application/xml"),
+        ("js", "text/javascript"),
+        ("svg", "image/svg+xml"),
+        ("webp", "image/webp"),
+        ("mp3", "audio/mpeg"),
+        ("mp4", "video/mp4"),
+        ("tar", "application/x-tar"),
+    ];
+
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(s, _)| contents.starts_with(s)) {
+        return mime;
+    }
+    let extension = name.rsplit_once('.').map(|(_, e)| e.to_ascii_lowercase());
+    extension
+        .and_then(|e| EXTENSIONS.iter().find(|(x, _)| *x == e))
+        .map_or(DEFAULT_MIME, |(_, mime)| mime)
+}
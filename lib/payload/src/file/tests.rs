@@ -0,0 +1,63 @@
+use super::*;
+
+fn file() -> EmbeddedFile {
+    let contents = (0..=255).collect();
+    EmbeddedFile::new("data.bin".to_string(), DEFAULT_MIME.to_string(), contents).unwrap()
+}
+
+#[test]
+fn test_round_trip() {
+    let file = file();
+    let bytes = file.to_bytes();
+    assert!(is_file(&bytes));
+    assert_eq!(bytes.len(), HEADER_LENGTH + 8 + DEFAULT_MIME.len() + 256);
+    assert_eq!(EmbeddedFile::try_from(bytes.as_slice()).unwrap(), file);
+}
+
+#[test]
+fn test_empty_name_and_contents() {
+    let file = EmbeddedFile::new(String::new(), "text/plain".to_string(), vec![]).unwrap();
+    let parsed = EmbeddedFile::try_from(file.to_bytes().as_slice()).unwrap();
+    assert_eq!(parsed.name(), "");
+    assert!(parsed.contents().is_empty());
+}
+
+#[test]
+fn test_size_mismatch() {
+    let mut bytes = file().to_bytes();
+    bytes.pop();
+    assert!(matches!(
+        EmbeddedFile::try_from(bytes.as_slice()),
+        Err(PayloadError::SizeMismatch {
+            expected: 256,
+            actual: 255
+        })
+    ));
+}
+
+#[test]
+fn test_truncated_header() {
+    let bytes = file().to_bytes();
+    assert!(matches!(
+        EmbeddedFile::try_from(&bytes[..10]),
+        Err(PayloadError::Truncated)
+    ));
+}
+
+#[test]
+fn test_field_lengths() {
+    let long = "x".repeat(256);
+    assert!(EmbeddedFile::new(String::new(), long, vec![]).is_err());
+    assert!(EmbeddedFile::new(String::new(), "tëxt/plain".to_string(), vec![]).is_err());
+}
+
+#[test]
+fn test_guess_mime() {
+    assert_eq!(guess_mime("a", b"\x89PNG\r\n\x1a\n...."), "image/png");
+    assert_eq!(guess_mime("notes.TXT", b"hello"), "text/plain");
+    assert_eq!(
+        guess_mime("archive.tar.gz", b"\x1f\x8b\x08"),
+        "application/gzip"
+    );
+    assert_eq!(guess_mime("", b"\x00\x01"), DEFAULT_MIME);
+}
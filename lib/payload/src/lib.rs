@@ -0,0 +1,45 @@
+//! Formats of the payloads hidden in PNG files.
+//!
+//! Every payload chunk starts with an [`envelope::Envelope`] describing the layers of its body.
+//! Chunks written before the envelope existed hold a bare message or a bare layer, each of which
+//! can be told apart by its magic bytes.
+
+pub use error::PayloadError;
+
+pub mod compress;
+pub mod decoy;
+pub mod envelope;
+mod error;
+pub mod file;
+pub mod multipart;
+pub mod passphrase;
+pub mod recipient;
+pub mod share;
+pub mod signature;
+
+/// Reads `N` bytes at `offset`, or reports the payload as truncated.
+fn read_array<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N], PayloadError> {
+    data.get(offset..offset + N)
+        .and_then(|b| b.try_into().ok())
+        .ok_or(PayloadError::Truncated)
+}
+
+/// Parses a hex encoded key written after `prefix`.
+fn parse_key(s: &str, prefix: &str) -> Result<[u8; 32], PayloadError> {
+    let s = s.trim();
+    let digits = s
+        .strip_prefix(prefix)
+        .ok_or_else(|| PayloadError::Key(format!("expected a key starting with '{prefix}'")))?;
+    let mut key = [0; 32];
+    hex::decode_to_slice(digits, &mut key).map_err(|e| PayloadError::Key(format!("'{s}': {e}")))?;
+    Ok(key)
+}
+
+/// The first line of a key file that is neither blank nor a `#` comment.
+fn first_key_line(contents: &str) -> Result<&str, PayloadError> {
+    contents
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#'))
+        .ok_or_else(|| PayloadError::Key("no secret key found".to_string()))
+}
@@ -0,0 +1,168 @@
+//! Splitting a payload over several chunks.
+//!
+//! | Field      | Size     |                                    |
+//! |------------|----------|------------------------------------|
+//! | Magic      | 4 bytes  | `PMmp`                             |
+//! | Version    | 1 byte   | 1                                  |
+//! | Message ID | 8 bytes  | random, shared by every part       |
+//! | Sequence   | 4 bytes  | 1 to total                         |
+//! | Total      | 4 bytes  | number of parts                    |
+//! | Data       | rest     |                                    |
+
+use crate::{read_array, PayloadError};
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod tests;
+
+const MAGIC: [u8; 4] = *b"PMmp";
+const VERSION: u8 = 1;
+
+/// Missing parts listed at most when a payload is incomplete.
+const MAX_LISTED_PARTS: usize = 32;
+
+/// Size of the header in front of the data of every part.
+pub const HEADER_LENGTH: usize = 4 + 1 + 8 + 4 + 4;
+
+/// One part of a payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part<'a> {
+    id: [u8; 8],
+    sequence: u32,
+    total: u32,
+    data: &'a [u8],
+}
+
+impl<'a> Part<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Part<'a>, PayloadError> {
+        if !is_part(bytes) {
+            return Err(PayloadError::Truncated);
+        }
+        let version = *bytes.get(4).ok_or(PayloadError::Truncated)?;
+        if version != VERSION {
+            return Err(PayloadError::UnsupportedVersion(version));
+        }
+        let part = Part {
+            id: read_array(bytes, 5)?,
+            sequence: u32::from_be_bytes(read_array(bytes, 13)?),
+            total: u32::from_be_bytes(read_array(bytes, 17)?),
+            data: &bytes[HEADER_LENGTH..],
+        };
+        if part.total == 0 || part.sequence == 0 || part.sequence > part.total {
+            return Err(PayloadError::InconsistentParts(format!(
+                "part {} of {}",
+                part.sequence, part.total
+            )));
+        }
+        Ok(part)
+    }
+
+    /// Identifies the payload the part belongs to.
+    pub fn id(&self) -> u64 {
+        u64::from_be_bytes(self.id)
+    }
+
+    /// Position of the part, from 1.
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+/// Whether `data` starts like a part of a split payload.
+pub fn is_part(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// Splits `payload` into parts holding at most `part_size` bytes of it each. An empty payload
+/// still makes one part.
+pub fn split(payload: &[u8], part_size: usize) -> Result<Vec<Vec<u8>>, PayloadError> {
+    if part_size == 0 {
+        return Err(PayloadError::PartSize(part_size));
+    }
+    let total = payload.len().div_ceil(part_size).max(1);
+    let total = u32::try_from(total).map_err(|_| PayloadError::PartSize(part_size))?;
+
+    let mut id = [0; 8];
+    OsRng.fill_bytes(&mut id);
+
+    let mut data = payload.chunks(part_size);
+    let parts = (1..=total)
+        .map(|sequence| {
+            let data = data.next().unwrap_or_default();
+            let mut part = Vec::with_capacity(HEADER_LENGTH + data.len());
+            part.extend(MAGIC);
+            part.push(VERSION);
+            part.extend(id);
+            part.extend(sequence.to_be_bytes());
+            part.extend(total.to_be_bytes());
+            part.extend(data);
+            part
+        })
+        .collect();
+    Ok(parts)
+}
+
+/// Puts the parts of one payload back together, in any order. Fails listing the duplicated
+/// sequence numbers, or the first [`MAX_LISTED_PARTS`] missing ones.
+pub fn join(parts: &[Part]) -> Result<Vec<u8>, PayloadError> {
+    let Some(first) = parts.first() else {
+        return Err(PayloadError::MissingParts {
+            missing: vec![],
+            count: 0,
+            total: 0,
+        });
+    };
+    if let Some(other) = parts.iter().find(|p| p.id != first.id) {
+        return Err(PayloadError::InconsistentParts(format!(
+            "parts of messages {:016x} and {:016x} are mixed",
+            first.id(),
+            other.id()
+        )));
+    }
+    if let Some(other) = parts.iter().find(|p| p.total != first.total) {
+        return Err(PayloadError::InconsistentParts(format!(
+            "parts disagree on the total: {} and {}",
+            first.total, other.total
+        )));
+    }
+
+    let mut ordered = BTreeMap::new();
+    let mut duplicates = vec![];
+    for part in parts {
+        if ordered.insert(part.sequence, part.data).is_some() {
+            duplicates.push(part.sequence);
+        }
+    }
+    if !duplicates.is_empty() {
+        duplicates.sort_unstable();
+        duplicates.dedup();
+        return Err(PayloadError::DuplicateParts(duplicates));
+    }
+
+    // every sequence number is at most the total, so the parts are complete if there are as many
+    let found = ordered.len() as u32;
+    if found != first.total {
+        // the total comes from the parts and may be in the billions, so only the first few
+        // missing parts are listed, which are found within `found + MAX_LISTED_PARTS` numbers
+        let missing = (1..=first.total)
+            .filter(|s| !ordered.contains_key(s))
+            .take(MAX_LISTED_PARTS)
+            .collect();
+        return Err(PayloadError::MissingParts {
+            missing,
+            count: first.total - found,
+            total: first.total,
+        });
+    }
+
+    Ok(ordered.into_values().flatten().copied().collect())
+}
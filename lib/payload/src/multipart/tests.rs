@@ -0,0 +1,94 @@
+use super::*;
+
+fn payload() -> Vec<u8> {
+    (0..1000u32).map(|i| i as u8).collect()
+}
+
+fn parse(parts: &[Vec<u8>]) -> Vec<Part<'_>> {
+    parts.iter().map(|p| Part::parse(p).unwrap()).collect()
+}
+
+#[test]
+fn test_split_join() {
+    let payload = payload();
+    let parts = split(&payload, 300).unwrap();
+    assert_eq!(parts.len(), 4);
+    assert!(parts.iter().all(|p| is_part(p)));
+
+    let mut parsed = parse(&parts);
+    assert_eq!(parsed[3].sequence(), 4);
+    assert_eq!(parsed[3].total(), 4);
+    assert_eq!(parsed[3].data().len(), 100);
+
+    parsed.reverse();
+    assert_eq!(join(&parsed).unwrap(), payload);
+}
+
+#[test]
+fn test_empty_payload() {
+    let parts = split(&[], 10).unwrap();
+    assert_eq!(parts.len(), 1);
+    assert!(join(&parse(&parts)).unwrap().is_empty());
+}
+
+#[test]
+fn test_invalid_part_size() {
+    assert!(matches!(split(b"x", 0), Err(PayloadError::PartSize(0))));
+}
+
+#[test]
+fn test_missing_parts() {
+    let parts = split(&payload(), 100).unwrap();
+    let mut parsed = parse(&parts);
+    parsed.remove(7);
+    parsed.remove(2);
+    assert!(matches!(
+        join(&parsed),
+        Err(PayloadError::MissingParts { missing, count: 2, total: 10 }) if missing == [3, 8]
+    ));
+}
+
+#[test]
+fn test_huge_total() {
+    let mut part = split(b"data", 10).unwrap().remove(0);
+    part[17..21].copy_from_slice(&u32::MAX.to_be_bytes());
+    let error = join(&[Part::parse(&part).unwrap()]).unwrap_err();
+    assert!(matches!(
+        &error,
+        PayloadError::MissingParts { missing, count, total: u32::MAX }
+            if missing.len() == MAX_LISTED_PARTS && missing[0] == 2 && *count == u32::MAX - 1
+    ));
+    assert!(error.to_string().ends_with(", ..."), "{error}");
+}
+
+#[test]
+fn test_duplicate_parts() {
+    let parts = split(&payload(), 100).unwrap();
+    let mut parsed = parse(&parts);
+    parsed.push(parsed[4].clone());
+    assert!(matches!(
+        join(&parsed),
+        Err(PayloadError::DuplicateParts(d)) if d == [5]
+    ));
+}
+
+#[test]
+fn test_mixed_messages() {
+    let a = split(&payload(), 500).unwrap();
+    let b = split(&payload(), 500).unwrap();
+    let parsed = vec![Part::parse(&a[0]).unwrap(), Part::parse(&b[1]).unwrap()];
+    assert!(matches!(
+        join(&parsed),
+        Err(PayloadError::InconsistentParts(_))
+    ));
+}
+
+#[test]
+fn test_invalid_sequence() {
+    let mut part = split(b"data", 10).unwrap().remove(0);
+    part[13..17].copy_from_slice(&2u32.to_be_bytes());
+    assert!(matches!(
+        Part::parse(&part),
+        Err(PayloadError::InconsistentParts(_))
+    ));
+}
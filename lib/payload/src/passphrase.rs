@@ -0,0 +1,245 @@
+//! Passphrase encryption: a key derived with Argon2id seals the payload with an AEAD cipher.
+//!
+//! | Field         | Size     |                                  |
+//! |---------------|----------|----------------------------------|
+//! | Magic         | 4 bytes  | `PMpw`                           |
+//! | Version       | 1 byte   | 1                                |
+//! | Cipher        | 1 byte   | 1 ChaCha20-Poly1305, 2 AES-256-GCM |
+//! | Memory cost   | 4 bytes  | Argon2id, KiB                    |
+//! | Time cost     | 4 bytes  | Argon2id, iterations             |
+//! | Parallelism   | 4 bytes  | Argon2id, lanes                  |
+//! | Salt          | 16 bytes |                                  |
+//! | Nonce         | 12 bytes |                                  |
+//! | Ciphertext    | rest     | including the 16 byte tag        |
+//!
+//! The header is authenticated as associated data, followed by any the caller gives, so tampering
+//! with the parameters is detected.
+
+use crate::{read_array, PayloadError};
+use aes_gcm::Aes256Gcm;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, KeyInit, OsRng, Payload},
+    ChaCha20Poly1305,
+};
+use std::{fmt, str::FromStr};
+
+#[cfg(test)]
+mod tests;
+
+const MAGIC: [u8; 4] = *b"PMpw";
+const VERSION: u8 = 1;
+pub(crate) const SALT_LENGTH: usize = 16;
+pub(crate) const NONCE_LENGTH: usize = 12;
+const HEADER_LENGTH: usize = 4 + 1 + 1 + 4 * 3 + SALT_LENGTH + NONCE_LENGTH;
+pub(crate) const TAG_LENGTH: usize = 16;
+
+/// Bytes sealing adds to the plaintext.
+pub const OVERHEAD: usize = HEADER_LENGTH + TAG_LENGTH;
+
+/// Refuse to derive keys needing more than 1 GiB, so a crafted payload cannot exhaust memory.
+const MAX_MEMORY_COST: u32 = 1 << 20;
+
+/// Refuse more iterations than this, so a crafted payload cannot keep opening it busy for hours.
+const MAX_TIME_COST: u32 = 16;
+
+/// Refuse more lanes than this, each of which needs its own share of memory and a thread.
+const MAX_PARALLELISM: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Cipher {
+    #[default]
+    ChaCha20Poly1305,
+    Aes256Gcm,
+}
+
+impl Cipher {
+    pub const ALL: [Cipher; 2] = [Cipher::ChaCha20Poly1305, Cipher::Aes256Gcm];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Cipher::ChaCha20Poly1305 => "chacha20-poly1305",
+            Cipher::Aes256Gcm => "aes-256-gcm",
+        }
+    }
+
+    pub(crate) fn id(&self) -> u8 {
+        match self {
+            Cipher::ChaCha20Poly1305 => 1,
+            Cipher::Aes256Gcm => 2,
+        }
+    }
+
+    pub(crate) fn from_id(id: u8) -> Result<Cipher, PayloadError> {
+        match id {
+            1 => Ok(Cipher::ChaCha20Poly1305),
+            2 => Ok(Cipher::Aes256Gcm),
+            id => Err(PayloadError::UnknownCipher(id)),
+        }
+    }
+
+    /// Seals or opens with the given 32 byte key.
+    pub(crate) fn apply(
+        &self,
+        key: &[u8; 32],
+        nonce: &[u8; NONCE_LENGTH],
+        payload: Payload,
+        seal: bool,
+    ) -> Result<Vec<u8>, PayloadError> {
+        let result = match (self, seal) {
+            (Cipher::ChaCha20Poly1305, true) => {
+                ChaCha20Poly1305::new(key.into()).encrypt(nonce.into(), payload)
+            }
+            (Cipher::ChaCha20Poly1305, false) => {
+                ChaCha20Poly1305::new(key.into()).decrypt(nonce.into(), payload)
+            }
+            (Cipher::Aes256Gcm, true) => Aes256Gcm::new(key.into()).encrypt(nonce.into(), payload),
+            (Cipher::Aes256Gcm, false) => Aes256Gcm::new(key.into()).decrypt(nonce.into(), payload),
+        };
+        result.map_err(|_| PayloadError::Decrypt)
+    }
+}
+
+impl fmt::Display for Cipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Cipher {
+    type Err = PayloadError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Cipher::ALL
+            .into_iter()
+            .find(|c| c.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| PayloadError::UnknownCipherName(s.to_string()))
+    }
+}
+
+/// Argon2id cost parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory in KiB
+    pub memory_cost: u32,
+    /// Iterations
+    pub time_cost: u32,
+    /// Lanes
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// The OWASP recommended minimum for Argon2id: 19 MiB, 2 iterations, 1 lane.
+    fn default() -> Self {
+        KdfParams {
+            memory_cost: 19 * 1024,
+            time_cost: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    pub(crate) fn derive(&self, password: &[u8], salt: &[u8]) -> Result<[u8; 32], PayloadError> {
+        if self.memory_cost > MAX_MEMORY_COST {
+            return Err(PayloadError::KdfParams(format!(
+                "memory cost {} KiB exceeds {MAX_MEMORY_COST} KiB",
+                self.memory_cost
+            )));
+        }
+        if self.time_cost > MAX_TIME_COST {
+            return Err(PayloadError::KdfParams(format!(
+                "time cost {} exceeds {MAX_TIME_COST} iterations",
+                self.time_cost
+            )));
+        }
+        if self.parallelism > MAX_PARALLELISM {
+            return Err(PayloadError::KdfParams(format!(
+                "parallelism {} exceeds {MAX_PARALLELISM} lanes",
+                self.parallelism
+            )));
+        }
+        let params = Params::new(self.memory_cost, self.time_cost, self.parallelism, Some(32))
+            .map_err(|e| PayloadError::KdfParams(e.to_string()))?;
+
+        let mut key = [0; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password, salt, &mut key)
+            .map_err(|e| PayloadError::KdfParams(e.to_string()))?;
+        Ok(key)
+    }
+}
+
+/// Whether `data` starts like a passphrase sealed payload.
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// Encrypts `plaintext` under a key derived from `password` with a fresh random salt and nonce.
+/// `associated_data` is authenticated but not stored, and must be given again to open it.
+pub fn seal(
+    plaintext: &[u8],
+    associated_data: &[u8],
+    password: &[u8],
+    cipher: Cipher,
+    params: KdfParams,
+) -> Result<Vec<u8>, PayloadError> {
+    let mut salt = [0; SALT_LENGTH];
+    let mut nonce = [0; NONCE_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let key = params.derive(password, &salt)?;
+
+    let mut sealed = Vec::with_capacity(OVERHEAD + plaintext.len());
+    sealed.extend(MAGIC);
+    sealed.push(VERSION);
+    sealed.push(cipher.id());
+    sealed.extend(params.memory_cost.to_be_bytes());
+    sealed.extend(params.time_cost.to_be_bytes());
+    sealed.extend(params.parallelism.to_be_bytes());
+    sealed.extend(salt);
+    sealed.extend(nonce);
+
+    let aad = [&sealed, associated_data].concat();
+    let payload = Payload {
+        msg: plaintext,
+        aad: &aad,
+    };
+    let ciphertext = cipher.apply(&key, &nonce, payload, true)?;
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+/// Decrypts a payload made by [`seal`] with the same `associated_data`. A wrong password or
+/// associated data is reported as [`PayloadError::Decrypt`].
+pub fn open(
+    sealed: &[u8],
+    associated_data: &[u8],
+    password: &[u8],
+) -> Result<Vec<u8>, PayloadError> {
+    if !is_sealed(sealed) || sealed.len() < HEADER_LENGTH {
+        return Err(PayloadError::Truncated);
+    }
+    let version = sealed[4];
+    if version != VERSION {
+        return Err(PayloadError::UnsupportedVersion(version));
+    }
+    let cipher = Cipher::from_id(sealed[5])?;
+    let params = KdfParams {
+        memory_cost: u32::from_be_bytes(read_array(sealed, 6)?),
+        time_cost: u32::from_be_bytes(read_array(sealed, 10)?),
+        parallelism: u32::from_be_bytes(read_array(sealed, 14)?),
+    };
+    let salt: [u8; SALT_LENGTH] = read_array(sealed, 18)?;
+    let nonce: [u8; NONCE_LENGTH] = read_array(sealed, 18 + SALT_LENGTH)?;
+
+    let key = params.derive(password, &salt)?;
+    let (header, ciphertext) = sealed.split_at(HEADER_LENGTH);
+    let aad = [header, associated_data].concat();
+    let payload = Payload {
+        msg: ciphertext,
+        aad: &aad,
+    };
+    cipher.apply(&key, &nonce, payload, false)
+}
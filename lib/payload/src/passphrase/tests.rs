@@ -0,0 +1,96 @@
+use super::*;
+
+/// Cheap parameters so the tests stay fast
+const PARAMS: KdfParams = KdfParams {
+    memory_cost: 64,
+    time_cost: 1,
+    parallelism: 1,
+};
+
+#[test]
+fn test_seal_open_round_trip() {
+    for cipher in [Cipher::ChaCha20Poly1305, Cipher::Aes256Gcm] {
+        let sealed = seal(b"secret message", b"", b"hunter2", cipher, PARAMS).unwrap();
+        assert!(is_sealed(&sealed));
+        assert_eq!(sealed.len(), OVERHEAD + 14);
+        assert_eq!(open(&sealed, b"", b"hunter2").unwrap(), b"secret message");
+    }
+}
+
+#[test]
+fn test_seal_is_randomized() {
+    let a = seal(b"secret", b"", b"pw", Cipher::default(), PARAMS).unwrap();
+    let b = seal(b"secret", b"", b"pw", Cipher::default(), PARAMS).unwrap();
+    assert_ne!(a, b);
+}
+
+#[test]
+fn test_associated_data() {
+    let sealed = seal(b"secret", b"header", b"pw", Cipher::default(), PARAMS).unwrap();
+    assert_eq!(open(&sealed, b"header", b"pw").unwrap(), b"secret");
+    assert!(matches!(
+        open(&sealed, b"headed", b"pw"),
+        Err(PayloadError::Decrypt)
+    ));
+}
+
+#[test]
+fn test_wrong_password() {
+    let sealed = seal(b"secret", b"", b"hunter2", Cipher::default(), PARAMS).unwrap();
+    assert!(matches!(
+        open(&sealed, b"", b"hunter3"),
+        Err(PayloadError::Decrypt)
+    ));
+}
+
+#[test]
+fn test_tampered_header() {
+    let mut sealed = seal(b"secret", b"", b"pw", Cipher::default(), PARAMS).unwrap();
+    // time cost is authenticated
+    sealed[13] += 1;
+    assert!(matches!(
+        open(&sealed, b"", b"pw"),
+        Err(PayloadError::Decrypt)
+    ));
+}
+
+#[test]
+fn test_unsupported_version() {
+    let mut sealed = seal(b"secret", b"", b"pw", Cipher::default(), PARAMS).unwrap();
+    sealed[4] = 2;
+    assert!(matches!(
+        open(&sealed, b"", b"pw"),
+        Err(PayloadError::UnsupportedVersion(2))
+    ));
+}
+
+#[test]
+fn test_truncated() {
+    let sealed = seal(b"secret", b"", b"pw", Cipher::default(), PARAMS).unwrap();
+    assert!(matches!(
+        open(&sealed[..20], b"", b"pw"),
+        Err(PayloadError::Truncated)
+    ));
+}
+
+#[test]
+fn test_excessive_costs() {
+    // memory cost, time cost and parallelism
+    for offset in [6, 10, 14] {
+        let mut sealed = seal(b"secret", b"", b"pw", Cipher::default(), PARAMS).unwrap();
+        sealed[offset..offset + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            open(&sealed, b"", b"pw"),
+            Err(PayloadError::KdfParams(_))
+        ));
+    }
+}
+
+#[test]
+fn test_cipher_from_str() {
+    for cipher in Cipher::ALL {
+        assert_eq!(cipher.to_string().parse::<Cipher>().unwrap(), cipher);
+    }
+    assert_eq!("AES-256-GCM".parse::<Cipher>().unwrap(), Cipher::Aes256Gcm);
+    assert!("rot13".parse::<Cipher>().is_err());
+}
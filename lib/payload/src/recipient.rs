@@ -0,0 +1,237 @@
+//! Public key encryption to one or more X25519 recipients, in the style of age.
+//!
+//! A random file key encrypts the payload. The file key is wrapped once per recipient with a key
+//! derived from an ephemeral X25519 exchange, and a recipient finds its wrapped key by trying
+//! each one. The payload does not record who the recipients are.
+//!
+//! | Field           | Size            |                                   |
+//! |-----------------|-----------------|-----------------------------------|
+//! | Magic           | 4 bytes         | `PMrc`                            |
+//! | Version         | 1 byte          | 1                                 |
+//! | Recipient count | 1 byte          | 1 to 255                          |
+//! | Ephemeral key   | 32 bytes        | X25519 public key                 |
+//! | Wrapped keys    | 48 bytes each   | ChaCha20-Poly1305 sealed file key |
+//! | Nonce           | 12 bytes        |                                   |
+//! | Ciphertext      | rest            | ChaCha20-Poly1305                 |
+//!
+//! The header, followed by any associated data the caller gives, is authenticated as associated
+//! data of the ciphertext.
+
+use crate::{read_array, PayloadError};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, KeyInit, OsRng, Payload},
+    ChaCha20Poly1305,
+};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use std::{fmt, str::FromStr};
+use x25519_dalek::StaticSecret;
+
+#[cfg(test)]
+mod tests;
+
+const MAGIC: [u8; 4] = *b"PMrc";
+const VERSION: u8 = 1;
+const KEY_LENGTH: usize = 32;
+const WRAPPED_KEY_LENGTH: usize = KEY_LENGTH + 16;
+const NONCE_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16;
+const WRAP_INFO: &[u8] = b"png-message x25519 v1";
+
+const PUBLIC_KEY_PREFIX: &str = "msg-pub-";
+const SECRET_KEY_PREFIX: &str = "MSG-SECRET-";
+
+/// An X25519 public key, written as `msg-pub-` followed by 64 hex digits.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PublicKey(x25519_dalek::PublicKey);
+
+/// An X25519 secret key, written as `MSG-SECRET-` followed by 64 hex digits.
+#[derive(Clone)]
+pub struct Identity(StaticSecret);
+
+impl Identity {
+    pub fn generate() -> Identity {
+        Identity(StaticSecret::random_from_rng(OsRng))
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey((&self.0).into())
+    }
+
+    /// Parses an identity file: the first line that is neither blank nor a `#` comment.
+    pub fn from_file_contents(contents: &str) -> Result<Identity, PayloadError> {
+        crate::first_key_line(contents)?.parse()
+    }
+
+    /// Contents of an identity file, with the public key as a comment.
+    pub fn to_file_contents(&self) -> String {
+        format!("# public key: {}\n{self}\n", self.public_key())
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = PayloadError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::parse_key(s, PUBLIC_KEY_PREFIX).map(|k| PublicKey(k.into()))
+    }
+}
+
+impl FromStr for Identity {
+    type Err = PayloadError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::parse_key(s, SECRET_KEY_PREFIX).map(|k| Identity(k.into()))
+    }
+}
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{PUBLIC_KEY_PREFIX}{}", hex::encode(self.0.as_bytes()))
+    }
+}
+
+impl fmt::Debug for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PublicKey({self})")
+    }
+}
+
+impl fmt::Display for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{SECRET_KEY_PREFIX}{}", hex::encode(self.0.as_bytes()))
+    }
+}
+
+impl fmt::Debug for Identity {
+    /// Never prints the secret.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Identity({})", self.public_key())
+    }
+}
+
+/// The ChaCha20-Poly1305 key wrapping the file key for `recipient`.
+fn wrap_key(shared: &[u8; 32], ephemeral: &PublicKey, recipient: &PublicKey) -> ChaCha20Poly1305 {
+    let mut salt = [0; 2 * KEY_LENGTH];
+    salt[..KEY_LENGTH].copy_from_slice(ephemeral.0.as_bytes());
+    salt[KEY_LENGTH..].copy_from_slice(recipient.0.as_bytes());
+
+    let mut key = [0; KEY_LENGTH];
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(WRAP_INFO, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    ChaCha20Poly1305::new(&key.into())
+}
+
+/// Whether `data` starts like a payload encrypted to recipients.
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// Bytes encrypting to `recipients` recipients adds to the plaintext.
+pub fn overhead(recipients: usize) -> usize {
+    4 + 1 + 1 + KEY_LENGTH + recipients * WRAPPED_KEY_LENGTH + NONCE_LENGTH + TAG_LENGTH
+}
+
+/// Encrypts `plaintext` so that any one of `recipients` can decrypt it. `associated_data` is
+/// authenticated but not stored, and must be given again to decrypt it.
+pub fn encrypt(
+    plaintext: &[u8],
+    associated_data: &[u8],
+    recipients: &[PublicKey],
+) -> Result<Vec<u8>, PayloadError> {
+    let count = u8::try_from(recipients.len())
+        .ok()
+        .filter(|&c| c > 0)
+        .ok_or(PayloadError::RecipientCount(recipients.len()))?;
+
+    let mut file_key = [0; KEY_LENGTH];
+    let mut nonce = [0; NONCE_LENGTH];
+    OsRng.fill_bytes(&mut file_key);
+    OsRng.fill_bytes(&mut nonce);
+
+    // a static secret, as it takes part in one exchange per recipient
+    let ephemeral_secret = StaticSecret::random_from_rng(OsRng);
+    let ephemeral = PublicKey((&ephemeral_secret).into());
+
+    let mut encrypted = Vec::new();
+    encrypted.extend(MAGIC);
+    encrypted.push(VERSION);
+    encrypted.push(count);
+    encrypted.extend(ephemeral.0.as_bytes());
+
+    // each wrap key is used once, so a zero nonce is safe
+    for recipient in recipients {
+        let shared = ephemeral_secret.diffie_hellman(&recipient.0);
+        // a low order key gives a shared secret anyone can compute
+        if !shared.was_contributory() {
+            return Err(PayloadError::Key(format!("{recipient} is a low order key")));
+        }
+        let wrapped = wrap_key(shared.as_bytes(), &ephemeral, recipient)
+            .encrypt(&[0; NONCE_LENGTH].into(), file_key.as_slice())
+            .map_err(|_| PayloadError::Decrypt)?;
+        encrypted.extend(wrapped);
+    }
+    encrypted.extend(nonce);
+
+    let aad = [&encrypted, associated_data].concat();
+    let payload = Payload {
+        msg: plaintext,
+        aad: &aad,
+    };
+    let ciphertext = ChaCha20Poly1305::new(&file_key.into())
+        .encrypt(&nonce.into(), payload)
+        .map_err(|_| PayloadError::Decrypt)?;
+    encrypted.extend(ciphertext);
+    Ok(encrypted)
+}
+
+/// Decrypts a payload made by [`encrypt`] with the same `associated_data`, using the first of
+/// `identities` it was encrypted to.
+pub fn decrypt(
+    encrypted: &[u8],
+    associated_data: &[u8],
+    identities: &[Identity],
+) -> Result<Vec<u8>, PayloadError> {
+    if !is_encrypted(encrypted) {
+        return Err(PayloadError::Truncated);
+    }
+    let version = *encrypted.get(4).ok_or(PayloadError::Truncated)?;
+    if version != VERSION {
+        return Err(PayloadError::UnsupportedVersion(version));
+    }
+    let count = *encrypted.get(5).ok_or(PayloadError::Truncated)? as usize;
+    let ephemeral = PublicKey(read_array::<KEY_LENGTH>(encrypted, 6)?.into());
+    let wrapped_start = 6 + KEY_LENGTH;
+    let nonce_start = wrapped_start + count * WRAPPED_KEY_LENGTH;
+    let nonce: [u8; NONCE_LENGTH] = read_array(encrypted, nonce_start)?;
+    let header_length = nonce_start + NONCE_LENGTH;
+
+    let mut file_key = None;
+    for identity in identities {
+        let shared = identity.0.diffie_hellman(&ephemeral.0);
+        // a low order ephemeral key gives a shared secret anyone can compute, as age rejects
+        if !shared.was_contributory() {
+            return Err(PayloadError::Decrypt);
+        }
+        let wrap = wrap_key(shared.as_bytes(), &ephemeral, &identity.public_key());
+        file_key = encrypted[wrapped_start..nonce_start]
+            .chunks_exact(WRAPPED_KEY_LENGTH)
+            .find_map(|w| wrap.decrypt(&[0; NONCE_LENGTH].into(), w).ok());
+        if file_key.is_some() {
+            break;
+        }
+    }
+    let file_key = file_key.ok_or(PayloadError::NoMatchingIdentity)?;
+    let file_key: [u8; KEY_LENGTH] = file_key.try_into().map_err(|_| PayloadError::Decrypt)?;
+
+    let (header, ciphertext) = encrypted.split_at(header_length);
+    let aad = [header, associated_data].concat();
+    let payload = Payload {
+        msg: ciphertext,
+        aad: &aad,
+    };
+    ChaCha20Poly1305::new(&file_key.into())
+        .decrypt(&nonce.into(), payload)
+        .map_err(|_| PayloadError::Decrypt)
+}
@@ -0,0 +1,104 @@
+use super::*;
+
+#[test]
+fn test_encrypt_decrypt_round_trip() {
+    let alice = Identity::generate();
+    let bob = Identity::generate();
+    let recipients = [alice.public_key(), bob.public_key()];
+
+    let encrypted = encrypt(b"team message", b"", &recipients).unwrap();
+    assert!(is_encrypted(&encrypted));
+    assert_eq!(encrypted.len(), overhead(2) + 12);
+    assert_eq!(decrypt(&encrypted, b"", &[alice]).unwrap(), b"team message");
+    assert_eq!(decrypt(&encrypted, b"", &[bob]).unwrap(), b"team message");
+}
+
+#[test]
+fn test_not_a_recipient() {
+    let alice = Identity::generate();
+    let eve = Identity::generate();
+    let encrypted = encrypt(b"secret", b"", &[alice.public_key()]).unwrap();
+    assert!(matches!(
+        decrypt(&encrypted, b"", std::slice::from_ref(&eve)),
+        Err(PayloadError::NoMatchingIdentity)
+    ));
+    assert_eq!(decrypt(&encrypted, b"", &[eve, alice]).unwrap(), b"secret");
+}
+
+#[test]
+fn test_associated_data() {
+    let alice = Identity::generate();
+    let encrypted = encrypt(b"secret", b"header", &[alice.public_key()]).unwrap();
+    assert_eq!(
+        decrypt(&encrypted, b"header", std::slice::from_ref(&alice)).unwrap(),
+        b"secret"
+    );
+    assert!(matches!(
+        decrypt(&encrypted, b"headed", &[alice]),
+        Err(PayloadError::Decrypt)
+    ));
+}
+
+#[test]
+fn test_tampered_ciphertext() {
+    let alice = Identity::generate();
+    let mut encrypted = encrypt(b"secret", b"", &[alice.public_key()]).unwrap();
+    *encrypted.last_mut().unwrap() ^= 1;
+    assert!(matches!(
+        decrypt(&encrypted, b"", &[alice]),
+        Err(PayloadError::Decrypt)
+    ));
+}
+
+#[test]
+fn test_recipient_count() {
+    assert!(matches!(
+        encrypt(b"secret", b"", &[]),
+        Err(PayloadError::RecipientCount(0))
+    ));
+}
+
+#[test]
+fn test_truncated() {
+    let alice = Identity::generate();
+    let encrypted = encrypt(b"secret", b"", &[alice.public_key()]).unwrap();
+    assert!(matches!(
+        decrypt(&encrypted[..50], b"", &[alice]),
+        Err(PayloadError::Truncated)
+    ));
+}
+
+#[test]
+fn test_key_strings() {
+    let identity = Identity::generate();
+    let public = identity.public_key();
+
+    let parsed: PublicKey = public.to_string().parse().unwrap();
+    assert_eq!(parsed, public);
+    assert!(public.to_string().starts_with("msg-pub-"));
+
+    let parsed = Identity::from_file_contents(&identity.to_file_contents()).unwrap();
+    assert_eq!(parsed.public_key(), public);
+    assert!(!format!("{identity:?}").contains("MSG-SECRET"));
+
+    assert!("msg-pub-1234".parse::<PublicKey>().is_err());
+    assert!(public.to_string().parse::<Identity>().is_err());
+}
+
+#[test]
+fn test_low_order_keys() {
+    // the identity point, whose shared secret is all zeros
+    let low_order = PublicKey(x25519_dalek::PublicKey::from([0; KEY_LENGTH]));
+    assert!(matches!(
+        encrypt(b"secret", b"", &[low_order]),
+        Err(PayloadError::Key(_))
+    ));
+
+    let alice = Identity::generate();
+    let mut encrypted = encrypt(b"secret", b"", &[alice.public_key()]).unwrap();
+    encrypted[6..6 + KEY_LENGTH].fill(0);
+    assert!(matches!(
+        decrypt(&encrypted, b"", &[alice]),
+        Err(PayloadError::Decrypt)
+    ));
+}
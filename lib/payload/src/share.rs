@@ -0,0 +1,232 @@
+//! Shamir secret sharing: a payload split into shares so that any `threshold` of them rebuild it
+//! and fewer tell nothing about it.
+//!
+//! | Field      | Size     |                                           |
+//! |------------|----------|-------------------------------------------|
+//! | Magic      | 4 bytes  | `PMsh`                                    |
+//! | Version    | 1 byte   | 1                                         |
+//! | Secret ID  | 8 bytes  | random, shared by every share             |
+//! | Threshold  | 1 byte   | shares needed, 1 to 255                   |
+//! | Index      | 1 byte   | 1 to 255, the point the share is taken at |
+//! | Checksum   | 4 bytes  | start of the SHA-256 of the other fields  |
+//! | Data       | rest     |                                           |
+//!
+//! Every byte of the payload is the constant term of its own random polynomial of degree
+//! `threshold - 1` over GF(2^8), and a share holds each polynomial evaluated at its index. The
+//! SHA-256 of the payload is shared with it, so a wrong combination is caught when rebuilding,
+//! while the checksum of each share catches damage to that share alone.
+
+use crate::{read_array, PayloadError};
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+use gf256::{div, mul};
+use sha2::{Digest, Sha256};
+
+#[cfg(test)]
+mod tests;
+
+const MAGIC: [u8; 4] = *b"PMsh";
+const VERSION: u8 = 1;
+const CHECKSUM_LENGTH: usize = 4;
+const DIGEST_LENGTH: usize = 32;
+
+/// Size of the header in front of the data of every share.
+pub const HEADER_LENGTH: usize = 4 + 1 + 8 + 1 + 1 + CHECKSUM_LENGTH;
+
+/// Bytes every share takes for a payload of `length` bytes.
+pub fn share_length(length: usize) -> usize {
+    HEADER_LENGTH + length + DIGEST_LENGTH
+}
+
+/// One share of a payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Share<'a> {
+    id: [u8; 8],
+    threshold: u8,
+    index: u8,
+    data: &'a [u8],
+}
+
+impl<'a> Share<'a> {
+    /// Parses a share, rejecting it if its checksum does not match.
+    pub fn parse(bytes: &'a [u8]) -> Result<Share<'a>, PayloadError> {
+        if !is_share(bytes) || bytes.len() < HEADER_LENGTH + DIGEST_LENGTH {
+            return Err(PayloadError::Truncated);
+        }
+        let version = bytes[4];
+        if version != VERSION {
+            return Err(PayloadError::UnsupportedVersion(version));
+        }
+        let share = Share {
+            id: read_array(bytes, 5)?,
+            threshold: bytes[13],
+            index: bytes[14],
+            data: &bytes[HEADER_LENGTH..],
+        };
+        let checksum: [u8; CHECKSUM_LENGTH] = read_array(bytes, 15)?;
+        if checksum != share.checksum() || share.threshold == 0 || share.index == 0 {
+            return Err(PayloadError::CorruptShare(share.index));
+        }
+        Ok(share)
+    }
+
+    /// Identifies the payload the share belongs to.
+    pub fn id(&self) -> u64 {
+        u64::from_be_bytes(self.id)
+    }
+
+    /// Shares needed to rebuild the payload.
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Position of the share, from 1.
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    fn checksum(&self) -> [u8; CHECKSUM_LENGTH] {
+        let mut hasher = Sha256::new();
+        hasher.update(MAGIC);
+        hasher.update([VERSION]);
+        hasher.update(self.id);
+        hasher.update([self.threshold, self.index]);
+        hasher.update(self.data);
+        hasher.finalize()[..CHECKSUM_LENGTH].try_into().unwrap()
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + self.data.len());
+        bytes.extend(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend(self.id);
+        bytes.push(self.threshold);
+        bytes.push(self.index);
+        bytes.extend(self.checksum());
+        bytes.extend(self.data);
+        bytes
+    }
+}
+
+/// Whether `data` starts like a share of a payload.
+pub fn is_share(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// Splits `payload` into `shares` shares, any `threshold` of which rebuild it.
+pub fn split(
+    payload: &[u8],
+    threshold: usize,
+    shares: usize,
+) -> Result<Vec<Vec<u8>>, PayloadError> {
+    if threshold == 0 || threshold > shares || shares > u8::MAX as usize {
+        return Err(PayloadError::ShareCount { threshold, shares });
+    }
+    let secret = [payload, &Sha256::digest(payload)].concat();
+    let mut coefficients = vec![0; secret.len() * (threshold - 1)];
+    OsRng.fill_bytes(&mut coefficients);
+
+    let mut id = [0; 8];
+    OsRng.fill_bytes(&mut id);
+
+    let shares = (1..=shares as u8)
+        .map(|x| {
+            let data: Vec<u8> = secret
+                .iter()
+                .enumerate()
+                .map(|(i, &constant)| {
+                    // Horner's rule, from the highest coefficient down to the payload byte
+                    let higher = &coefficients[i * (threshold - 1)..(i + 1) * (threshold - 1)];
+                    higher
+                        .iter()
+                        .rev()
+                        .chain([&constant])
+                        .fold(0, |y, &c| mul(y, x) ^ c)
+                })
+                .collect();
+            Share {
+                id,
+                threshold: threshold as u8,
+                index: x,
+                data: &data,
+            }
+            .to_bytes()
+        })
+        .collect();
+    Ok(shares)
+}
+
+/// Rebuilds the payload from at least `threshold` shares of it, in any order. Fails if the shares
+/// belong to different payloads or rebuild one that does not match its digest.
+pub fn combine(shares: &[Share]) -> Result<Vec<u8>, PayloadError> {
+    let Some(first) = shares.first() else {
+        return Err(PayloadError::NotEnoughShares {
+            needed: 1,
+            found: 0,
+        });
+    };
+    if let Some(other) = shares.iter().find(|s| s.id != first.id) {
+        return Err(PayloadError::InconsistentShares(format!(
+            "shares of payloads {:016x} and {:016x} are mixed",
+            first.id(),
+            other.id()
+        )));
+    }
+    if let Some(other) = shares
+        .iter()
+        .find(|s| s.threshold != first.threshold || s.data.len() != first.data.len())
+    {
+        return Err(PayloadError::InconsistentShares(format!(
+            "shares {} and {} disagree on the threshold or the length",
+            first.index, other.index
+        )));
+    }
+
+    let mut distinct: Vec<&Share> = Vec::new();
+    for share in shares {
+        match distinct.iter().find(|s| s.index == share.index) {
+            Some(same) if same.data != share.data => {
+                return Err(PayloadError::InconsistentShares(format!(
+                    "two different shares have index {}",
+                    share.index
+                )))
+            }
+            Some(_) => {}
+            None => distinct.push(share),
+        }
+    }
+    let needed = first.threshold as usize;
+    if distinct.len() < needed {
+        return Err(PayloadError::NotEnoughShares {
+            needed,
+            found: distinct.len(),
+        });
+    }
+
+    // Lagrange interpolation at 0, where each share's weight only depends on the indices
+    let distinct = &distinct[..needed];
+    let weights: Vec<u8> = distinct
+        .iter()
+        .map(|share| {
+            distinct
+                .iter()
+                .filter(|other| other.index != share.index)
+                .fold(1, |w, other| {
+                    mul(w, div(other.index, other.index ^ share.index))
+                })
+        })
+        .collect();
+    let mut secret = vec![0; first.data.len()];
+    for (share, &weight) in distinct.iter().zip(&weights) {
+        for (byte, &y) in secret.iter_mut().zip(share.data) {
+            *byte ^= mul(y, weight);
+        }
+    }
+
+    let digest = secret.split_off(secret.len() - DIGEST_LENGTH);
+    if digest[..] != Sha256::digest(&secret)[..] {
+        return Err(PayloadError::InconsistentShares(
+            "the shares do not rebuild the payload they were made from".to_string(),
+        ));
+    }
+    Ok(secret)
+}
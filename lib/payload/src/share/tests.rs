@@ -0,0 +1,115 @@
+use super::*;
+
+fn payload() -> Vec<u8> {
+    (0..500u32).map(|i| (i * 31) as u8).collect()
+}
+
+fn parse(shares: &[Vec<u8>]) -> Vec<Share<'_>> {
+    shares.iter().map(|s| Share::parse(s).unwrap()).collect()
+}
+
+#[test]
+fn test_any_threshold_shares_rebuild() {
+    let payload = payload();
+    let shares = split(&payload, 3, 5).unwrap();
+    assert_eq!(shares.len(), 5);
+    assert!(shares
+        .iter()
+        .all(|s| is_share(s) && s.len() == share_length(500)));
+
+    let parsed = parse(&shares);
+    assert_eq!(parsed[4].index(), 5);
+    assert_eq!(parsed[4].threshold(), 3);
+    for picked in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+        let subset: Vec<Share> = picked.iter().map(|&i| parsed[i].clone()).collect();
+        assert_eq!(combine(&subset).unwrap(), payload);
+    }
+    assert_eq!(combine(&parsed).unwrap(), payload);
+}
+
+#[test]
+fn test_single_share_threshold() {
+    let shares = split(b"copies", 1, 2).unwrap();
+    // with a threshold of 1 each share is the payload itself
+    assert_eq!(&shares[0][HEADER_LENGTH..HEADER_LENGTH + 6], b"copies");
+    assert_eq!(combine(&parse(&shares[1..])).unwrap(), b"copies");
+}
+
+#[test]
+fn test_too_few_shares() {
+    let shares = split(&payload(), 3, 5).unwrap();
+    let parsed = parse(&shares);
+    let duplicated = [parsed[0].clone(), parsed[3].clone(), parsed[0].clone()];
+    assert!(matches!(
+        combine(&duplicated),
+        Err(PayloadError::NotEnoughShares {
+            needed: 3,
+            found: 2
+        })
+    ));
+}
+
+#[test]
+fn test_invalid_counts() {
+    for (threshold, shares) in [(0, 3), (4, 3), (2, 256)] {
+        assert!(matches!(
+            split(b"x", threshold, shares),
+            Err(PayloadError::ShareCount { .. })
+        ));
+    }
+}
+
+#[test]
+fn test_corrupt_share() {
+    let mut shares = split(&payload(), 2, 3).unwrap();
+    shares[1][HEADER_LENGTH + 7] ^= 0x40;
+    assert!(matches!(
+        Share::parse(&shares[1]),
+        Err(PayloadError::CorruptShare(2))
+    ));
+}
+
+#[test]
+fn test_mismatched_shares() {
+    let first = split(&payload(), 2, 3).unwrap();
+    let second = split(&payload(), 2, 3).unwrap();
+    let mixed = [
+        Share::parse(&first[0]).unwrap(),
+        Share::parse(&second[1]).unwrap(),
+    ];
+    assert!(matches!(
+        combine(&mixed),
+        Err(PayloadError::InconsistentShares(_))
+    ));
+
+    let other_length = split(b"short", 2, 3).unwrap();
+    let mixed = [
+        Share::parse(&first[0]).unwrap(),
+        Share::parse(&other_length[1]).unwrap(),
+    ];
+    assert!(matches!(
+        combine(&mixed),
+        Err(PayloadError::InconsistentShares(_))
+    ));
+}
+
+#[test]
+fn test_forged_share_fails_digest() {
+    let shares = split(&payload(), 2, 3).unwrap();
+    // a share altered with a matching checksum still rebuilds the wrong payload
+    let mut data = shares[2][HEADER_LENGTH..].to_vec();
+    data[0] ^= 1;
+    let forged = Share {
+        data: &data,
+        ..Share::parse(&shares[2]).unwrap()
+    }
+    .to_bytes();
+    let parsed = [
+        Share::parse(&shares[0]).unwrap(),
+        Share::parse(&forged).unwrap(),
+    ];
+    assert!(matches!(
+        combine(&parsed),
+        Err(PayloadError::InconsistentShares(_))
+    ));
+}
@@ -0,0 +1,236 @@
+//! Ed25519 signatures over a payload, the chunk type it is stored in and optionally a digest of
+//! the critical image data.
+//!
+//! | Field        | Size     |                                     |
+//! |--------------|----------|-------------------------------------|
+//! | Magic        | 4 bytes  | `PMsg`                              |
+//! | Version      | 1 byte   | 1                                   |
+//! | Flags        | 1 byte   | bit 0: an image digest follows      |
+//! | Signer       | 32 bytes | Ed25519 public key                  |
+//! | Image digest | 32 bytes | only if flagged                     |
+//! | Signature    | 64 bytes |                                     |
+//! | Payload      | rest     |                                     |
+//!
+//! The signature covers every field before it, then the chunk type, the length of the associated
+//! data given by the caller as 4 big endian bytes, the associated data and the payload. The
+//! associated data is not stored.
+
+use crate::{read_array, PayloadError};
+use chacha20poly1305::aead::OsRng;
+use ed25519_dalek::{Signature, Signer};
+use std::{fmt, str::FromStr};
+
+#[cfg(test)]
+mod tests;
+
+const MAGIC: [u8; 4] = *b"PMsg";
+const VERSION: u8 = 1;
+const FLAG_IMAGE_DIGEST: u8 = 1;
+const KEY_LENGTH: usize = 32;
+const DIGEST_LENGTH: usize = 32;
+const SIGNATURE_LENGTH: usize = 64;
+
+const PUBLIC_KEY_PREFIX: &str = "msg-sign-pub-";
+const SECRET_KEY_PREFIX: &str = "MSG-SIGN-SECRET-";
+
+/// An Ed25519 public key, written as `msg-sign-pub-` followed by 64 hex digits.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct VerifyingKey(ed25519_dalek::VerifyingKey);
+
+/// An Ed25519 secret key, written as `MSG-SIGN-SECRET-` followed by 64 hex digits.
+#[derive(Clone)]
+pub struct SigningKey(ed25519_dalek::SigningKey);
+
+impl SigningKey {
+    pub fn generate() -> SigningKey {
+        SigningKey(ed25519_dalek::SigningKey::generate(&mut OsRng))
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        VerifyingKey(self.0.verifying_key())
+    }
+
+    /// Parses a key file: the first line that is neither blank nor a `#` comment.
+    pub fn from_file_contents(contents: &str) -> Result<SigningKey, PayloadError> {
+        crate::first_key_line(contents)?.parse()
+    }
+
+    /// Contents of a key file, with the public key as a comment.
+    pub fn to_file_contents(&self) -> String {
+        format!("# public key: {}\n{self}\n", self.verifying_key())
+    }
+}
+
+impl FromStr for VerifyingKey {
+    type Err = PayloadError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = crate::parse_key(s, PUBLIC_KEY_PREFIX)?;
+        ed25519_dalek::VerifyingKey::from_bytes(&bytes)
+            .map(VerifyingKey)
+            .map_err(|e| PayloadError::Key(format!("'{s}': {e}")))
+    }
+}
+
+impl FromStr for SigningKey {
+    type Err = PayloadError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::parse_key(s, SECRET_KEY_PREFIX).map(|k| SigningKey(k.into()))
+    }
+}
+
+impl fmt::Display for VerifyingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{PUBLIC_KEY_PREFIX}{}", hex::encode(self.0.as_bytes()))
+    }
+}
+
+impl fmt::Debug for VerifyingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VerifyingKey({self})")
+    }
+}
+
+impl fmt::Display for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{SECRET_KEY_PREFIX}{}", hex::encode(self.0.as_bytes()))
+    }
+}
+
+impl fmt::Debug for SigningKey {
+    /// Never prints the secret.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SigningKey({})", self.verifying_key())
+    }
+}
+
+/// Whether `data` starts like a signed payload.
+pub fn is_signed(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// The bytes covered by the signature.
+fn signed_message(
+    header: &[u8],
+    chunk_type: &[u8; 4],
+    associated_data: &[u8],
+    payload: &[u8],
+) -> Vec<u8> {
+    let length = (associated_data.len() as u32).to_be_bytes();
+    [header, chunk_type, &length, associated_data, payload].concat()
+}
+
+/// Bytes signing adds in front of the payload, with or without an image digest.
+pub fn overhead(image_digest: bool) -> usize {
+    let digest = if image_digest { DIGEST_LENGTH } else { 0 };
+    4 + 1 + 1 + KEY_LENGTH + digest + SIGNATURE_LENGTH
+}
+
+/// Signs `payload` and `associated_data` as stored in a chunk of type `chunk_type`, binding them
+/// to `image_digest` if given.
+pub fn sign(
+    payload: &[u8],
+    associated_data: &[u8],
+    chunk_type: &[u8; 4],
+    image_digest: Option<&[u8; DIGEST_LENGTH]>,
+    key: &SigningKey,
+) -> Vec<u8> {
+    let mut signed = Vec::new();
+    signed.extend(MAGIC);
+    signed.push(VERSION);
+    signed.push(if image_digest.is_some() {
+        FLAG_IMAGE_DIGEST
+    } else {
+        0
+    });
+    signed.extend(key.verifying_key().0.as_bytes());
+    if let Some(digest) = image_digest {
+        signed.extend(digest);
+    }
+
+    let message = signed_message(&signed, chunk_type, associated_data, payload);
+    let signature = key.0.sign(&message);
+    signed.extend(signature.to_bytes());
+    signed.extend(payload);
+    signed
+}
+
+/// A parsed signed payload. Parsing does not check the signature, see [`Signed::verify`].
+#[derive(Debug)]
+pub struct Signed<'a> {
+    header: &'a [u8],
+    signer: VerifyingKey,
+    image_digest: Option<[u8; DIGEST_LENGTH]>,
+    signature: Signature,
+    payload: &'a [u8],
+}
+
+impl<'a> Signed<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Signed<'a>, PayloadError> {
+        if !is_signed(data) {
+            return Err(PayloadError::Truncated);
+        }
+        let version = *data.get(4).ok_or(PayloadError::Truncated)?;
+        if version != VERSION {
+            return Err(PayloadError::UnsupportedVersion(version));
+        }
+        let flags = *data.get(5).ok_or(PayloadError::Truncated)?;
+        let signer = ed25519_dalek::VerifyingKey::from_bytes(&read_array(data, 6)?)
+            .map_err(|e| PayloadError::Key(e.to_string()))?;
+
+        let mut offset = 6 + KEY_LENGTH;
+        let image_digest = if flags & FLAG_IMAGE_DIGEST != 0 {
+            offset += DIGEST_LENGTH;
+            Some(read_array(data, offset - DIGEST_LENGTH)?)
+        } else {
+            None
+        };
+        let signature = Signature::from_bytes(&read_array(data, offset)?);
+
+        Ok(Signed {
+            header: &data[..offset],
+            signer: VerifyingKey(signer),
+            image_digest,
+            signature,
+            payload: &data[offset + SIGNATURE_LENGTH..],
+        })
+    }
+
+    /// Key the payload claims to be signed with.
+    pub fn signer(&self) -> &VerifyingKey {
+        &self.signer
+    }
+
+    /// Digest of the critical image data at signing time, if it was signed.
+    pub fn image_digest(&self) -> Option<&[u8; DIGEST_LENGTH]> {
+        self.image_digest.as_ref()
+    }
+
+    /// The payload without the signature.
+    pub fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+
+    /// Checks the payload was signed by `key` with `associated_data` while stored as
+    /// `chunk_type`, and, if an image digest was signed, that it matches `image_digest`.
+    pub fn verify(
+        &self,
+        key: &VerifyingKey,
+        associated_data: &[u8],
+        chunk_type: &[u8; 4],
+        image_digest: &[u8; DIGEST_LENGTH],
+    ) -> Result<(), PayloadError> {
+        if &self.signer != key {
+            return Err(PayloadError::SignerMismatch(self.signer.to_string()));
+        }
+        let message = signed_message(self.header, chunk_type, associated_data, self.payload);
+        key.0
+            .verify_strict(&message, &self.signature)
+            .map_err(|_| PayloadError::BadSignature)?;
+        if self.image_digest.is_some_and(|d| &d != image_digest) {
+            return Err(PayloadError::ImageModified);
+        }
+        Ok(())
+    }
+}
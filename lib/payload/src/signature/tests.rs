@@ -0,0 +1,110 @@
+use super::*;
+
+const CHUNK_TYPE: &[u8; 4] = b"ruSt";
+const DIGEST: [u8; 32] = [7; 32];
+
+#[test]
+fn test_sign_verify() {
+    let key = SigningKey::generate();
+    let signed = sign(b"message", b"", CHUNK_TYPE, None, &key);
+    assert!(is_signed(&signed));
+    assert_eq!(signed.len(), overhead(false) + 7);
+
+    let parsed = Signed::parse(&signed).unwrap();
+    assert_eq!(parsed.payload(), b"message");
+    assert_eq!(parsed.signer(), &key.verifying_key());
+    assert!(parsed.image_digest().is_none());
+    // without a signed digest the image may change
+    parsed
+        .verify(&key.verifying_key(), b"", CHUNK_TYPE, &[0; 32])
+        .unwrap();
+}
+
+#[test]
+fn test_modified_payload() {
+    let key = SigningKey::generate();
+    let mut signed = sign(b"message", b"", CHUNK_TYPE, None, &key);
+    *signed.last_mut().unwrap() ^= 1;
+    let parsed = Signed::parse(&signed).unwrap();
+    assert!(matches!(
+        parsed.verify(&key.verifying_key(), b"", CHUNK_TYPE, &DIGEST),
+        Err(PayloadError::BadSignature)
+    ));
+}
+
+#[test]
+fn test_modified_associated_data() {
+    let key = SigningKey::generate();
+    let signed = sign(b"message", b"header", CHUNK_TYPE, None, &key);
+    let parsed = Signed::parse(&signed).unwrap();
+    parsed
+        .verify(&key.verifying_key(), b"header", CHUNK_TYPE, &DIGEST)
+        .unwrap();
+    assert!(matches!(
+        parsed.verify(&key.verifying_key(), b"header2", CHUNK_TYPE, &DIGEST),
+        Err(PayloadError::BadSignature)
+    ));
+}
+
+#[test]
+fn test_moved_to_other_chunk_type() {
+    let key = SigningKey::generate();
+    let signed = sign(b"message", b"", CHUNK_TYPE, None, &key);
+    let parsed = Signed::parse(&signed).unwrap();
+    assert!(matches!(
+        parsed.verify(&key.verifying_key(), b"", b"ruSx", &DIGEST),
+        Err(PayloadError::BadSignature)
+    ));
+}
+
+#[test]
+fn test_other_signer() {
+    let key = SigningKey::generate();
+    let other = SigningKey::generate();
+    let signed = sign(b"message", b"", CHUNK_TYPE, None, &key);
+    let parsed = Signed::parse(&signed).unwrap();
+    assert!(matches!(
+        parsed.verify(&other.verifying_key(), b"", CHUNK_TYPE, &DIGEST),
+        Err(PayloadError::SignerMismatch(_))
+    ));
+}
+
+#[test]
+fn test_image_digest() {
+    let key = SigningKey::generate();
+    let signed = sign(b"message", b"", CHUNK_TYPE, Some(&DIGEST), &key);
+    assert_eq!(signed.len(), overhead(true) + 7);
+    let parsed = Signed::parse(&signed).unwrap();
+    assert_eq!(parsed.image_digest(), Some(&DIGEST));
+    assert_eq!(parsed.payload(), b"message");
+
+    parsed
+        .verify(&key.verifying_key(), b"", CHUNK_TYPE, &DIGEST)
+        .unwrap();
+    assert!(matches!(
+        parsed.verify(&key.verifying_key(), b"", CHUNK_TYPE, &[0; 32]),
+        Err(PayloadError::ImageModified)
+    ));
+}
+
+#[test]
+fn test_truncated() {
+    let key = SigningKey::generate();
+    let signed = sign(b"", b"", CHUNK_TYPE, None, &key);
+    assert!(Signed::parse(&signed).is_ok());
+    assert!(matches!(
+        Signed::parse(&signed[..signed.len() - 1]),
+        Err(PayloadError::Truncated)
+    ));
+}
+
+#[test]
+fn test_key_strings() {
+    let key = SigningKey::generate();
+    let public = key.verifying_key();
+    assert_eq!(public.to_string().parse::<VerifyingKey>().unwrap(), public);
+
+    let parsed = SigningKey::from_file_contents(&key.to_file_contents()).unwrap();
+    assert_eq!(parsed.verifying_key(), public);
+    assert!(!format!("{key:?}").contains("MSG-SIGN-SECRET"));
+}
@@ -25,6 +25,8 @@ impl Chunk {
 
     // const MAX_DATA_LENGTH: usize = 2_usize.pow(31);
 
+    const CRC: Crc<u32> = Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+
     /// A 4-byte unsigned integer giving the number of bytes in the chunk's data field. The length
     /// counts **only the data field**, not itself, the chunk type code, or the CRC. Zero is a valid
     /// length. Although encoders and decoders should treat the length as unsigned, its value must
@@ -52,13 +54,10 @@ impl Chunk {
     /// including the chunk type code and chunk data fields, but **not** including the length
     /// field. The CRC is always present, even for chunks containing no data.
     pub fn crc(&self) -> u32 {
-        let length = 4 + self.data_length(); // 4 byte chunk type + data length
-        let mut bytes: Vec<u8> = Vec::with_capacity(length);
-        bytes.extend(self.chunk_type.bytes());
-        bytes.extend(self.data.iter());
-
-        let crc = Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
-        crc.checksum(&bytes)
+        let mut digest = Self::CRC.digest();
+        digest.update(&self.chunk_type.bytes());
+        digest.update(&self.data);
+        digest.finalize()
     }
 
     pub fn data_as_string(&self) -> Result<String, Utf8Error> {
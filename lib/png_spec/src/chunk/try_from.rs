@@ -19,9 +19,10 @@ impl TryFrom<&[u8]> for Chunk {
         let chunk = Self::new(chunk_type, data);
 
         // validating chunk
-        if chunk.crc() != crc {
+        let expected = chunk.crc();
+        if expected != crc {
             return Err(ChunkError::Crc {
-                expected: chunk.crc(),
+                expected,
                 actual: crc,
             });
         }
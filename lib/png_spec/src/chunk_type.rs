@@ -88,6 +88,29 @@ impl ChunkType {
     /// Property bits position
     const POSITION: util::Bit = util::Bit::Five;
 
+    /// Image header
+    pub const IHDR: ChunkType = ChunkType::from_bytes_unchecked(*b"IHDR");
+    /// Palette
+    pub const PLTE: ChunkType = ChunkType::from_bytes_unchecked(*b"PLTE");
+    /// Image data
+    pub const IDAT: ChunkType = ChunkType::from_bytes_unchecked(*b"IDAT");
+    /// Image trailer
+    pub const IEND: ChunkType = ChunkType::from_bytes_unchecked(*b"IEND");
+    /// International textual data
+    pub const ITXT: ChunkType = ChunkType::from_bytes_unchecked(*b"iTXt");
+
+    /// Builds a chunk type without validating the bytes. Only used for the well known chunk types
+    /// defined by the specification.
+    const fn from_bytes_unchecked(bytes: [u8; 4]) -> ChunkType {
+        let [ancillary, private, reserved, safe_to_copy] = bytes;
+        ChunkType {
+            ancillary,
+            private,
+            reserved,
+            safe_to_copy,
+        }
+    }
+
     /// Valid bytes are represented by the characters A-Z or a-z
     pub fn is_valid_byte(byte: u8) -> bool {
         // restricted to uper and lower case ASCII letters.
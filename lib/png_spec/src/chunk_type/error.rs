@@ -55,7 +55,7 @@ impl fmt::Display for ChunkTypeError {
                 "invalid '{byte}' byte: ['{}'/'{:#02X}'].\n\
                 Chunk type codes are restricted to consist of uppercase or lowercase \
                 ASCII letters (A-Z or a-z).",
-                char::try_from(byte.value()).unwrap_or(char::REPLACEMENT_CHARACTER),
+                char::from(byte.value()),
                 byte.value()
             ),
             ChunkTypeError::InvalidLength(e) => {
@@ -0,0 +1,434 @@
+use crate::{chunk::Chunk, chunk_type::ChunkType, png::Png};
+
+pub use self::{
+    encode::{EncodeOptions, FilterStrategy},
+    error::ImageError,
+    filter::FilterType,
+    header::{ColorType, Header},
+};
+
+mod decode;
+mod encode;
+mod error;
+mod filter;
+mod header;
+mod reduce;
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+/// Decoded image: unfiltered, non-interlaced scanlines packed at the header's bit depth, together
+/// with the palette and transparency needed to interpret them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    header: Header,
+    /// PLTE entries; empty if there is no palette
+    palette: Vec<[u8; 3]>,
+    /// Raw tRNS data; empty if there is no transparency chunk
+    transparency: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl Image {
+    /// Transparency chunk
+    const TRNS: &'static str = "tRNS";
+
+    /// Background colour chunk
+    const BKGD: &'static str = "bKGD";
+
+    /// Palette histogram chunk
+    const HIST: &'static str = "hIST";
+
+    pub fn new(header: Header, data: Vec<u8>) -> Result<Image, ImageError> {
+        let expected = header.data_length().ok_or(ImageError::TooLarge {
+            width: header.width(),
+            height: header.height(),
+        })?;
+        if data.len() != expected {
+            return Err(ImageError::DataLength {
+                expected,
+                actual: data.len(),
+            });
+        }
+        Ok(Image {
+            header,
+            palette: Vec::new(),
+            transparency: Vec::new(),
+            data,
+        })
+    }
+
+    pub fn with_palette(mut self, palette: Vec<[u8; 3]>, transparency: Vec<u8>) -> Image {
+        self.palette = palette;
+        self.transparency = transparency;
+        self
+    }
+
+    /// Decodes the image data of a PNG file.
+    pub fn from_png(png: &Png) -> Result<Image, ImageError> {
+        let mut image = Self::format_of(png)?;
+        image.data = decode::decode(&image.header, &png.image_data())?;
+        // the data is no longer interlaced
+        image.header = Header::new(
+            image.width(),
+            image.height(),
+            image.header.bit_depth(),
+            image.header.color_type(),
+        )?;
+        Ok(image)
+    }
+
+    /// Filter type of every scanline of a PNG file, pass by pass if interlaced.
+    pub fn filter_types(png: &Png) -> Result<Vec<FilterType>, ImageError> {
+        let image = Self::format_of(png)?;
+        decode::filter_types(&image.header, &png.image_data())
+    }
+
+    /// Filter type the adaptive heuristic of the specification picks for every scanline, as the
+    /// encoder would with [`FilterStrategy::Adaptive`].
+    pub fn adaptive_filter_types(&self) -> Vec<FilterType> {
+        let row_bytes = self.header.row_bytes(self.width());
+        let distance = self.header.filter_distance();
+        let zeros = vec![0; row_bytes];
+        let rows: Vec<&[u8]> = self.data.chunks_exact(row_bytes).collect();
+        rows.iter()
+            .enumerate()
+            .map(|(y, row)| {
+                let previous = if y == 0 { &zeros[..] } else { rows[y - 1] };
+                filter::adaptive(distance, previous, row)
+            })
+            .collect()
+    }
+
+    /// Header, palette and transparency of a PNG file, without decoding the image data.
+    fn format_of(png: &Png) -> Result<Image, ImageError> {
+        let header = png
+            .chunks()
+            .first()
+            .ok_or(ImageError::MissingChunk(ChunkType::IHDR))
+            .and_then(Header::try_from)?;
+
+        let palette: Vec<[u8; 3]> = png
+            .chunk_by_type("PLTE")
+            .map(|c| {
+                c.data()
+                    .chunks_exact(3)
+                    .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+                    .collect()
+            })
+            .unwrap_or_default();
+        if header.color_type() == ColorType::Indexed && palette.is_empty() {
+            return Err(ImageError::MissingChunk(ChunkType::PLTE));
+        }
+        let transparency = png
+            .chunk_by_type(Self::TRNS)
+            .map(|c| c.data().to_vec())
+            .unwrap_or_default();
+
+        Ok(Image {
+            header,
+            palette,
+            transparency,
+            data: Vec::new(),
+        })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    pub fn width(&self) -> u32 {
+        self.header.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.header.height()
+    }
+
+    pub fn palette(&self) -> &[[u8; 3]] {
+        &self.palette
+    }
+
+    pub fn transparency(&self) -> &[u8] {
+        &self.transparency
+    }
+
+    /// Unfiltered scanlines without filter type bytes.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    /// Bit offset of `channel` of the pixel at `x`, `y`.
+    fn sample_offset(&self, x: u32, y: u32, channel: usize) -> usize {
+        let row = self.header.row_bytes(self.width()) * 8 * y as usize;
+        let depth = self.header.bit_depth() as usize;
+        row + (x as usize * self.header.color_type().channels() + channel) * depth
+    }
+
+    /// Raw sample value of `channel` of the pixel at `x`, `y`, at the image's bit depth.
+    pub fn sample(&self, x: u32, y: u32, channel: usize) -> u16 {
+        let offset = self.sample_offset(x, y, channel);
+        let byte = offset / 8;
+        match self.header.bit_depth() {
+            16 => u16::from_be_bytes([self.data[byte], self.data[byte + 1]]),
+            8 => self.data[byte].into(),
+            depth => {
+                let shift = 8 - depth as usize - offset % 8;
+                ((self.data[byte] >> shift) & ((1 << depth) - 1)).into()
+            }
+        }
+    }
+
+    /// Sets the raw sample value of `channel` of the pixel at `x`, `y`.
+    pub fn set_sample(&mut self, x: u32, y: u32, channel: usize, value: u16) {
+        let offset = self.sample_offset(x, y, channel);
+        let byte = offset / 8;
+        match self.header.bit_depth() {
+            16 => self.data[byte..byte + 2].copy_from_slice(&value.to_be_bytes()),
+            8 => self.data[byte] = value as u8,
+            depth => {
+                let shift = 8 - depth as usize - offset % 8;
+                let mask = ((1u8 << depth) - 1) << shift;
+                self.data[byte] = (self.data[byte] & !mask) | (((value as u8) << shift) & mask);
+            }
+        }
+    }
+
+    /// Every pixel as 16-bit RGBA, row by row. Palette, transparency and bit depth are applied,
+    /// so two images with the same RGBA16 pixels look exactly the same.
+    pub fn to_rgba16(&self) -> Vec<[u16; 4]> {
+        let depth = self.header.bit_depth();
+        let color_type = self.header.color_type();
+        let key = |i: usize| {
+            self.transparency
+                .get(2 * i..2 * i + 2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        };
+
+        let mut pixels = Vec::with_capacity(self.width() as usize * self.height() as usize);
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let s = |c| self.sample(x, y, c);
+                let pixel = match color_type {
+                    ColorType::Grayscale => {
+                        let v = s(0);
+                        let alpha = if key(0) == Some(v) { 0 } else { u16::MAX };
+                        let v = scale_to_16(v, depth);
+                        [v, v, v, alpha]
+                    }
+                    ColorType::GrayscaleAlpha => {
+                        let v = scale_to_16(s(0), depth);
+                        [v, v, v, scale_to_16(s(1), depth)]
+                    }
+                    ColorType::Rgb => {
+                        let rgb = [s(0), s(1), s(2)];
+                        let keyed = [key(0), key(1), key(2)] == rgb.map(Some);
+                        let alpha = if keyed { 0 } else { u16::MAX };
+                        let [r, g, b] = rgb.map(|v| scale_to_16(v, depth));
+                        [r, g, b, alpha]
+                    }
+                    ColorType::Rgba => [s(0), s(1), s(2), s(3)].map(|v| scale_to_16(v, depth)),
+                    ColorType::Indexed => {
+                        let i = s(0) as usize;
+                        let [r, g, b] = self.palette.get(i).copied().unwrap_or_default();
+                        let a = self.transparency.get(i).copied().unwrap_or(u8::MAX);
+                        [r, g, b, a].map(|v| scale_to_16(v.into(), 8))
+                    }
+                };
+                pixels.push(pixel);
+            }
+        }
+        pixels
+    }
+
+    /// Whether the two images look the same composited over any background: the same size and,
+    /// pixel by pixel, the same alpha and the same color wherever the pixel is not fully
+    /// transparent.
+    pub fn looks_like(&self, other: &Image) -> bool {
+        self.width() == other.width()
+            && self.height() == other.height()
+            && self
+                .to_rgba16()
+                .iter()
+                .zip(other.to_rgba16())
+                .all(|(a, b)| a[3] == b[3] && (a[3] == 0 || *a == b))
+    }
+
+    /// Checks that every palette index refers to an existing entry.
+    pub fn validate_palette(&self) -> Result<(), ImageError> {
+        if self.header.color_type() != ColorType::Indexed {
+            return Ok(());
+        }
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let i = self.sample(x, y, 0);
+                if i as usize >= self.palette.len() {
+                    return Err(ImageError::PaletteIndex(i as u8));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes the image and writes it into `png`, replacing IHDR, PLTE, tRNS and IDAT. Ancillary
+    /// chunks that depend on the image format are converted when possible and dropped otherwise.
+    pub fn write_to(&self, png: &mut Png, options: &EncodeOptions) -> Result<(), ImageError> {
+        self.validate_palette()?;
+        let data = self.encode(options)?;
+        self.write_stream_to(png, &data)
+    }
+
+    /// Like [`Image::write_to`], with the filter of every scanline given instead of
+    /// `options.filter`.
+    pub fn write_filtered_to(
+        &self,
+        png: &mut Png,
+        filters: &[FilterType],
+        options: &EncodeOptions,
+    ) -> Result<(), ImageError> {
+        self.validate_palette()?;
+        let data = self.encode_with_filters(filters, options)?;
+        self.write_stream_to(png, &data)
+    }
+
+    /// Writes the header, palette and the encoded image data `data` into `png`.
+    fn write_stream_to(&self, png: &mut Png, data: &[u8]) -> Result<(), ImageError> {
+        let previous = Image::format_of(png)?;
+
+        if previous.header != self.header
+            || previous.palette != self.palette
+            || previous.transparency != self.transparency
+        {
+            let background = png
+                .chunk_by_type(Self::BKGD)
+                .and_then(|c| previous.background_to_rgb16(c.data()))
+                .and_then(|rgb| self.background_from_rgb16(rgb));
+            let histogram = png
+                .chunk_by_type(Self::HIST)
+                .and_then(|c| self.histogram_from(&previous, c.data()));
+            let format_changed = previous.header.bit_depth() != self.header.bit_depth()
+                || previous.header.color_type() != self.header.color_type();
+            let gray_changed = previous.header.color_type().is_grayscale()
+                != self.header.color_type().is_grayscale();
+
+            png.remove_chunks_where(|c| {
+                let t = c.chunk_type().to_string();
+                let dependent = matches!(t.as_str(), "IHDR" | "PLTE" | "tRNS" | "bKGD" | "hIST");
+                // significant bits are given per channel of the old format, and an ICC profile
+                // must be for gray images if and only if the image is gray
+                dependent || (format_changed && t == "sBIT") || (gray_changed && t == "iCCP")
+            });
+            png.insert_chunk(0, self.header.to_chunk());
+
+            let mut index = png
+                .chunks()
+                .iter()
+                .position(|c| c.chunk_type() == &ChunkType::IDAT)
+                .unwrap_or(1);
+            let mut insert = |chunk| {
+                png.insert_chunk(index, chunk);
+                index += 1;
+            };
+            if !self.palette.is_empty() {
+                insert(Chunk::new(ChunkType::PLTE, self.palette.concat()));
+            }
+            if !self.transparency.is_empty() {
+                let trns = Self::TRNS.parse().expect("tRNS is a valid chunk type");
+                insert(Chunk::new(trns, self.transparency.clone()));
+            }
+            if let Some(background) = background {
+                let bkgd = Self::BKGD.parse().expect("bKGD is a valid chunk type");
+                insert(Chunk::new(bkgd, background));
+            }
+            if let Some(histogram) = histogram {
+                let hist = Self::HIST.parse().expect("hIST is a valid chunk type");
+                insert(Chunk::new(hist, histogram));
+            }
+        }
+
+        png.set_image_data(data, Png::MAX_CHUNK_DATA_LENGTH)?;
+        Ok(())
+    }
+
+    /// hIST data of `previous` for this image's palette. Each entry keeps the count of the same
+    /// color, so reordered or reduced palettes keep their histogram. `None` if a color is new.
+    fn histogram_from(&self, previous: &Image, data: &[u8]) -> Option<Vec<u8>> {
+        if self.palette.is_empty() || data.len() != previous.palette.len() * 2 {
+            return None;
+        }
+        let mut histogram = Vec::with_capacity(self.palette.len() * 2);
+        for color in &self.palette {
+            let old = previous.palette.iter().position(|c| c == color)?;
+            histogram.extend_from_slice(&data[2 * old..2 * old + 2]);
+        }
+        Some(histogram)
+    }
+
+    /// Interprets bKGD data in this image's format.
+    fn background_to_rgb16(&self, data: &[u8]) -> Option<[u16; 3]> {
+        let depth = self.header.bit_depth();
+        let value = |i: usize| {
+            let v = u16::from_be_bytes(data.get(2 * i..2 * i + 2)?.try_into().ok()?);
+            Some(scale_to_16(v, depth))
+        };
+        match self.header.color_type() {
+            ColorType::Grayscale | ColorType::GrayscaleAlpha => value(0).map(|v| [v, v, v]),
+            ColorType::Rgb | ColorType::Rgba => Some([value(0)?, value(1)?, value(2)?]),
+            ColorType::Indexed => {
+                let rgb = self.palette.get(*data.first()? as usize)?;
+                Some(rgb.map(|v| scale_to_16(v.into(), 8)))
+            }
+        }
+    }
+
+    /// Expresses a background colour as bKGD data in this image's format, if it can be
+    /// represented exactly.
+    fn background_from_rgb16(&self, rgb: [u16; 3]) -> Option<Vec<u8>> {
+        let depth = self.header.bit_depth();
+        match self.header.color_type() {
+            ColorType::Grayscale | ColorType::GrayscaleAlpha => {
+                if rgb[0] != rgb[1] || rgb[1] != rgb[2] {
+                    return None;
+                }
+                Some(scale_from_16(rgb[0], depth)?.to_be_bytes().to_vec())
+            }
+            ColorType::Rgb | ColorType::Rgba => {
+                let mut data = Vec::with_capacity(6);
+                for v in rgb {
+                    data.extend(scale_from_16(v, depth)?.to_be_bytes());
+                }
+                Some(data)
+            }
+            ColorType::Indexed => {
+                let rgb8 = rgb.map(|v| scale_from_16(v, 8));
+                let index = self
+                    .palette
+                    .iter()
+                    .position(|p| p.map(|v| Some(u16::from(v))) == rgb8)?;
+                Some(vec![index as u8])
+            }
+        }
+    }
+}
+
+/// Largest sample value at `depth` bits.
+fn max_value(depth: u8) -> u32 {
+    (1 << depth) - 1
+}
+
+/// Scales a sample at `depth` bits to 16 bits.
+fn scale_to_16(value: u16, depth: u8) -> u16 {
+    (value as u32 * 0xffff / max_value(depth)) as u16
+}
+
+/// Scales a 16-bit sample down to `depth` bits, if it can be represented exactly.
+fn scale_from_16(value: u16, depth: u8) -> Option<u16> {
+    let step = 0xffff / max_value(depth);
+    (value as u32)
+        .is_multiple_of(step)
+        .then_some((value as u32 / step) as u16)
+}
@@ -0,0 +1,185 @@
+use super::{
+    error::ImageError,
+    filter::{self, FilterType},
+    header::Header,
+};
+use flate2::read::ZlibDecoder;
+use std::io::Read;
+
+/// Adam7 passes as (first column, first row, column step, row step).
+///
+/// ['Interlacing and pass extraction'](https://www.w3.org/TR/png/#8Interlace)
+const ADAM7: [(u32, u32, u32, u32); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Inflates, unfilters and deinterlaces the image data stream into packed scanlines.
+pub fn decode(header: &Header, stream: &[u8]) -> Result<Vec<u8>, ImageError> {
+    let raw = inflate(header, stream)?;
+
+    if !header.is_interlaced() {
+        let (data, used) = unfilter_pass(header, header.width(), header.height(), &raw)?;
+        check_length(raw.len(), used)?;
+        return Ok(data);
+    }
+
+    // no larger than the stream already inflated, which holds a filter byte per scanline more
+    let row_bytes = header.row_bytes(header.width());
+    let mut data = vec![0; row_bytes * header.height() as usize];
+    let mut remaining = raw.as_slice();
+
+    for (x0, y0, dx, dy) in ADAM7 {
+        let (width, height) = pass_size(header, (x0, y0, dx, dy));
+        if width == 0 || height == 0 {
+            continue;
+        }
+        let (pass, used) = unfilter_pass(header, width, height, remaining)?;
+        remaining = &remaining[used..];
+
+        let pass_row_bytes = header.row_bytes(width);
+        for py in 0..height {
+            let row = &pass[py as usize * pass_row_bytes..][..pass_row_bytes];
+            let y = (y0 + py * dy) as usize;
+            for px in 0..width {
+                let x = x0 + px * dx;
+                copy_pixel(header, row, px, &mut data[y * row_bytes..], x);
+            }
+        }
+    }
+    check_length(remaining.len(), 0)?;
+    Ok(data)
+}
+
+/// Filter type of every scanline in the image data stream, pass by pass if interlaced.
+pub fn filter_types(header: &Header, stream: &[u8]) -> Result<Vec<FilterType>, ImageError> {
+    let raw = inflate(header, stream)?;
+
+    let mut types = Vec::new();
+    let mut offset = 0;
+    for (width, height) in passes(header) {
+        if width == 0 {
+            continue;
+        }
+        let line = header.row_bytes(width) + 1;
+        for _ in 0..height {
+            let byte = *raw.get(offset).ok_or(ImageError::DataLength {
+                expected: offset + line,
+                actual: raw.len(),
+            })?;
+            types.push(FilterType::try_from(byte)?);
+            offset += line;
+        }
+    }
+    check_length(raw.len(), offset)?;
+    Ok(types)
+}
+
+/// Inflates the image data stream, checking it holds exactly the bytes the header calls for
+/// before it is unfiltered. Inflation stops past that length, so a small stream claiming a huge
+/// image cannot use up memory.
+fn inflate(header: &Header, stream: &[u8]) -> Result<Vec<u8>, ImageError> {
+    let too_large = ImageError::TooLarge {
+        width: header.width(),
+        height: header.height(),
+    };
+    // every scanline of every pass, with its filter type byte
+    let expected = passes(header)
+        .into_iter()
+        .filter(|&(width, _)| width > 0)
+        .try_fold(0usize, |total, (width, height)| {
+            (header.row_bytes(width) + 1)
+                .checked_mul(height as usize)?
+                .checked_add(total)
+        })
+        .ok_or(too_large)?;
+
+    let mut raw = Vec::new();
+    ZlibDecoder::new(stream)
+        .take(expected as u64 + 1)
+        .read_to_end(&mut raw)?;
+    check_length(raw.len(), expected)?;
+    Ok(raw)
+}
+
+/// Width and height of every pass: the whole image, or the seven Adam7 passes if interlaced.
+fn passes(header: &Header) -> Vec<(u32, u32)> {
+    if header.is_interlaced() {
+        ADAM7.map(|pass| pass_size(header, pass)).to_vec()
+    } else {
+        vec![(header.width(), header.height())]
+    }
+}
+
+/// Width and height of an Adam7 pass.
+fn pass_size(header: &Header, (x0, y0, dx, dy): (u32, u32, u32, u32)) -> (u32, u32) {
+    (
+        (header.width() + dx - 1 - x0) / dx,
+        (header.height() + dy - 1 - y0) / dy,
+    )
+}
+
+fn check_length(actual: usize, expected: usize) -> Result<(), ImageError> {
+    if actual != expected {
+        return Err(ImageError::DataLength { expected, actual });
+    }
+    Ok(())
+}
+
+/// Unfilters a `width` by `height` (sub-)image from the start of `raw`, returning the scanlines
+/// and the number of bytes consumed.
+fn unfilter_pass(
+    header: &Header,
+    width: u32,
+    height: u32,
+    raw: &[u8],
+) -> Result<(Vec<u8>, usize), ImageError> {
+    let row_bytes = header.row_bytes(width);
+    let used = (row_bytes + 1) * height as usize;
+    if raw.len() < used {
+        return Err(ImageError::DataLength {
+            expected: used,
+            actual: raw.len(),
+        });
+    }
+
+    let distance = header.filter_distance();
+    let mut data = vec![0; row_bytes * height as usize];
+    let zeros = vec![0; row_bytes];
+
+    for (y, line) in raw[..used].chunks_exact(row_bytes + 1).enumerate() {
+        let filter_type = FilterType::try_from(line[0])?;
+        let (previous, current) = data.split_at_mut(y * row_bytes);
+        let previous = if y == 0 {
+            &zeros
+        } else {
+            &previous[(y - 1) * row_bytes..]
+        };
+        let current = &mut current[..row_bytes];
+        current.copy_from_slice(&line[1..]);
+        filter::unfilter(filter_type, distance, previous, current);
+    }
+    Ok((data, used))
+}
+
+/// Copies pixel `from_x` of the scanline `from` to pixel `to_x` of the scanline `to`.
+fn copy_pixel(header: &Header, from: &[u8], from_x: u32, to: &mut [u8], to_x: u32) {
+    let bits = header.bits_per_pixel();
+    if bits >= 8 {
+        let bytes = bits / 8;
+        let (f, t) = (from_x as usize * bytes, to_x as usize * bytes);
+        to[t..t + bytes].copy_from_slice(&from[f..f + bytes]);
+        return;
+    }
+
+    let mask = (1u8 << bits) - 1;
+    let (f, t) = (from_x as usize * bits, to_x as usize * bits);
+    let value = (from[f / 8] >> (8 - bits - f % 8)) & mask;
+    let shift = 8 - bits - t % 8;
+    to[t / 8] = (to[t / 8] & !(mask << shift)) | (value << shift);
+}
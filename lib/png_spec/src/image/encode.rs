@@ -0,0 +1,167 @@
+use super::{
+    error::ImageError,
+    filter::{self, FilterType},
+    Image,
+};
+use crate::zlib;
+use flate2::{write::ZlibEncoder, Compression};
+use std::{io::Write, ops::Range};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// How scanline filters are chosen when encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterStrategy {
+    /// The same filter for every scanline
+    Fixed(FilterType),
+    /// The filter minimizing the sum of absolute differences, chosen per scanline
+    Adaptive,
+}
+
+impl FilterStrategy {
+    pub const ALL: [FilterStrategy; 6] = [
+        FilterStrategy::Fixed(FilterType::None),
+        FilterStrategy::Fixed(FilterType::Sub),
+        FilterStrategy::Fixed(FilterType::Up),
+        FilterStrategy::Fixed(FilterType::Average),
+        FilterStrategy::Fixed(FilterType::Paeth),
+        FilterStrategy::Adaptive,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeOptions {
+    pub filter: FilterStrategy,
+    /// Deflate compression level, 0 (store) to 9 (best)
+    pub level: u32,
+    /// Compresses blocks of this many scanlines independently and joins them into one zlib
+    /// stream, trading a little compression for speed. With the `parallel` feature the blocks
+    /// are compressed on all cores; the output is the same either way. `None` compresses the
+    /// whole image as a single block.
+    pub rows_per_block: Option<usize>,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            filter: FilterStrategy::Adaptive,
+            level: 6,
+            rows_per_block: None,
+        }
+    }
+}
+
+impl Image {
+    /// Filtered scanlines, each prefixed with its filter type byte.
+    pub fn filtered(&self, strategy: FilterStrategy) -> Vec<u8> {
+        self.filtered_rows(strategy, 0..self.height() as usize)
+    }
+
+    /// Filtered scanlines with the filter of every scanline given, instead of chosen by a
+    /// strategy.
+    pub fn filtered_with(&self, filters: &[FilterType]) -> Result<Vec<u8>, ImageError> {
+        if filters.len() != self.height() as usize {
+            return Err(ImageError::FilterCount {
+                expected: self.height() as usize,
+                actual: filters.len(),
+            });
+        }
+        Ok(self.filter_rows(0..filters.len(), |y, _, _| filters[y]))
+    }
+
+    /// Filtered scanlines `rows`. Filters only look at the unfiltered previous scanline, so any
+    /// range of rows can be filtered independently.
+    fn filtered_rows(&self, strategy: FilterStrategy, rows: Range<usize>) -> Vec<u8> {
+        let distance = self.header.filter_distance();
+        self.filter_rows(rows, |_, previous, row| match strategy {
+            FilterStrategy::Fixed(f) => f,
+            FilterStrategy::Adaptive => filter::adaptive(distance, previous, row),
+        })
+    }
+
+    /// Filters scanlines `rows` with the filter `choose` picks from the row index, the previous
+    /// scanline and the scanline.
+    fn filter_rows(
+        &self,
+        rows: Range<usize>,
+        choose: impl Fn(usize, &[u8], &[u8]) -> FilterType,
+    ) -> Vec<u8> {
+        let row_bytes = self.header.row_bytes(self.width());
+        let distance = self.header.filter_distance();
+        let mut out = Vec::with_capacity((row_bytes + 1) * rows.len());
+        let zeros = vec![0; row_bytes];
+
+        let mut previous: &[u8] = match rows.start {
+            0 => &zeros,
+            start => &self.data[(start - 1) * row_bytes..start * row_bytes],
+        };
+        let data = &self.data[rows.start * row_bytes..rows.end * row_bytes];
+        for (y, row) in rows.zip(data.chunks_exact(row_bytes)) {
+            filter::filter(choose(y, previous, row), distance, previous, row, &mut out);
+            previous = row;
+        }
+        out
+    }
+
+    /// Filters and compresses the image into a zlib stream ready to be split into IDAT chunks.
+    pub fn encode(&self, options: &EncodeOptions) -> Result<Vec<u8>, ImageError> {
+        if let Some(rows) = options.rows_per_block {
+            return Ok(self.encode_blocks(options, rows.max(1)));
+        }
+
+        Self::deflate(&self.filtered(options.filter), options.level)
+    }
+
+    /// Like [`Image::encode`], with the filter of every scanline given instead of
+    /// `options.filter`. The whole image is compressed as a single block.
+    pub fn encode_with_filters(
+        &self,
+        filters: &[FilterType],
+        options: &EncodeOptions,
+    ) -> Result<Vec<u8>, ImageError> {
+        Self::deflate(&self.filtered_with(filters)?, options.level)
+    }
+
+    fn deflate(filtered: &[u8], level: u32) -> Result<Vec<u8>, ImageError> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(level));
+        encoder.write_all(filtered)?;
+        Ok(encoder.finish()?)
+    }
+
+    /// Filters and deflates blocks of `rows` scanlines independently, then joins them: every
+    /// block but the last ends byte aligned after a sync flush, and the Adler-32 checksums of the
+    /// blocks are combined into the checksum of the whole stream.
+    fn encode_blocks(&self, options: &EncodeOptions, rows: usize) -> Vec<u8> {
+        let height = self.height() as usize;
+        let blocks: Vec<Range<usize>> = (0..height)
+            .step_by(rows)
+            .map(|start| start..(start + rows).min(height))
+            .collect();
+
+        let compress = |block: Range<usize>| {
+            let last = block.end == height;
+            let filtered = self.filtered_rows(options.filter, block);
+            let adler = zlib::adler32(&filtered);
+            (
+                adler,
+                filtered.len(),
+                zlib::deflate_block(&filtered, options.level, last),
+            )
+        };
+
+        #[cfg(feature = "parallel")]
+        let parts: Vec<(u32, usize, Vec<u8>)> = blocks.into_par_iter().map(compress).collect();
+        #[cfg(not(feature = "parallel"))]
+        let parts: Vec<(u32, usize, Vec<u8>)> = blocks.into_iter().map(compress).collect();
+
+        let mut stream = zlib::header(options.level).to_vec();
+        let mut adler = zlib::adler32(&[]);
+        for (block_adler, length, deflated) in parts {
+            adler = zlib::adler32_combine(adler, block_adler, length);
+            stream.extend(deflated);
+        }
+        stream.extend(adler.to_be_bytes());
+        stream
+    }
+}
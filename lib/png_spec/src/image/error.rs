@@ -0,0 +1,106 @@
+use super::header::ColorType;
+use crate::{chunk_type::ChunkType, png::PngError};
+use std::{error, fmt, io};
+
+#[derive(Debug)]
+pub enum ImageError {
+    Png(PngError),
+    MissingChunk(ChunkType),
+    ChunkLength(ChunkType),
+    Dimensions {
+        width: u32,
+        height: u32,
+    },
+    /// The image is too large to hold in memory.
+    TooLarge {
+        width: u32,
+        height: u32,
+    },
+    ColorType(u8),
+    BitDepth {
+        bit_depth: u8,
+        color_type: ColorType,
+    },
+    CompressionMethod(u8),
+    FilterMethod(u8),
+    InterlaceMethod(u8),
+    FilterType(u8),
+    /// The image data could not be inflated or deflated.
+    Zlib(io::Error),
+    /// The inflated image data does not match the size given by the header.
+    DataLength {
+        expected: usize,
+        actual: usize,
+    },
+    /// The number of scanline filters given does not match the number of scanlines.
+    FilterCount {
+        expected: usize,
+        actual: usize,
+    },
+    /// A pixel refers to a palette entry that does not exist.
+    PaletteIndex(u8),
+    /// An operation produced pixels that differ from the original.
+    PixelMismatch,
+}
+
+impl From<PngError> for ImageError {
+    fn from(v: PngError) -> Self {
+        Self::Png(v)
+    }
+}
+
+impl From<io::Error> for ImageError {
+    fn from(v: io::Error) -> Self {
+        Self::Zlib(v)
+    }
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Png(e) => e.fmt(f),
+            ImageError::MissingChunk(t) => write!(f, "missing required '{t}' chunk"),
+            ImageError::ChunkLength(t) => write!(f, "invalid '{t}' chunk length"),
+            ImageError::Dimensions { width, height } => write!(
+                f,
+                "invalid dimensions {width}x{height}: must be between 1 and 2^31 - 1"
+            ),
+            ImageError::TooLarge { width, height } => {
+                write!(f, "image of {width}x{height} pixels is too large to decode")
+            }
+            ImageError::ColorType(v) => write!(f, "invalid color type '{v}'"),
+            ImageError::BitDepth {
+                bit_depth,
+                color_type,
+            } => write!(
+                f,
+                "bit depth '{bit_depth}' is not allowed for color type {color_type:?}"
+            ),
+            ImageError::CompressionMethod(v) => write!(f, "unknown compression method '{v}'"),
+            ImageError::FilterMethod(v) => write!(f, "unknown filter method '{v}'"),
+            ImageError::InterlaceMethod(v) => write!(f, "unknown interlace method '{v}'"),
+            ImageError::FilterType(v) => write!(f, "invalid scanline filter type '{v}'"),
+            ImageError::Zlib(e) => write!(f, "invalid image data stream: {e}"),
+            ImageError::DataLength { expected, actual } => write!(
+                f,
+                "image data length mismatch: expected '{expected}' != '{actual}' actual"
+            ),
+            ImageError::FilterCount { expected, actual } => write!(
+                f,
+                "{actual} scanline filters given for {expected} scanlines"
+            ),
+            ImageError::PaletteIndex(i) => write!(f, "palette index '{i}' out of range"),
+            ImageError::PixelMismatch => write!(f, "pixels changed: refusing to write result"),
+        }
+    }
+}
+
+impl error::Error for ImageError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ImageError::Png(e) => Some(e),
+            ImageError::Zlib(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use super::error::ImageError;
+
+/// Scanline filter types of filter method 0.
+///
+/// ['Filtering'](https://www.w3.org/TR/png/#9Filters)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterType {
+    None,
+    Sub,
+    Up,
+    Average,
+    Paeth,
+}
+
+impl FilterType {
+    pub const ALL: [FilterType; 5] = [
+        FilterType::None,
+        FilterType::Sub,
+        FilterType::Up,
+        FilterType::Average,
+        FilterType::Paeth,
+    ];
+}
+
+impl From<FilterType> for u8 {
+    fn from(f: FilterType) -> Self {
+        match f {
+            FilterType::None => 0,
+            FilterType::Sub => 1,
+            FilterType::Up => 2,
+            FilterType::Average => 3,
+            FilterType::Paeth => 4,
+        }
+    }
+}
+
+impl TryFrom<u8> for FilterType {
+    type Error = ImageError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        FilterType::ALL
+            .into_iter()
+            .find(|f| u8::from(*f) == value)
+            .ok_or(ImageError::FilterType(value))
+    }
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Predicts byte `i` of `row` from its left (`a`), above (`b`) and upper left (`c`) neighbours.
+fn predict(filter: FilterType, a: u8, b: u8, c: u8) -> u8 {
+    match filter {
+        FilterType::None => 0,
+        FilterType::Sub => a,
+        FilterType::Up => b,
+        FilterType::Average => ((a as u16 + b as u16) / 2) as u8,
+        FilterType::Paeth => paeth(a, b, c),
+    }
+}
+
+/// Reverses `filter` in place. `previous` is the unfiltered previous scanline, all zeros for the
+/// first scanline. `distance` is [`Header::filter_distance`](super::Header::filter_distance).
+pub fn unfilter(filter: FilterType, distance: usize, previous: &[u8], row: &mut [u8]) {
+    for i in 0..row.len() {
+        let a = if i >= distance { row[i - distance] } else { 0 };
+        let c = if i >= distance {
+            previous[i - distance]
+        } else {
+            0
+        };
+        row[i] = row[i].wrapping_add(predict(filter, a, previous[i], c));
+    }
+}
+
+/// Appends the filter type byte and the filtered scanline to `out`.
+pub fn filter(filter: FilterType, distance: usize, previous: &[u8], row: &[u8], out: &mut Vec<u8>) {
+    out.push(filter.into());
+    for i in 0..row.len() {
+        let a = if i >= distance { row[i - distance] } else { 0 };
+        let c = if i >= distance {
+            previous[i - distance]
+        } else {
+            0
+        };
+        out.push(row[i].wrapping_sub(predict(filter, a, previous[i], c)));
+    }
+}
+
+/// The filter with the smallest sum of absolute differences, the heuristic recommended by the
+/// specification for choosing a filter per scanline.
+pub fn adaptive(distance: usize, previous: &[u8], row: &[u8]) -> FilterType {
+    let mut filtered = Vec::with_capacity(row.len() + 1);
+    FilterType::ALL
+        .into_iter()
+        .min_by_key(|f| {
+            filtered.clear();
+            filter(*f, distance, previous, row, &mut filtered);
+            filtered[1..]
+                .iter()
+                .map(|&b| (b as i8).unsigned_abs() as u64)
+                .sum::<u64>()
+        })
+        .expect("there are five filter types")
+}
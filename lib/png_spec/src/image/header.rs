@@ -0,0 +1,212 @@
+use super::error::ImageError;
+use crate::{chunk::Chunk, chunk_type::ChunkType};
+
+/// Image type: which channels each pixel has and how they are stored.
+///
+/// ['IHDR Image header'](https://www.w3.org/TR/png/#11IHDR)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorType {
+    Grayscale,
+    Rgb,
+    /// Each pixel is a palette index
+    Indexed,
+    GrayscaleAlpha,
+    Rgba,
+}
+
+impl ColorType {
+    pub fn channels(&self) -> usize {
+        match self {
+            ColorType::Grayscale | ColorType::Indexed => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+        }
+    }
+
+    pub fn allowed_bit_depths(&self) -> &'static [u8] {
+        match self {
+            ColorType::Grayscale => &[1, 2, 4, 8, 16],
+            ColorType::Indexed => &[1, 2, 4, 8],
+            ColorType::Rgb | ColorType::GrayscaleAlpha | ColorType::Rgba => &[8, 16],
+        }
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        matches!(self, ColorType::GrayscaleAlpha | ColorType::Rgba)
+    }
+
+    pub fn is_grayscale(&self) -> bool {
+        matches!(self, ColorType::Grayscale | ColorType::GrayscaleAlpha)
+    }
+
+    fn code(&self) -> u8 {
+        match self {
+            ColorType::Grayscale => 0,
+            ColorType::Rgb => 2,
+            ColorType::Indexed => 3,
+            ColorType::GrayscaleAlpha => 4,
+            ColorType::Rgba => 6,
+        }
+    }
+}
+
+impl TryFrom<u8> for ColorType {
+    type Error = ImageError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ColorType::Grayscale),
+            2 => Ok(ColorType::Rgb),
+            3 => Ok(ColorType::Indexed),
+            4 => Ok(ColorType::GrayscaleAlpha),
+            6 => Ok(ColorType::Rgba),
+            v => Err(ImageError::ColorType(v)),
+        }
+    }
+}
+
+/// Image header (IHDR).
+///
+/// | Field              | Size    |
+/// |--------------------|---------|
+/// | Width              | 4 bytes |
+/// | Height             | 4 bytes |
+/// | Bit depth          | 1 byte  |
+/// | Colour type        | 1 byte  |
+/// | Compression method | 1 byte  |
+/// | Filter method      | 1 byte  |
+/// | Interlace method   | 1 byte  |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: ColorType,
+    interlaced: bool,
+}
+
+impl Header {
+    const LENGTH: usize = 13;
+
+    /// Width and height must not exceed 2^31 - 1.
+    const MAX_DIMENSION: u32 = (1 << 31) - 1;
+
+    /// A non-interlaced header.
+    pub fn new(
+        width: u32,
+        height: u32,
+        bit_depth: u8,
+        color_type: ColorType,
+    ) -> Result<Header, ImageError> {
+        if width == 0 || height == 0 || width > Self::MAX_DIMENSION || height > Self::MAX_DIMENSION
+        {
+            return Err(ImageError::Dimensions { width, height });
+        }
+        if !color_type.allowed_bit_depths().contains(&bit_depth) {
+            return Err(ImageError::BitDepth {
+                bit_depth,
+                color_type,
+            });
+        }
+        Ok(Header {
+            width,
+            height,
+            bit_depth,
+            color_type,
+            interlaced: false,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn bit_depth(&self) -> u8 {
+        self.bit_depth
+    }
+
+    pub fn color_type(&self) -> ColorType {
+        self.color_type
+    }
+
+    /// Adam7 interlacing
+    pub fn is_interlaced(&self) -> bool {
+        self.interlaced
+    }
+
+    pub fn bits_per_pixel(&self) -> usize {
+        self.color_type.channels() * self.bit_depth as usize
+    }
+
+    /// Distance in bytes to the corresponding byte of the previous pixel, as used by filters.
+    /// Rounds up to one for bit depths below 8.
+    pub fn filter_distance(&self) -> usize {
+        self.bits_per_pixel().div_ceil(8)
+    }
+
+    /// Bytes of the whole unfiltered image, `None` if that does not fit in memory.
+    pub fn data_length(&self) -> Option<usize> {
+        self.row_bytes(self.width).checked_mul(self.height as usize)
+    }
+
+    /// Bytes in one unfiltered scanline `width` pixels wide.
+    pub fn row_bytes(&self, width: u32) -> usize {
+        (width as usize * self.bits_per_pixel()).div_ceil(8)
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let data: Vec<u8> = [
+            self.width.to_be_bytes().as_slice(),
+            self.height.to_be_bytes().as_slice(),
+            &[
+                self.bit_depth,
+                self.color_type.code(),
+                0, // compression method: deflate
+                0, // filter method: adaptive filtering with five basic filter types
+                self.interlaced.into(),
+            ],
+        ]
+        .concat();
+        Chunk::new(ChunkType::IHDR, data)
+    }
+}
+
+impl TryFrom<&Chunk> for Header {
+    type Error = ImageError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        if chunk.chunk_type() != &ChunkType::IHDR {
+            return Err(ImageError::MissingChunk(ChunkType::IHDR));
+        }
+        let data: [u8; Self::LENGTH] = chunk
+            .data()
+            .try_into()
+            .map_err(|_| ImageError::ChunkLength(ChunkType::IHDR))?;
+
+        let width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        let height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+        let [bit_depth, color_type, compression, filter, interlace] =
+            [data[8], data[9], data[10], data[11], data[12]];
+
+        if compression != 0 {
+            return Err(ImageError::CompressionMethod(compression));
+        }
+        if filter != 0 {
+            return Err(ImageError::FilterMethod(filter));
+        }
+        let interlaced = match interlace {
+            0 => false,
+            1 => true,
+            v => return Err(ImageError::InterlaceMethod(v)),
+        };
+
+        let mut header = Header::new(width, height, bit_depth, color_type.try_into()?)?;
+        header.interlaced = interlaced;
+        Ok(header)
+    }
+}
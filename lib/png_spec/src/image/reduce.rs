@@ -0,0 +1,170 @@
+use super::{header::ColorType, scale_from_16, Header, Image};
+use std::collections::HashMap;
+
+impl Image {
+    /// Lossless alternatives to this image with a smaller color type, bit depth or palette.
+    /// Every alternative has exactly the same [`Image::to_rgba16`] pixels.
+    pub fn reductions(&self) -> Vec<Image> {
+        let pixels = self.to_rgba16();
+        let opaque = pixels.iter().all(|p| p[3] == u16::MAX);
+        let gray = pixels.iter().all(|p| p[0] == p[1] && p[1] == p[2]);
+        let fits_8 = pixels
+            .iter()
+            .flatten()
+            .all(|v| scale_from_16(*v, 8).is_some());
+        let depth = if fits_8 { 8 } else { 16 };
+
+        let mut reductions = Vec::new();
+        let direct = match (gray, opaque) {
+            (true, true) => {
+                let depth = [1, 2, 4, 8, 16]
+                    .into_iter()
+                    .find(|d| pixels.iter().all(|p| scale_from_16(p[0], *d).is_some()))
+                    .expect("every 16-bit value fits 16 bits");
+                Self::from_rgba16(&pixels, self.header, ColorType::Grayscale, depth)
+            }
+            (true, false) => {
+                Self::from_rgba16(&pixels, self.header, ColorType::GrayscaleAlpha, depth)
+            }
+            (false, true) => Self::from_rgba16(&pixels, self.header, ColorType::Rgb, depth),
+            (false, false) => Self::from_rgba16(&pixels, self.header, ColorType::Rgba, depth),
+        };
+        reductions.push(direct);
+
+        if fits_8 {
+            reductions.extend(Self::palettized(&pixels, self.header));
+        }
+
+        reductions.retain(|r| r != self);
+        reductions
+    }
+
+    /// The image as 8-bit RGB, or RGBA if any palette entry is translucent, with exactly the same
+    /// [`Image::to_rgba16`] pixels. Other images are returned unchanged.
+    pub fn expand_palette(&self) -> Image {
+        if self.header.color_type() != ColorType::Indexed {
+            return self.clone();
+        }
+        let pixels = self.to_rgba16();
+        let color_type = if self.transparency.iter().any(|a| *a != u8::MAX) {
+            ColorType::Rgba
+        } else {
+            ColorType::Rgb
+        };
+        Self::from_rgba16(&pixels, self.header, color_type, 8)
+    }
+
+    /// The image with its palette reordered so entry `i` is the current entry `order[i]`. Pixels
+    /// and transparency follow their entries, so the image looks the same. `None` unless the image
+    /// is indexed and `order` is a permutation of its palette.
+    pub fn permute_palette(&self, order: &[usize]) -> Option<Image> {
+        let length = self.palette.len();
+        if self.header.color_type() != ColorType::Indexed || order.len() != length {
+            return None;
+        }
+        let mut new_index = vec![usize::MAX; length];
+        for (i, &old) in order.iter().enumerate() {
+            if *new_index.get(old)? != usize::MAX {
+                return None;
+            }
+            new_index[old] = i;
+        }
+
+        let mut image = self.clone();
+        image.palette = order.iter().map(|&old| self.palette[old]).collect();
+        let alpha = |old: usize| self.transparency.get(old).copied().unwrap_or(u8::MAX);
+        image.transparency = order.iter().map(|&old| alpha(old)).collect();
+        let opaque_tail = image
+            .transparency
+            .iter()
+            .rev()
+            .take_while(|&&a| a == u8::MAX)
+            .count();
+        image
+            .transparency
+            .truncate(image.transparency.len() - opaque_tail);
+
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let old = self.sample(x, y, 0) as usize;
+                image.set_sample(x, y, 0, *new_index.get(old)? as u16);
+            }
+        }
+        Some(image)
+    }
+
+    /// Builds an image of `color_type` at `depth` bits from pixels that are known to be
+    /// representable in it.
+    fn from_rgba16(pixels: &[[u16; 4]], like: Header, color_type: ColorType, depth: u8) -> Image {
+        let header = Header::new(like.width(), like.height(), depth, color_type)
+            .expect("reductions only use valid bit depths");
+        let data = vec![0; header.row_bytes(header.width()) * header.height() as usize];
+        let mut image = Image::new(header, data).expect("data length matches header");
+
+        let channels: &[usize] = match color_type {
+            ColorType::Grayscale => &[0],
+            ColorType::GrayscaleAlpha => &[0, 3],
+            ColorType::Rgb => &[0, 1, 2],
+            ColorType::Rgba => &[0, 1, 2, 3],
+            ColorType::Indexed => unreachable!("palettes are built by Image::palettized"),
+        };
+        for (i, pixel) in pixels.iter().enumerate() {
+            let (x, y) = (
+                (i % header.width() as usize) as u32,
+                (i / header.width() as usize) as u32,
+            );
+            for (channel, &source) in channels.iter().enumerate() {
+                let value = scale_from_16(pixel[source], depth).expect("checked by caller");
+                image.set_sample(x, y, channel, value);
+            }
+        }
+        image
+    }
+
+    /// An indexed image if there are at most 256 distinct 8-bit colors. Translucent entries come
+    /// first so the tRNS chunk is as short as possible, the rest are ordered by frequency.
+    fn palettized(pixels: &[[u16; 4]], like: Header) -> Option<Image> {
+        let mut counts: HashMap<[u8; 4], usize> = HashMap::new();
+        for p in pixels {
+            *counts.entry(p.map(|v| (v / 257) as u8)).or_default() += 1;
+            if counts.len() > 256 {
+                return None;
+            }
+        }
+
+        let mut colors: Vec<([u8; 4], usize)> = counts.into_iter().collect();
+        colors.sort_by_key(|(c, count)| (c[3] == u8::MAX, std::cmp::Reverse(*count), *c));
+        let index: HashMap<[u8; 4], u16> = colors
+            .iter()
+            .enumerate()
+            .map(|(i, (c, _))| (*c, i as u16))
+            .collect();
+
+        let depth = match colors.len() {
+            0..=2 => 1,
+            3..=4 => 2,
+            5..=16 => 4,
+            _ => 8,
+        };
+        let header = Header::new(like.width(), like.height(), depth, ColorType::Indexed)
+            .expect("indexed bit depths are valid");
+        let data = vec![0; header.row_bytes(header.width()) * header.height() as usize];
+        let mut image = Image::new(header, data).expect("data length matches header");
+
+        for (i, p) in pixels.iter().enumerate() {
+            let (x, y) = (
+                (i % header.width() as usize) as u32,
+                (i / header.width() as usize) as u32,
+            );
+            image.set_sample(x, y, 0, index[&p.map(|v| (v / 257) as u8)]);
+        }
+
+        image.palette = colors.iter().map(|(c, _)| [c[0], c[1], c[2]]).collect();
+        image.transparency = colors
+            .iter()
+            .map(|(c, _)| c[3])
+            .take_while(|a| *a != u8::MAX)
+            .collect();
+        Some(image)
+    }
+}
@@ -0,0 +1,419 @@
+use super::*;
+use flate2::{write::ZlibEncoder, Compression};
+use std::io::Write;
+
+fn testing_image(color_type: ColorType, bit_depth: u8) -> Image {
+    let header = Header::new(13, 7, bit_depth, color_type).unwrap();
+    let max = (1u32 << bit_depth) - 1;
+    let data = vec![0; header.row_bytes(13) * 7];
+    let mut image = Image::new(header, data).unwrap();
+    for y in 0..7 {
+        for x in 0..13 {
+            for c in 0..color_type.channels() {
+                let value = (x * 31 + y * 17 + c as u32 * 7) % (max + 1);
+                image.set_sample(x, y, c, value as u16);
+            }
+        }
+    }
+    if color_type == ColorType::Indexed {
+        let palette = (0..=max).map(|i| [i as u8, 255 - i as u8, 7]).collect();
+        image = image.with_palette(palette, vec![0, 128]);
+    }
+    image
+}
+
+fn testing_png(image: &Image) -> Png {
+    let mut png = Png::from_chunks(vec![
+        image.header().to_chunk(),
+        Chunk::new(ChunkType::IDAT, Vec::new()),
+        Chunk::new(ChunkType::IEND, Vec::new()),
+    ]);
+    if !image.palette().is_empty() {
+        png.insert_chunk(1, Chunk::new(ChunkType::PLTE, image.palette().concat()));
+    }
+    image.write_to(&mut png, &EncodeOptions::default()).unwrap();
+    png
+}
+
+/// Adam7 interlaced image data stream, the reverse of the decoder.
+pub(crate) fn interlaced_stream(image: &Image) -> Vec<u8> {
+    let passes = [
+        (0, 0, 8, 8),
+        (4, 0, 8, 8),
+        (0, 4, 4, 8),
+        (2, 0, 4, 4),
+        (0, 2, 2, 4),
+        (1, 0, 2, 2),
+        (0, 1, 1, 2),
+    ];
+    let h = image.header();
+    let mut raw = Vec::new();
+    for (x0, y0, dx, dy) in passes {
+        let width = (h.width() + dx - 1 - x0) / dx;
+        let height = (h.height() + dy - 1 - y0) / dy;
+        if width == 0 || height == 0 {
+            continue;
+        }
+        let header = Header::new(width, height, h.bit_depth(), h.color_type()).unwrap();
+        let data = vec![0; header.row_bytes(width) * height as usize];
+        let mut pass = Image::new(header, data).unwrap();
+        for y in 0..height {
+            for x in 0..width {
+                for c in 0..h.color_type().channels() {
+                    let value = image.sample(x0 + x * dx, y0 + y * dy, c);
+                    pass.set_sample(x, y, c, value);
+                }
+            }
+        }
+        raw.extend(pass.filtered(FilterStrategy::Fixed(FilterType::Paeth)));
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&raw).unwrap();
+    encoder.finish().unwrap()
+}
+
+const FORMATS: [(ColorType, u8); 12] = [
+    (ColorType::Grayscale, 1),
+    (ColorType::Grayscale, 2),
+    (ColorType::Grayscale, 4),
+    (ColorType::Grayscale, 8),
+    (ColorType::Grayscale, 16),
+    (ColorType::Rgb, 8),
+    (ColorType::Rgb, 16),
+    (ColorType::Indexed, 2),
+    (ColorType::Indexed, 8),
+    (ColorType::GrayscaleAlpha, 8),
+    (ColorType::Rgba, 8),
+    (ColorType::Rgba, 16),
+];
+
+#[test]
+fn test_header_round_trip() {
+    let header = Header::new(640, 480, 16, ColorType::Rgba).unwrap();
+    let chunk = header.to_chunk();
+    assert_eq!(chunk.data_length(), 13);
+    assert_eq!(Header::try_from(&chunk).unwrap(), header);
+}
+
+#[test]
+fn test_header_invalid() {
+    assert!(Header::new(0, 1, 8, ColorType::Rgb).is_err());
+    assert!(Header::new(1, 1, 4, ColorType::Rgb).is_err());
+    assert!(Header::new(1, 1, 16, ColorType::Indexed).is_err());
+}
+
+#[test]
+fn test_filter_round_trip() {
+    let image = testing_image(ColorType::Rgb, 8);
+    for strategy in FilterStrategy::ALL {
+        let filtered = image.filtered(strategy);
+        let decoded = decode::decode(image.header(), &{
+            let mut e = ZlibEncoder::new(Vec::new(), Compression::fast());
+            e.write_all(&filtered).unwrap();
+            e.finish().unwrap()
+        })
+        .unwrap();
+        assert_eq!(decoded, image.data(), "{strategy:?}");
+    }
+}
+
+#[test]
+fn test_filter_types() {
+    let image = testing_image(ColorType::Rgb, 8);
+    let mut png = testing_png(&image);
+    assert_eq!(
+        Image::filter_types(&png).unwrap(),
+        image.adaptive_filter_types()
+    );
+
+    let options = EncodeOptions {
+        filter: FilterStrategy::Fixed(FilterType::Up),
+        ..EncodeOptions::default()
+    };
+    image.write_to(&mut png, &options).unwrap();
+    let types = Image::filter_types(&png).unwrap();
+    assert_eq!(types, vec![FilterType::Up; image.height() as usize]);
+
+    // every pass has its own scanlines
+    let interlaced = interlaced_stream(&image);
+    let mut header = image.header().to_chunk().data().to_vec();
+    header[12] = 1;
+    let header = Header::try_from(&Chunk::new(ChunkType::IHDR, header)).unwrap();
+    let passes: u32 = decode::filter_types(&header, &interlaced)
+        .unwrap()
+        .len()
+        .try_into()
+        .unwrap();
+    assert!(passes > image.height());
+}
+
+#[test]
+fn test_write_filtered() {
+    let image = testing_image(ColorType::Rgba, 8);
+    let mut png = testing_png(&image);
+    let filters = [
+        FilterType::Paeth,
+        FilterType::None,
+        FilterType::Sub,
+        FilterType::Sub,
+        FilterType::Average,
+        FilterType::Up,
+        FilterType::None,
+    ];
+    image
+        .write_filtered_to(&mut png, &filters, &EncodeOptions::default())
+        .unwrap();
+    assert_eq!(Image::filter_types(&png).unwrap(), filters);
+    assert_eq!(Image::from_png(&png).unwrap(), image);
+
+    assert!(matches!(
+        image.filtered_with(&filters[1..]),
+        Err(ImageError::FilterCount {
+            expected: 7,
+            actual: 6
+        })
+    ));
+}
+
+#[test]
+fn test_encode_decode_round_trip() {
+    for (color_type, depth) in FORMATS {
+        let image = testing_image(color_type, depth);
+        let png = testing_png(&image);
+        assert_eq!(
+            Image::from_png(&png).unwrap(),
+            image,
+            "{color_type:?} {depth}"
+        );
+    }
+}
+
+#[test]
+fn test_decode_interlaced() {
+    for (color_type, depth) in FORMATS {
+        let image = testing_image(color_type, depth);
+        let header = {
+            let mut data = image.header().to_chunk().data().to_vec();
+            data[12] = 1;
+            Header::try_from(&Chunk::new(ChunkType::IHDR, data)).unwrap()
+        };
+        assert!(header.is_interlaced());
+        let decoded = decode::decode(&header, &interlaced_stream(&image)).unwrap();
+        assert_eq!(decoded, image.data(), "{color_type:?} {depth}");
+    }
+}
+
+#[test]
+fn test_decode_truncated() {
+    let image = testing_image(ColorType::Rgb, 8);
+    let mut png = testing_png(&image);
+    let data = image.encode(&EncodeOptions::default()).unwrap();
+    let short = Image::new(
+        *image.header(),
+        image.data()[..image.data().len() - 39].to_vec(),
+    );
+    assert!(short.is_err());
+
+    png.set_image_data(&data[..data.len() / 2], 1000).unwrap();
+    assert!(Image::from_png(&png).is_err());
+}
+
+#[test]
+fn test_decode_huge_dimensions() {
+    // a tiny stream claiming the largest RGBA16 image there can be
+    let max = (1 << 31) - 1;
+    let mut stream = ZlibEncoder::new(Vec::new(), Compression::default());
+    stream.write_all(&[0; 1000]).unwrap();
+    let stream = stream.finish().unwrap();
+
+    for interlaced in [0, 1] {
+        let mut data = Header::new(max, max, 16, ColorType::Rgba)
+            .unwrap()
+            .to_chunk()
+            .data()
+            .to_vec();
+        data[12] = interlaced;
+        let header = Header::try_from(&Chunk::new(ChunkType::IHDR, data)).unwrap();
+        assert!(matches!(
+            decode::decode(&header, &stream),
+            Err(ImageError::TooLarge { .. })
+        ));
+        assert!(Image::new(header, Vec::new()).is_err());
+    }
+
+    // large enough to fit in memory, but more than the stream holds
+    let header = Header::new(max, 4, 8, ColorType::Rgb).unwrap();
+    assert!(matches!(
+        decode::decode(&header, &stream),
+        Err(ImageError::DataLength { actual: 1000, .. })
+    ));
+}
+
+#[test]
+fn test_to_rgba16() {
+    let image = testing_image(ColorType::Indexed, 2);
+    let pixels = image.to_rgba16();
+    // index of (0, 0) is 0: transparent
+    assert_eq!(pixels[0], [0, 0xffff, 7 * 257, 0]);
+    // index of (1, 0) is 31 % 4 = 3: opaque
+    assert_eq!(pixels[1], [3 * 257, 252 * 257, 7 * 257, 0xffff]);
+
+    let image = testing_image(ColorType::Grayscale, 1);
+    assert!(image
+        .to_rgba16()
+        .iter()
+        .all(|p| p[0] == 0 || p[0] == 0xffff));
+}
+
+#[test]
+fn test_reductions_keep_pixels() {
+    for (color_type, depth) in FORMATS {
+        let image = testing_image(color_type, depth);
+        for reduced in image.reductions() {
+            assert_eq!(
+                reduced.to_rgba16(),
+                image.to_rgba16(),
+                "{color_type:?} {depth} -> {:?}",
+                reduced.header()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_expand_palette() {
+    let image = testing_image(ColorType::Indexed, 2);
+    let expanded = image.expand_palette();
+    assert_eq!(expanded.header().color_type(), ColorType::Rgba);
+    assert_eq!(expanded.header().bit_depth(), 8);
+    assert!(expanded.palette().is_empty());
+    assert_eq!(expanded.to_rgba16(), image.to_rgba16());
+
+    let opaque = image.clone().with_palette(image.palette().to_vec(), vec![]);
+    assert_eq!(
+        opaque.expand_palette().header().color_type(),
+        ColorType::Rgb
+    );
+
+    let rgb = testing_image(ColorType::Rgb, 8);
+    assert_eq!(rgb.expand_palette(), rgb);
+}
+
+#[test]
+fn test_looks_like() {
+    let mut image = testing_image(ColorType::Rgba, 8);
+    image.set_sample(0, 0, 3, 0);
+    let mut changed = image.clone();
+    changed.set_sample(0, 0, 1, 99);
+    assert_ne!(changed, image);
+    assert!(changed.looks_like(&image));
+
+    changed.set_sample(1, 0, 1, 99);
+    assert!(!changed.looks_like(&image));
+    assert!(!image.looks_like(&testing_image(ColorType::Rgb, 8)));
+}
+
+#[test]
+fn test_permute_palette() {
+    let image = testing_image(ColorType::Indexed, 2);
+    let permuted = image.permute_palette(&[3, 1, 0, 2]).unwrap();
+    assert_eq!(permuted.palette()[0], image.palette()[3]);
+    // entries 0 and 1 are translucent, now at 2 and 1
+    assert_eq!(permuted.transparency(), [255, 128, 0]);
+    assert_eq!(permuted.to_rgba16(), image.to_rgba16());
+    assert_ne!(permuted.data(), image.data());
+
+    assert!(image.permute_palette(&[0, 1, 2]).is_none());
+    assert!(image.permute_palette(&[0, 1, 1, 2]).is_none());
+    assert!(image.permute_palette(&[0, 1, 2, 4]).is_none());
+    assert!(testing_image(ColorType::Rgb, 8)
+        .permute_palette(&[])
+        .is_none());
+}
+
+#[test]
+fn test_reduce_opaque_gray_rgba() {
+    let header = Header::new(4, 1, 8, ColorType::Rgba).unwrap();
+    let data = [
+        0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255,
+    ]
+    .to_vec();
+    let image = Image::new(header, data).unwrap();
+    let reductions = image.reductions();
+
+    let gray = &reductions[0];
+    assert_eq!(gray.header().color_type(), ColorType::Grayscale);
+    assert_eq!(gray.header().bit_depth(), 1);
+    assert_eq!(gray.data(), [0b0101_0000]);
+}
+
+#[test]
+fn test_write_to_converts_background() {
+    let image = testing_image(ColorType::Rgb, 16);
+    let mut png = testing_png(&image);
+    let bkgd = Chunk::new("bKGD".parse().unwrap(), [0, 0, 0xff, 0xff, 0, 0].to_vec());
+    png.insert_chunk(1, bkgd);
+
+    let header = Header::new(13, 7, 8, ColorType::Rgb).unwrap();
+    let pixels = vec![0; header.row_bytes(13) * 7];
+    Image::new(header, pixels)
+        .unwrap()
+        .write_to(&mut png, &EncodeOptions::default())
+        .unwrap();
+
+    assert_eq!(
+        png.chunk_by_type("bKGD").unwrap().data(),
+        [0, 0, 0, 0xff, 0, 0]
+    );
+    assert_eq!(Header::try_from(&png.chunks()[0]).unwrap(), header);
+}
+
+#[test]
+fn test_reduction_drops_color_profile() {
+    let header = Header::new(8, 8, 16, ColorType::Rgb).unwrap();
+    let image = Image::new(header, vec![0x42; header.row_bytes(8) * 8]).unwrap();
+
+    for reduced in image.reductions() {
+        let mut png = testing_png(&image);
+        let iccp = Chunk::new("iCCP".parse().unwrap(), b"RGB profile\0\0x".to_vec());
+        png.insert_chunk(1, iccp);
+        reduced
+            .write_to(&mut png, &EncodeOptions::default())
+            .unwrap();
+        let gray = reduced.header().color_type().is_grayscale();
+        assert_eq!(
+            png.chunk_by_type("iCCP").is_none(),
+            gray,
+            "{:?}",
+            reduced.header()
+        );
+    }
+}
+
+#[test]
+fn test_encode_blocks() {
+    for (color_type, depth) in FORMATS {
+        let image = testing_image(color_type, depth);
+        for rows in [1, 2, 3, 7] {
+            let options = EncodeOptions {
+                rows_per_block: Some(rows),
+                ..Default::default()
+            };
+            let stream = image.encode(&options).unwrap();
+            assert_eq!(stream, image.encode(&options).unwrap());
+            let decoded = decode::decode(image.header(), &stream).unwrap();
+            assert_eq!(decoded, image.data(), "{color_type:?} {depth} {rows}");
+        }
+    }
+}
+
+#[test]
+fn test_encode_single_block_matches_stream() {
+    let image = testing_image(ColorType::Rgba, 8);
+    let single = image.encode(&EncodeOptions::default()).unwrap();
+    let options = EncodeOptions {
+        rows_per_block: Some(image.height() as usize),
+        ..Default::default()
+    };
+    assert_eq!(image.encode(&options).unwrap(), single);
+}
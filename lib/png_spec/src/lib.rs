@@ -1,6 +1,12 @@
 pub mod chunk;
 pub mod chunk_type;
+pub mod image;
+pub mod optimize;
 pub mod png;
+pub mod strip;
+pub mod text;
+pub mod xmp;
+mod zlib;
 
 mod util {
     pub enum Bit {
@@ -0,0 +1,112 @@
+use crate::{
+    image::{EncodeOptions, FilterStrategy, Image, ImageError},
+    png::Png,
+    strip::{Strip, StripReport},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Options for [`Png::optimize`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptimizeOptions {
+    /// Filter strategies to try
+    pub filters: Vec<FilterStrategy>,
+    /// Deflate levels to try
+    pub levels: Vec<u32>,
+    /// Try smaller color types, bit depths and palettes
+    pub reduce: bool,
+    /// Ancillary data to drop
+    pub strip: Option<Strip>,
+    /// See [`EncodeOptions::rows_per_block`]
+    pub rows_per_block: Option<usize>,
+}
+
+impl Default for OptimizeOptions {
+    fn default() -> Self {
+        OptimizeOptions {
+            filters: FilterStrategy::ALL.to_vec(),
+            levels: vec![6, 9],
+            reduce: true,
+            strip: None,
+            rows_per_block: None,
+        }
+    }
+}
+
+/// Outcome of [`Png::optimize`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptimizeReport {
+    pub original_size: usize,
+    pub optimized_size: usize,
+    /// Format and encoding of the written image; `None` if the original image data was kept
+    /// because nothing was smaller.
+    pub chosen: Option<(crate::image::Header, EncodeOptions)>,
+    pub stripped: Option<StripReport>,
+}
+
+impl Png {
+    /// Losslessly recompresses the image data, keeping the smallest result.
+    ///
+    /// Every candidate format from [`Image::reductions`] is encoded with every filter strategy and
+    /// deflate level. The result is decoded again and compared with the original pixels before
+    /// anything is written, so the pixels are guaranteed to stay identical.
+    pub fn optimize(&mut self, options: &OptimizeOptions) -> Result<OptimizeReport, ImageError> {
+        let original_size = self.as_bytes().len();
+        let image = Image::from_png(self)?;
+        let pixels = image.to_rgba16();
+
+        // animation frames (fdAT) are stored in the format of the default image
+        let animated = self.chunk_by_type("acTL").is_some();
+
+        let mut candidates = vec![image.clone()];
+        if options.reduce && !animated {
+            candidates.extend(image.reductions());
+        }
+
+        let overhead = |i: &Image| i.palette().len() * 3 + i.transparency().len();
+        let current = self.image_data().len() + overhead(&image);
+
+        let mut best: Option<(usize, &Image, EncodeOptions, Vec<u8>)> = None;
+        for candidate in &candidates {
+            for &filter in &options.filters {
+                for &level in &options.levels {
+                    let encode = EncodeOptions {
+                        filter,
+                        level,
+                        rows_per_block: options.rows_per_block,
+                    };
+                    let data = candidate.encode(&encode)?;
+                    let size = data.len() + overhead(candidate);
+                    if best.as_ref().is_none_or(|(s, ..)| size < *s) {
+                        best = Some((size, candidate, encode, data));
+                    }
+                }
+            }
+        }
+
+        let mut chosen = None;
+        if let Some((size, candidate, encode, _)) = best {
+            if size < current {
+                // written to a copy so a mismatch leaves this file untouched
+                let mut optimized = Png::try_from(self.as_bytes().as_slice())?;
+                candidate.write_to(&mut optimized, &encode)?;
+                if Image::from_png(&optimized)?.to_rgba16() != pixels {
+                    return Err(ImageError::PixelMismatch);
+                }
+                *self = optimized;
+                chosen = Some((*candidate.header(), encode));
+            }
+        }
+
+        let stripped = options.strip.as_ref().map(|strip| self.strip(strip));
+        self.merge_idat();
+
+        Ok(OptimizeReport {
+            original_size,
+            optimized_size: self.as_bytes().len(),
+            chosen,
+            stripped,
+        })
+    }
+}
@@ -0,0 +1,132 @@
+use super::*;
+use crate::{
+    chunk::Chunk,
+    chunk_type::ChunkType,
+    image::{tests::interlaced_stream, ColorType, FilterType, Header},
+};
+
+/// A gray gradient stored wastefully as 16-bit RGBA, with a text chunk.
+fn testing_png() -> Png {
+    let header = Header::new(32, 32, 16, ColorType::Rgba).unwrap();
+    let data = vec![0; header.row_bytes(32) * 32];
+    let mut image = Image::new(header, data).unwrap();
+    for y in 0..32 {
+        for x in 0..32 {
+            let v = ((x + y) * 4 * 257) as u16;
+            for c in 0..3 {
+                image.set_sample(x, y, c, v);
+            }
+            image.set_sample(x, y, 3, u16::MAX);
+        }
+    }
+
+    let mut png = Png::from_chunks(vec![
+        header.to_chunk(),
+        Chunk::new("tEXt".parse().unwrap(), b"Comment\0hello".to_vec()),
+        Chunk::new(ChunkType::IDAT, Vec::new()),
+        Chunk::new(ChunkType::IEND, Vec::new()),
+    ]);
+    let options = EncodeOptions {
+        filter: FilterStrategy::Fixed(FilterType::None),
+        level: 1,
+        ..Default::default()
+    };
+    image.write_to(&mut png, &options).unwrap();
+    png.split_idat(100).unwrap();
+    png
+}
+
+#[test]
+fn test_optimize_keeps_pixels() {
+    let mut png = testing_png();
+    let pixels = Image::from_png(&png).unwrap().to_rgba16();
+
+    let report = png.optimize(&OptimizeOptions::default()).unwrap();
+
+    assert!(report.optimized_size < report.original_size);
+    assert_eq!(report.optimized_size, png.as_bytes().len());
+    let (header, _) = report.chosen.unwrap();
+    assert_eq!(header.color_type(), ColorType::Grayscale);
+    assert_eq!(header.bit_depth(), 8);
+    assert_eq!(png.idat_count(), 1);
+    assert!(png.chunk_by_type("tEXt").is_some());
+
+    let reparsed = Png::try_from(png.as_bytes().as_slice()).unwrap();
+    assert_eq!(Image::from_png(&reparsed).unwrap().to_rgba16(), pixels);
+}
+
+#[test]
+fn test_optimize_without_reductions() {
+    let mut png = testing_png();
+    let options = OptimizeOptions {
+        reduce: false,
+        ..Default::default()
+    };
+    let report = png.optimize(&options).unwrap();
+    let (header, _) = report.chosen.unwrap();
+    assert_eq!(header.color_type(), ColorType::Rgba);
+    assert_eq!(header.bit_depth(), 16);
+}
+
+#[test]
+fn test_optimize_strip() {
+    let mut png = testing_png();
+    let options = OptimizeOptions {
+        strip: Some(Strip::Default),
+        ..Default::default()
+    };
+    let report = png.optimize(&options).unwrap();
+    assert!(report.stripped.is_some());
+    assert!(png.chunk_by_type("tEXt").is_none());
+}
+
+#[test]
+fn test_optimize_twice_keeps_original() {
+    let mut png = testing_png();
+    png.optimize(&OptimizeOptions::default()).unwrap();
+    let optimized = png.as_bytes();
+
+    let report = png.optimize(&OptimizeOptions::default()).unwrap();
+    assert!(report.chosen.is_none());
+    assert_eq!(png.as_bytes(), optimized);
+}
+
+#[test]
+fn test_optimize_keeps_histogram() {
+    let header = Header::new(32, 32, 8, ColorType::Indexed).unwrap();
+    let data = (0..32 * 32).map(|i| (i / 7 % 3) as u8 + 1).collect();
+    let palette = vec![[9, 9, 9], [200, 0, 0], [0, 200, 0], [0, 0, 200]];
+    let image = Image::new(header, data)
+        .unwrap()
+        .with_palette(palette, vec![]);
+    let mut ihdr = header.to_chunk().data().to_vec();
+    ihdr[12] = 1;
+    let mut png = Png::from_chunks(vec![
+        Chunk::new(ChunkType::IHDR, ihdr),
+        Chunk::new(ChunkType::PLTE, image.palette().concat()),
+        Chunk::new("hIST".parse().unwrap(), vec![0, 0, 0, 1, 0, 2, 0, 3]),
+        Chunk::new(ChunkType::IDAT, Vec::new()),
+        Chunk::new(ChunkType::IEND, Vec::new()),
+    ]);
+    png.set_image_data(&interlaced_stream(&image), 1 << 16)
+        .unwrap();
+    assert_eq!(Image::from_png(&png).unwrap(), image);
+
+    // de-interlacing alone keeps the palette, and reducing drops the unused entry
+    for reduce in [false, true] {
+        let mut png = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        let options = OptimizeOptions {
+            reduce,
+            ..Default::default()
+        };
+        png.optimize(&options).unwrap();
+        assert!(!Header::try_from(&png.chunks()[0]).unwrap().is_interlaced());
+        let image = Image::from_png(&png).unwrap();
+        let hist = png.chunk_by_type("hIST").unwrap().data().to_vec();
+        assert_eq!(hist.len(), image.palette().len() * 2);
+        for (color, count) in [([200, 0, 0], 1), ([0, 200, 0], 2), ([0, 0, 200], 3)] {
+            let i = image.palette().iter().position(|c| *c == color).unwrap();
+            assert_eq!(hist[2 * i..2 * i + 2], [0, count]);
+        }
+    }
+}
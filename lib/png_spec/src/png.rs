@@ -1,9 +1,17 @@
-use crate::{chunk::Chunk, chunk_type::ChunkType};
+use crate::{
+    chunk::Chunk,
+    chunk_type::ChunkType,
+    image::{Image, ImageError},
+    text::InternationalText,
+    xmp::{Xmp, XmpError},
+};
+use sha2::{Digest, Sha256};
 use std::str::FromStr;
 
 pub use self::error::PngError;
 
 mod error;
+mod idat;
 mod trait_impls;
 
 #[cfg(test)]
@@ -11,6 +19,10 @@ mod tests;
 
 pub struct Png {
     chunks: Vec<Chunk>,
+
+    /// Bytes following the IEND chunk. Not part of the PNG datastream but kept so files round
+    /// trip unchanged.
+    trailing: Vec<u8>,
 }
 
 impl Png {
@@ -20,7 +32,10 @@ impl Png {
     const STANDARD_HEADER: [u8; 8] = [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a];
 
     pub fn from_chunks(chunks: Vec<Chunk>) -> Png {
-        Png { chunks }
+        Png {
+            chunks,
+            trailing: Vec::new(),
+        }
     }
 
     pub fn append_chunk(&mut self, chunk: Chunk) {
@@ -34,7 +49,27 @@ impl Png {
             .position(|c| c.chunk_type() == chunk_type)
             .ok_or(PngError::ChunckTypeNotFound)?;
 
-        Ok(self.chunks.swap_remove(index))
+        Ok(self.chunks.remove(index))
+    }
+
+    /// Inserts a chunk at `index`, shifting all chunks after it.
+    pub fn insert_chunk(&mut self, index: usize, chunk: Chunk) {
+        self.chunks.insert(index, chunk)
+    }
+
+    /// Replaces the chunk at `index`, returning the old chunk.
+    pub fn replace_chunk(&mut self, index: usize, chunk: Chunk) -> Chunk {
+        std::mem::replace(&mut self.chunks[index], chunk)
+    }
+
+    /// Inserts a chunk just before IEND, or at the end if there is no IEND.
+    pub fn insert_before_end(&mut self, chunk: Chunk) {
+        let index = self
+            .chunks
+            .iter()
+            .position(|c| c.chunk_type() == &ChunkType::IEND)
+            .unwrap_or(self.chunks.len());
+        self.insert_chunk(index, chunk)
     }
 
     pub fn remove_chunk(&mut self, chunk_type: &ChunkType) -> Result<Chunk, PngError> {
@@ -46,6 +81,45 @@ impl Png {
         self.remove(&chunk_type)
     }
 
+    /// Removes every chunk matching `predicate`, keeping the order of the remaining chunks, and
+    /// returns the removed chunks.
+    pub fn remove_chunks_where(&mut self, mut predicate: impl FnMut(&Chunk) -> bool) -> Vec<Chunk> {
+        let (removed, kept) = std::mem::take(&mut self.chunks)
+            .into_iter()
+            .partition(|c| predicate(c));
+        self.chunks = kept;
+        removed
+    }
+
+    /// Bytes found after the IEND chunk.
+    pub fn trailing_data(&self) -> &[u8] {
+        &self.trailing
+    }
+
+    /// Well formed chunks at the start of the trailing data, such as those appended after IEND
+    /// by older versions of `message encode`. Parsing stops at the first invalid chunk.
+    pub fn trailing_chunks(&self) -> Vec<Chunk> {
+        let mut chunks = vec![];
+        let mut v = self.trailing.as_slice();
+        while let Some(length) = v.get(..4) {
+            let length = u32::from_be_bytes(length.try_into().unwrap()) as usize;
+            let Some(bytes) = v.get(..length.saturating_add(12)) else {
+                break;
+            };
+            let Ok(chunk) = Chunk::try_from(bytes) else {
+                break;
+            };
+            chunks.push(chunk);
+            v = &v[bytes.len()..];
+        }
+        chunks
+    }
+
+    /// Removes and returns the bytes found after the IEND chunk.
+    pub fn remove_trailing_data(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.trailing)
+    }
+
     pub fn header(&self) -> &[u8; 8] {
         &Self::STANDARD_HEADER
     }
@@ -62,10 +136,81 @@ impl Png {
         }
     }
 
+    /// Index of the iTXt chunk holding the XMP packet.
+    fn xmp_index(&self) -> Option<usize> {
+        let mut prefix = Xmp::KEYWORD.as_bytes().to_vec();
+        prefix.push(0);
+        self.chunks
+            .iter()
+            .position(|c| c.chunk_type() == &ChunkType::ITXT && c.data().starts_with(&prefix))
+    }
+
+    /// The XMP packet stored in the iTXt chunk keyed `XML:com.adobe.xmp`, if any.
+    pub fn xmp(&self) -> Result<Option<Xmp>, XmpError> {
+        self.xmp_index()
+            .map(|i| Xmp::try_from(&InternationalText::try_from(&self.chunks[i])?))
+            .transpose()
+    }
+
+    /// Writes the XMP packet back as iTXt, replacing the existing packet in place or inserting a
+    /// new chunk before IEND.
+    pub fn set_xmp(&mut self, xmp: &Xmp) -> Result<(), XmpError> {
+        let chunk = xmp.to_text().to_chunk()?;
+        match self.xmp_index() {
+            Some(i) => self.chunks[i] = chunk,
+            None => self.insert_before_end(chunk),
+        }
+        Ok(())
+    }
+
+    /// SHA-256 over the type, length and data of every critical chunk, in order. The IDAT chunks
+    /// are hashed as one, so splitting or merging them does not change the digest, and ancillary
+    /// chunks can be added or removed freely.
+    pub fn critical_digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        let mut idat_hashed = false;
+        for chunk in self.chunks.iter().filter(|c| c.chunk_type().is_critical()) {
+            let image_data;
+            let data = if chunk.chunk_type() == &ChunkType::IDAT {
+                if idat_hashed {
+                    continue;
+                }
+                idat_hashed = true;
+                image_data = self.image_data();
+                &image_data
+            } else {
+                chunk.data()
+            };
+            hasher.update(chunk.chunk_type().bytes());
+            hasher.update((data.len() as u64).to_be_bytes());
+            hasher.update(data);
+        }
+        hasher.finalize().into()
+    }
+
+    /// SHA-256 over the width, height and decoded pixels as 16-bit RGBA, so it only changes if
+    /// the picture does. Ancillary chunks, the color type, bit depth, palette order, filters and
+    /// compression make no difference.
+    pub fn pixel_digest(&self) -> Result<[u8; 32], ImageError> {
+        let image = Image::from_png(self)?;
+        let mut hasher = Sha256::new();
+        hasher.update(image.width().to_be_bytes());
+        hasher.update(image.height().to_be_bytes());
+        for pixel in image.to_rgba16() {
+            for sample in pixel {
+                hasher.update(sample.to_be_bytes());
+            }
+        }
+        Ok(hasher.finalize().into())
+    }
+
     pub fn as_bytes(&self) -> Vec<u8> {
         let h = self.header().iter();
         let c: Vec<u8> = self.chunks.iter().flat_map(Chunk::as_bytes).collect::<_>();
 
-        h.copied().chain(c).collect::<Vec<u8>>()
+        h.copied()
+            .chain(c)
+            .chain(self.trailing.iter().copied())
+            .collect::<Vec<u8>>()
     }
 }
@@ -8,6 +8,8 @@ pub enum PngError {
     Io(io::Error),
     Header,
     ChunckTypeNotFound,
+    /// Chunk data length outside of 1..=2^31 - 1
+    ChunkSize(usize),
 }
 
 impl From<io::Error> for PngError {
@@ -36,6 +38,7 @@ impl std::error::Error for PngError {
             PngError::Io(e) => Some(e),
             PngError::Header => None,
             PngError::ChunckTypeNotFound => None,
+            PngError::ChunkSize(_) => None,
         }
     }
 }
@@ -47,7 +50,11 @@ impl std::fmt::Display for PngError {
             PngError::Chunk(e) => e.fmt(f),
             PngError::Io(e) => e.fmt(f),
             PngError::Header => writeln!(f, "header is not png standard"),
-            PngError::ChunckTypeNotFound => todo!(),
+            PngError::ChunckTypeNotFound => write!(f, "chunk type not found"),
+            PngError::ChunkSize(size) => write!(
+                f,
+                "invalid chunk size '{size}': must be between 1 and 2^31 - 1 bytes"
+            ),
         }
     }
 }
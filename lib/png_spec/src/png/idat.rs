@@ -0,0 +1,93 @@
+use super::{error::PngError, Png};
+use crate::{chunk::Chunk, chunk_type::ChunkType};
+
+impl Png {
+    /// Chunk data length limit: lengths must not exceed 2^31 - 1 bytes.
+    pub const MAX_CHUNK_DATA_LENGTH: usize = (1 << 31) - 1;
+
+    /// The compressed image datastream: the concatenation of the data of all IDAT chunks.
+    pub fn image_data(&self) -> Vec<u8> {
+        self.chunks
+            .iter()
+            .filter(|c| c.chunk_type() == &ChunkType::IDAT)
+            .flat_map(|c| c.data().iter().copied())
+            .collect()
+    }
+
+    pub fn idat_count(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|c| c.chunk_type() == &ChunkType::IDAT)
+            .count()
+    }
+
+    /// Merges every run of consecutive IDAT chunks into a single IDAT chunk. The compressed
+    /// stream is copied as is, only the chunk boundaries change.
+    pub fn merge_idat(&mut self) {
+        self.resplit_idat(Self::MAX_CHUNK_DATA_LENGTH)
+    }
+
+    /// Re-splits every run of consecutive IDAT chunks into IDAT chunks holding `size` bytes of
+    /// the compressed stream each; the last chunk of a run holds the remainder.
+    pub fn split_idat(&mut self, size: usize) -> Result<(), PngError> {
+        if size == 0 || size > Self::MAX_CHUNK_DATA_LENGTH {
+            return Err(PngError::ChunkSize(size));
+        }
+        self.resplit_idat(size);
+        Ok(())
+    }
+
+    /// Replaces the image data with `data`, split into IDAT chunks of at most `size` bytes,
+    /// placed where the first IDAT chunk was.
+    pub fn set_image_data(&mut self, data: &[u8], size: usize) -> Result<(), PngError> {
+        if size == 0 || size > Self::MAX_CHUNK_DATA_LENGTH {
+            return Err(PngError::ChunkSize(size));
+        }
+        let index = self
+            .chunks
+            .iter()
+            .position(|c| c.chunk_type() == &ChunkType::IDAT)
+            .ok_or(PngError::ChunckTypeNotFound)?;
+        self.remove_chunks_where(|c| c.chunk_type() == &ChunkType::IDAT);
+
+        for (i, part) in idat_chunks(data, size).into_iter().enumerate() {
+            self.insert_chunk(index + i, part);
+        }
+        Ok(())
+    }
+
+    fn resplit_idat(&mut self, size: usize) {
+        let mut chunks: Vec<Chunk> = Vec::with_capacity(self.chunks.len());
+        let mut run: Vec<u8> = Vec::new();
+        let mut in_run = false;
+
+        for chunk in std::mem::take(&mut self.chunks) {
+            if chunk.chunk_type() == &ChunkType::IDAT {
+                in_run = true;
+                run.extend(chunk.data());
+                continue;
+            }
+            if in_run {
+                chunks.extend(idat_chunks(&run, size));
+                run.clear();
+                in_run = false;
+            }
+            chunks.push(chunk);
+        }
+        if in_run {
+            chunks.extend(idat_chunks(&run, size));
+        }
+
+        self.chunks = chunks;
+    }
+}
+
+/// An empty stream still gets one (empty) IDAT chunk so the run is not lost.
+fn idat_chunks(data: &[u8], size: usize) -> Vec<Chunk> {
+    if data.is_empty() {
+        return vec![Chunk::new(ChunkType::IDAT, Vec::new())];
+    }
+    data.chunks(size)
+        .map(|part| Chunk::new(ChunkType::IDAT, part.to_vec()))
+        .collect()
+}
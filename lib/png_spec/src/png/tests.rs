@@ -154,6 +154,166 @@ fn test_png_trait_impls() {
     let _png_string = format!("{}", png);
 }
 
+#[test]
+fn test_set_xmp() {
+    let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+    assert!(png.xmp().unwrap().is_none());
+
+    let mut xmp = crate::xmp::Xmp::new();
+    xmp.set(
+        "http://purl.org/dc/elements/1.1/",
+        "dc",
+        "format",
+        "image/png",
+    )
+    .unwrap();
+    png.set_xmp(&xmp).unwrap();
+    png.set_xmp(&xmp).unwrap();
+
+    let chunks = png.chunks();
+    let itxt = chunks.iter().filter(|c| c.chunk_type() == &ChunkType::ITXT);
+    assert_eq!(itxt.count(), 1);
+    assert_eq!(chunks[chunks.len() - 1].chunk_type(), &ChunkType::IEND);
+    assert_eq!(png.xmp().unwrap().unwrap(), xmp);
+}
+
+#[test]
+fn test_remove_chunk_keeps_order() {
+    let mut png = testing_png();
+    png.remove_str("FrSt").unwrap();
+    let types: Vec<String> = png
+        .chunks()
+        .iter()
+        .map(|c| c.chunk_type().to_string())
+        .collect();
+    assert_eq!(types, ["miDl", "LASt"]);
+}
+
+#[test]
+fn test_replace_chunk() {
+    let mut png = testing_png();
+    let old = png.replace_chunk(1, chunk_from_strings("neWr", "Replaced").unwrap());
+    assert_eq!(&old.chunk_type().to_string(), "miDl");
+    let types: Vec<String> = png
+        .chunks()
+        .iter()
+        .map(|c| c.chunk_type().to_string())
+        .collect();
+    assert_eq!(types, ["FrSt", "neWr", "LASt"]);
+}
+
+#[test]
+fn test_remove_chunks_where() {
+    let mut png = testing_png();
+    let removed = png.remove_chunks_where(|c| !c.chunk_type().is_critical());
+    assert_eq!(removed.len(), 1);
+    assert_eq!(png.chunks().len(), 2);
+}
+
+#[test]
+fn test_trailing_data() {
+    let mut bytes = PNG_FILE.to_vec();
+    bytes.extend(b"not a chunk");
+
+    let mut png = Png::try_from(bytes.as_ref()).unwrap();
+    assert_eq!(png.trailing_data(), b"not a chunk");
+    assert_eq!(png.as_bytes(), bytes);
+
+    assert_eq!(png.remove_trailing_data(), b"not a chunk");
+    assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+}
+
+#[test]
+fn test_trailing_chunks() {
+    let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hidden".to_vec());
+    let mut bytes = PNG_FILE.to_vec();
+    bytes.extend(chunk.as_bytes());
+    bytes.extend(b"not a chunk");
+
+    let png = Png::try_from(bytes.as_ref()).unwrap();
+    let chunks = png.trailing_chunks();
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].data(), b"hidden");
+
+    assert!(Png::try_from(&PNG_FILE[..])
+        .unwrap()
+        .trailing_chunks()
+        .is_empty());
+}
+
+#[test]
+fn test_critical_digest() {
+    let png = Png::try_from(&PNG_FILE[..]).unwrap();
+    let digest = png.critical_digest();
+
+    let mut edited = Png::try_from(&PNG_FILE[..]).unwrap();
+    edited.insert_before_end(chunk_from_strings("ruSt", "hidden").unwrap());
+    edited.split_idat(100).unwrap();
+    assert_eq!(edited.critical_digest(), digest);
+
+    let mut data = png.image_data();
+    data[10] ^= 1;
+    edited.set_image_data(&data, 1000).unwrap();
+    assert_ne!(edited.critical_digest(), digest);
+}
+
+#[test]
+fn test_pixel_digest() {
+    let png = Png::try_from(&PNG_FILE[..]).unwrap();
+    let digest = png.pixel_digest().unwrap();
+
+    let mut edited = Png::try_from(&PNG_FILE[..]).unwrap();
+    edited.insert_before_end(chunk_from_strings("ruSt", "hidden").unwrap());
+    edited.strip(&crate::strip::Strip::Default);
+    edited
+        .optimize(&crate::optimize::OptimizeOptions::default())
+        .unwrap();
+    assert_ne!(edited.image_data(), png.image_data());
+    assert_eq!(edited.pixel_digest().unwrap(), digest);
+
+    let mut image = Image::from_png(&png).unwrap();
+    let sample = image.sample(3, 2, 0);
+    image.set_sample(3, 2, 0, sample ^ 1);
+    image.write_to(&mut edited, &Default::default()).unwrap();
+    assert_ne!(edited.pixel_digest().unwrap(), digest);
+}
+
+#[test]
+fn test_split_and_merge_idat() {
+    let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+    let data = png.image_data();
+    assert_eq!(png.idat_count(), 1);
+
+    png.split_idat(1000).unwrap();
+    assert_eq!(png.idat_count(), data.len().div_ceil(1000));
+    assert_eq!(png.image_data(), data);
+    assert_eq!(png.chunks().last().unwrap().chunk_type(), &ChunkType::IEND);
+
+    // chunks survive a round trip through bytes, so CRCs are valid
+    let reparsed = Png::try_from(png.as_bytes().as_ref()).unwrap();
+    assert_eq!(reparsed.idat_count(), png.idat_count());
+
+    png.merge_idat();
+    assert_eq!(png.idat_count(), 1);
+    assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+}
+
+#[test]
+fn test_split_idat_invalid_size() {
+    let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+    assert!(png.split_idat(0).is_err());
+}
+
+#[test]
+fn test_set_image_data() {
+    let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+    let data = png.image_data();
+    png.split_idat(100).unwrap();
+    png.set_image_data(&data, Png::MAX_CHUNK_DATA_LENGTH)
+        .unwrap();
+    assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+}
+
 // This is the raw bytes for a shrunken version of the `dice.png` image on Wikipedia
 const PNG_FILE: [u8; 4803] = [
     137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 50, 0, 0, 0, 50, 8, 6,
@@ -3,7 +3,13 @@ use std::{
     io::{BufReader, Read},
 };
 
-use crate::chunk::Chunk;
+use crate::{
+    chunk::{error::ChunkError, Chunk},
+    chunk_type::ChunkType,
+};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use super::{error::PngError, Png};
 
@@ -12,6 +18,9 @@ impl Display for Png {
         for c in self.chunks() {
             writeln!(f, "{c}")?;
         }
+        if !self.trailing.is_empty() {
+            writeln!(f, "Trailing data: {} bytes", self.trailing.len())?;
+        }
         Ok(())
     }
 }
@@ -37,19 +46,50 @@ impl TryFrom<&[u8]> for Png {
         // reader.read_exact(&mut ihdr)?;
         // let _ihdr = ihdr;
 
-        let mut chunks: Vec<Chunk> = vec![];
+        // Chunk boundaries only depend on the length fields, so the chunks are located first and
+        // then parsed and CRC checked independently.
+        let mut slices: Vec<&[u8]> = vec![];
 
         let mut v = &value[8..];
         loop {
             if v.is_empty() {
                 break;
             }
-            let c = Chunk::try_from(v)?;
-            let size = c.size();
-            chunks.push(c);
-            v = &v[size..];
+            let size = chunk_size(v)?;
+            let end = v.get(4..8) == Some(ChunkType::IEND.bytes().as_slice());
+            slices.push(&v[..size.min(v.len())]);
+            v = &v[size.min(v.len())..];
+
+            // anything after IEND is not part of the datastream
+            if end {
+                break;
+            }
         }
 
-        Ok(Self::from_chunks(chunks))
+        #[cfg(feature = "parallel")]
+        let chunks: Vec<Result<Chunk, ChunkError>> =
+            slices.into_par_iter().map(Chunk::try_from).collect();
+        #[cfg(not(feature = "parallel"))]
+        let chunks: Vec<Result<Chunk, ChunkError>> =
+            slices.into_iter().map(Chunk::try_from).collect();
+
+        // reports the first invalid chunk, as a sequential parse would
+        let chunks = chunks
+            .into_iter()
+            .collect::<Result<Vec<Chunk>, ChunkError>>()?;
+
+        let mut png = Self::from_chunks(chunks);
+        png.trailing = v.to_vec();
+        Ok(png)
     }
 }
+
+/// Total size of the chunk starting at `bytes`, from its length field.
+fn chunk_size(bytes: &[u8]) -> Result<usize, ChunkError> {
+    let length: [u8; 4] = bytes
+        .get(..4)
+        .and_then(|l| l.try_into().ok())
+        .ok_or_else(|| ChunkError::IoError(std::io::ErrorKind::UnexpectedEof.into()))?;
+    let length = u32::from_be_bytes(length);
+    Ok((length as usize).saturating_add(4 + 4 + 4))
+}
@@ -0,0 +1,241 @@
+use crate::{chunk::Chunk, chunk_type::ChunkType, png::Png};
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+pub use self::error::StripError;
+
+mod error;
+
+#[cfg(test)]
+mod tests;
+
+/// Groups of ancillary data that can be stripped from a PNG file. Critical chunks never belong to
+/// a category and are never stripped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Category {
+    /// tEXt, zTXt and iTXt
+    Text,
+    /// eXIf
+    Exif,
+    /// Color space information: cHRM, gAMA, iCCP, sBIT, sRGB, cICP, mDCV and cLLI
+    Color,
+    /// tIME
+    Timestamps,
+    /// Ancillary chunks with the private bit set
+    Private,
+    /// Public ancillary chunks this library does not know about
+    Unknown,
+    /// Known public ancillary chunks not covered by another category, e.g. tRNS or pHYs
+    Other,
+    /// Bytes after IEND
+    Trailing,
+}
+
+impl Category {
+    /// Categories stripped when no allow-list or deny-list is given: everything that can identify
+    /// the author, device or time but does not change how the image is displayed.
+    pub const DEFAULT: [Category; 6] = [
+        Category::Text,
+        Category::Exif,
+        Category::Timestamps,
+        Category::Private,
+        Category::Unknown,
+        Category::Trailing,
+    ];
+
+    const ALL: [Category; 8] = [
+        Category::Text,
+        Category::Exif,
+        Category::Color,
+        Category::Timestamps,
+        Category::Private,
+        Category::Unknown,
+        Category::Other,
+        Category::Trailing,
+    ];
+
+    const TEXT: [&'static [u8; 4]; 3] = [b"tEXt", b"zTXt", b"iTXt"];
+    const COLOR: [&'static [u8; 4]; 8] = [
+        b"cHRM", b"gAMA", b"iCCP", b"sBIT", b"sRGB", b"cICP", b"mDCV", b"cLLI",
+    ];
+    const OTHER: [&'static [u8; 4]; 15] = [
+        b"bKGD", b"hIST", b"pHYs", b"sPLT", b"tRNS", b"oFFs", b"pCAL", b"sCAL", b"sTER", b"gIFg",
+        b"gIFx", b"dSIG", b"acTL", b"fcTL", b"fdAT",
+    ];
+
+    /// The category of a chunk type, or `None` for critical chunks.
+    pub fn of(chunk_type: &ChunkType) -> Option<Category> {
+        if chunk_type.is_critical() {
+            return None;
+        }
+        if !chunk_type.is_public() {
+            return Some(Category::Private);
+        }
+
+        let bytes = chunk_type.bytes();
+        let category = if Self::TEXT.contains(&&bytes) {
+            Category::Text
+        } else if &bytes == b"eXIf" {
+            Category::Exif
+        } else if Self::COLOR.contains(&&bytes) {
+            Category::Color
+        } else if &bytes == b"tIME" {
+            Category::Timestamps
+        } else if Self::OTHER.contains(&&bytes) {
+            Category::Other
+        } else {
+            Category::Unknown
+        };
+        Some(category)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Text => "text",
+            Category::Exif => "exif",
+            Category::Color => "color",
+            Category::Timestamps => "timestamps",
+            Category::Private => "private",
+            Category::Unknown => "unknown",
+            Category::Other => "other",
+            Category::Trailing => "trailing",
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Category {
+    type Err = StripError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.name() == s)
+            .ok_or_else(|| StripError::Category(s.to_owned()))
+    }
+}
+
+/// Selects chunks by category or by exact chunk type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    Category(Category),
+    ChunkType(ChunkType),
+}
+
+impl Rule {
+    /// Trailing data has a category but no chunk type.
+    fn matches(&self, chunk_type: Option<&ChunkType>, category: Category) -> bool {
+        match self {
+            Rule::Category(c) => *c == category,
+            Rule::ChunkType(t) => Some(t) == chunk_type,
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = StripError;
+
+    /// Category names are lowercase words and take precedence, so `text` is the text category
+    /// while `tEXt` is the chunk type.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(category) = s.parse() {
+            return Ok(Rule::Category(category));
+        }
+        ChunkType::from_str(s)
+            .map(Rule::ChunkType)
+            .map_err(|_| StripError::Rule(s.to_owned()))
+    }
+}
+
+/// Which ancillary data to strip.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Strip {
+    /// Strips the [`Category::DEFAULT`] categories.
+    #[default]
+    Default,
+    /// Strips only what matches one of the rules.
+    DenyList(Vec<Rule>),
+    /// Strips everything except what matches one of the rules.
+    AllowList(Vec<Rule>),
+}
+
+impl Strip {
+    fn should_strip(&self, chunk_type: Option<&ChunkType>, category: Category) -> bool {
+        let matches = |rules: &[Rule]| rules.iter().any(|r| r.matches(chunk_type, category));
+        match self {
+            Strip::Default => Category::DEFAULT.contains(&category),
+            Strip::DenyList(rules) => matches(rules),
+            Strip::AllowList(rules) => !matches(rules),
+        }
+    }
+}
+
+/// Chunks and bytes removed per category.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StripReport {
+    removed: BTreeMap<Category, Removed>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Removed {
+    /// Number of chunks; zero for trailing data
+    pub chunks: usize,
+    pub bytes: usize,
+}
+
+impl StripReport {
+    pub fn removed(&self) -> impl Iterator<Item = (Category, Removed)> + '_ {
+        self.removed.iter().map(|(c, r)| (*c, *r))
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.removed.values().map(|r| r.bytes).sum()
+    }
+
+    fn record(&mut self, category: Category, chunks: usize, bytes: usize) {
+        let removed = self.removed.entry(category).or_default();
+        removed.chunks += chunks;
+        removed.bytes += bytes;
+    }
+}
+
+impl fmt::Display for StripReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (category, removed) in self.removed() {
+            writeln!(
+                f,
+                "{category}: {} chunks, {} bytes",
+                removed.chunks, removed.bytes
+            )?;
+        }
+        write!(f, "total: {} bytes", self.total_bytes())
+    }
+}
+
+impl Png {
+    /// Strips ancillary chunks and trailing data. Critical chunks are never removed.
+    pub fn strip(&mut self, strip: &Strip) -> StripReport {
+        let mut report = StripReport::default();
+
+        let removed = self.remove_chunks_where(|c: &Chunk| {
+            Category::of(c.chunk_type())
+                .is_some_and(|category| strip.should_strip(Some(c.chunk_type()), category))
+        });
+        for chunk in removed {
+            if let Some(category) = Category::of(chunk.chunk_type()) {
+                report.record(category, 1, chunk.size());
+            }
+        }
+
+        if !self.trailing_data().is_empty() && strip.should_strip(None, Category::Trailing) {
+            let trailing = self.remove_trailing_data();
+            report.record(Category::Trailing, 0, trailing.len());
+        }
+
+        report
+    }
+}
@@ -0,0 +1,23 @@
+use std::{error, fmt};
+
+#[derive(Debug)]
+pub enum StripError {
+    Category(String),
+    /// Neither a category nor a chunk type
+    Rule(String),
+}
+
+impl fmt::Display for StripError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StripError::Category(c) => write!(f, "unknown category '{c}'"),
+            StripError::Rule(r) => write!(
+                f,
+                "'{r}' is neither a chunk type nor one of the categories: text, exif, color, \
+                timestamps, private, unknown, other, trailing"
+            ),
+        }
+    }
+}
+
+impl error::Error for StripError {}
@@ -0,0 +1,116 @@
+use super::*;
+
+fn chunk(chunk_type: &str, data: &str) -> Chunk {
+    Chunk::new(chunk_type.parse().unwrap(), data.as_bytes().to_vec())
+}
+
+fn testing_png() -> Png {
+    let chunks = vec![
+        chunk("IHDR", "header"),
+        chunk("gAMA", "gamma"),
+        chunk("tEXt", "Author\0Alice"),
+        chunk("tIME", "time"),
+        chunk("IDAT", "data"),
+        chunk("ruSt", "private"),
+        chunk("RuSt", "private critical"),
+        chunk("zZZz", "unknown"),
+        chunk("eXIf", "exif"),
+        chunk("IEND", ""),
+    ];
+    let bytes: Vec<u8> = Png::from_chunks(chunks)
+        .as_bytes()
+        .into_iter()
+        .chain(*b"trailing")
+        .collect();
+    Png::try_from(bytes.as_ref()).unwrap()
+}
+
+fn types(png: &Png) -> Vec<String> {
+    png.chunks()
+        .iter()
+        .map(|c| c.chunk_type().to_string())
+        .collect()
+}
+
+#[test]
+fn test_category_of() {
+    let of = |s: &str| Category::of(&s.parse().unwrap());
+    assert_eq!(of("IHDR"), None);
+    assert_eq!(of("RuSt"), None);
+    assert_eq!(of("zTXt"), Some(Category::Text));
+    assert_eq!(of("eXIf"), Some(Category::Exif));
+    assert_eq!(of("sRGB"), Some(Category::Color));
+    assert_eq!(of("tIME"), Some(Category::Timestamps));
+    assert_eq!(of("ruSt"), Some(Category::Private));
+    assert_eq!(of("zZZz"), Some(Category::Unknown));
+    assert_eq!(of("pHYs"), Some(Category::Other));
+}
+
+#[test]
+fn test_rule_from_str() {
+    assert_eq!(
+        "text".parse::<Rule>().unwrap(),
+        Rule::Category(Category::Text)
+    );
+    assert_eq!(
+        "tEXt".parse::<Rule>().unwrap(),
+        Rule::ChunkType("tEXt".parse().unwrap())
+    );
+    assert!("nope!".parse::<Rule>().is_err());
+}
+
+#[test]
+fn test_strip_default() {
+    let mut png = testing_png();
+    let report = png.strip(&Strip::Default);
+
+    assert_eq!(types(&png), ["IHDR", "gAMA", "IDAT", "RuSt", "IEND"]);
+    assert!(png.trailing_data().is_empty());
+
+    let removed: Vec<_> = report.removed().collect();
+    assert_eq!(removed.len(), 6);
+    assert_eq!(
+        removed[0],
+        (
+            Category::Text,
+            Removed {
+                chunks: 1,
+                bytes: 12 + 12
+            }
+        )
+    );
+    assert_eq!(
+        removed[5],
+        (
+            Category::Trailing,
+            Removed {
+                chunks: 0,
+                bytes: 8
+            }
+        )
+    );
+}
+
+#[test]
+fn test_strip_deny_list() {
+    let mut png = testing_png();
+    let rules = vec![Rule::Category(Category::Color), "tIME".parse().unwrap()];
+    png.strip(&Strip::DenyList(rules));
+
+    assert_eq!(
+        types(&png),
+        ["IHDR", "tEXt", "IDAT", "ruSt", "RuSt", "zZZz", "eXIf", "IEND"]
+    );
+    assert_eq!(png.trailing_data(), b"trailing");
+}
+
+#[test]
+fn test_strip_allow_list() {
+    let mut png = testing_png();
+    let rules = vec![Rule::Category(Category::Trailing), "eXIf".parse().unwrap()];
+    let report = png.strip(&Strip::AllowList(rules));
+
+    assert_eq!(types(&png), ["IHDR", "IDAT", "RuSt", "eXIf", "IEND"]);
+    assert_eq!(png.trailing_data(), b"trailing");
+    assert_eq!(report.removed().count(), 5);
+}
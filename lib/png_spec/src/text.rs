@@ -0,0 +1,191 @@
+use crate::{chunk::Chunk, chunk_type::ChunkType};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use std::io::{Read, Write};
+
+pub use self::error::TextError;
+
+mod error;
+
+#[cfg(test)]
+mod tests;
+
+/// International textual data (iTXt).
+///
+/// | Field              | Size                                    |
+/// |--------------------|-----------------------------------------|
+/// | Keyword            | 1-79 bytes (character string)           |
+/// | Null separator     | 1 byte                                  |
+/// | Compression flag   | 1 byte                                  |
+/// | Compression method | 1 byte                                  |
+/// | Language tag       | 0 or more bytes (character string)      |
+/// | Null separator     | 1 byte                                  |
+/// | Translated keyword | 0 or more bytes                         |
+/// | Null separator     | 1 byte                                  |
+/// | Text               | 0 or more bytes                         |
+///
+/// ['iTXt International textual data'](https://www.w3.org/TR/png/#11iTXt)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InternationalText {
+    keyword: String,
+    compressed: bool,
+    language_tag: String,
+    translated_keyword: String,
+    text: String,
+}
+
+impl InternationalText {
+    /// The only compression method defined by the specification: zlib datastream with deflate
+    /// compression.
+    const COMPRESSION_METHOD: u8 = 0;
+
+    /// Keywords must be between 1 and 79 bytes long.
+    const MAX_KEYWORD_LENGTH: usize = 79;
+
+    /// Longest compressed text that is inflated, so a small chunk cannot exhaust memory.
+    const MAX_INFLATED_LENGTH: usize = 1 << 26;
+
+    pub fn new(keyword: &str, text: &str) -> Result<InternationalText, TextError> {
+        validate_keyword(keyword)?;
+        Ok(InternationalText {
+            keyword: keyword.to_owned(),
+            compressed: false,
+            language_tag: String::new(),
+            translated_keyword: String::new(),
+            text: text.to_owned(),
+        })
+    }
+
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    /// Whether the text is stored zlib compressed when written back to a chunk.
+    pub fn set_compressed(&mut self, compressed: bool) {
+        self.compressed = compressed;
+    }
+
+    pub fn language_tag(&self) -> &str {
+        &self.language_tag
+    }
+
+    pub fn translated_keyword(&self) -> &str {
+        &self.translated_keyword
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn set_text(&mut self, text: String) {
+        self.text = text;
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk, TextError> {
+        let mut data: Vec<u8> = Vec::with_capacity(self.keyword.len() + self.text.len() + 5);
+        // validated as Latin-1 on construction
+        data.extend(self.keyword.chars().map(|c| c as u8));
+        data.push(0);
+        data.push(self.compressed.into());
+        data.push(Self::COMPRESSION_METHOD);
+        data.extend(self.language_tag.bytes());
+        data.push(0);
+        data.extend(self.translated_keyword.bytes());
+        data.push(0);
+
+        if self.compressed {
+            let mut encoder = ZlibEncoder::new(data, Compression::default());
+            encoder.write_all(self.text.as_bytes())?;
+            data = encoder.finish()?;
+        } else {
+            data.extend(self.text.bytes());
+        }
+
+        Ok(Chunk::new(ChunkType::ITXT, data))
+    }
+}
+
+impl TryFrom<&Chunk> for InternationalText {
+    type Error = TextError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        if chunk.chunk_type() != &ChunkType::ITXT {
+            return Err(TextError::ChunkType(*chunk.chunk_type()));
+        }
+
+        let (keyword, rest) = split_null(chunk.data())?;
+        let keyword = latin1(keyword);
+        validate_keyword(&keyword)?;
+
+        let (compressed, method, rest) = match rest {
+            [flag, method, rest @ ..] => (*flag, *method, rest),
+            _ => return Err(TextError::UnexpectedEnd),
+        };
+        let compressed = match compressed {
+            0 => false,
+            1 => true,
+            flag => return Err(TextError::CompressionFlag(flag)),
+        };
+        if compressed && method != Self::COMPRESSION_METHOD {
+            return Err(TextError::CompressionMethod(method));
+        }
+
+        let (language_tag, rest) = split_null(rest)?;
+        let (translated_keyword, text) = split_null(rest)?;
+
+        let text = if compressed {
+            let mut decompressed = Vec::new();
+            ZlibDecoder::new(text)
+                .take(Self::MAX_INFLATED_LENGTH as u64 + 1)
+                .read_to_end(&mut decompressed)?;
+            if decompressed.len() > Self::MAX_INFLATED_LENGTH {
+                return Err(TextError::TooLong(Self::MAX_INFLATED_LENGTH));
+            }
+            String::from_utf8(decompressed).map_err(|e| e.utf8_error())?
+        } else {
+            std::str::from_utf8(text)?.to_owned()
+        };
+
+        Ok(InternationalText {
+            keyword,
+            compressed,
+            language_tag: std::str::from_utf8(language_tag)?.to_owned(),
+            translated_keyword: std::str::from_utf8(translated_keyword)?.to_owned(),
+            text,
+        })
+    }
+}
+
+/// Splits `bytes` at the first null separator, dropping the separator.
+fn split_null(bytes: &[u8]) -> Result<(&[u8], &[u8]), TextError> {
+    let index = bytes
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or(TextError::MissingNullSeparator)?;
+    Ok((&bytes[..index], &bytes[index + 1..]))
+}
+
+/// Keywords are restricted to Latin-1, which maps one to one onto the first 256 code points.
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().copied().map(char::from).collect()
+}
+
+/// Keywords are 1-79 bytes of printable Latin-1 characters with no leading, trailing or
+/// consecutive spaces.
+fn validate_keyword(keyword: &str) -> Result<(), TextError> {
+    let valid_char = |c: char| matches!(c as u32, 32..=126 | 161..=255);
+
+    if keyword.is_empty()
+        || keyword.chars().count() > InternationalText::MAX_KEYWORD_LENGTH
+        || !keyword.chars().all(valid_char)
+        || keyword.starts_with(' ')
+        || keyword.ends_with(' ')
+        || keyword.contains("  ")
+    {
+        return Err(TextError::Keyword(keyword.to_owned()));
+    }
+    Ok(())
+}
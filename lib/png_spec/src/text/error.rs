@@ -0,0 +1,65 @@
+use crate::chunk_type::ChunkType;
+use std::{error, fmt, io, str::Utf8Error};
+
+#[derive(Debug)]
+pub enum TextError {
+    /// The chunk is not a textual chunk of the expected type.
+    ChunkType(ChunkType),
+    /// Keywords are 1-79 printable Latin-1 characters.
+    Keyword(String),
+    MissingNullSeparator,
+    UnexpectedEnd,
+    CompressionFlag(u8),
+    CompressionMethod(u8),
+    /// The compressed text inflates to more than this many bytes.
+    TooLong(usize),
+    Utf8(Utf8Error),
+    Io(io::Error),
+}
+
+impl From<Utf8Error> for TextError {
+    fn from(v: Utf8Error) -> Self {
+        Self::Utf8(v)
+    }
+}
+
+impl From<io::Error> for TextError {
+    fn from(v: io::Error) -> Self {
+        Self::Io(v)
+    }
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextError::ChunkType(t) => write!(f, "chunk type '{t}' is not a textual chunk"),
+            TextError::Keyword(k) => write!(
+                f,
+                "invalid keyword '{k}': keywords are 1-79 printable Latin-1 characters"
+            ),
+            TextError::MissingNullSeparator => write!(f, "missing null separator"),
+            TextError::UnexpectedEnd => write!(f, "unexpected end of chunk data"),
+            TextError::CompressionFlag(flag) => {
+                write!(f, "invalid compression flag '{flag}': must be 0 or 1")
+            }
+            TextError::CompressionMethod(method) => {
+                write!(f, "unknown compression method '{method}'")
+            }
+            TextError::TooLong(max) => {
+                write!(f, "compressed text inflates to more than {max} bytes")
+            }
+            TextError::Utf8(e) => write!(f, "text is not valid UTF-8: {e}"),
+            TextError::Io(e) => write!(f, "failed to (de)compress text: {e}"),
+        }
+    }
+}
+
+impl error::Error for TextError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            TextError::Utf8(e) => Some(e),
+            TextError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use super::*;
+
+#[test]
+fn test_itxt_round_trip() {
+    let text = InternationalText::new("Comment", "Hello, wörld").unwrap();
+    let chunk = text.to_chunk().unwrap();
+    assert_eq!(chunk.chunk_type(), &ChunkType::ITXT);
+
+    let actual = InternationalText::try_from(&chunk).unwrap();
+    assert_eq!(actual, text);
+}
+
+#[test]
+fn test_itxt_compressed_round_trip() {
+    let mut text = InternationalText::new("Comment", &"repeat ".repeat(100)).unwrap();
+    text.set_compressed(true);
+    let chunk = text.to_chunk().unwrap();
+    assert!(chunk.data_length() < text.text().len());
+
+    let actual = InternationalText::try_from(&chunk).unwrap();
+    assert!(actual.is_compressed());
+    assert_eq!(actual.text(), text.text());
+}
+
+#[test]
+fn test_itxt_from_bytes() {
+    let data = b"Title\0\0\0en\0Titel\0Dice".to_vec();
+    let chunk = Chunk::new(ChunkType::ITXT, data);
+    let text = InternationalText::try_from(&chunk).unwrap();
+
+    assert_eq!(text.keyword(), "Title");
+    assert_eq!(text.language_tag(), "en");
+    assert_eq!(text.translated_keyword(), "Titel");
+    assert_eq!(text.text(), "Dice");
+}
+
+#[test]
+fn test_itxt_invalid_keyword() {
+    assert!(InternationalText::new("", "text").is_err());
+    assert!(InternationalText::new(" Comment", "text").is_err());
+    assert!(InternationalText::new("Two  spaces", "text").is_err());
+    assert!(InternationalText::new(&"k".repeat(80), "text").is_err());
+}
+
+#[test]
+fn test_itxt_missing_separator() {
+    let chunk = Chunk::new(ChunkType::ITXT, b"Comment".to_vec());
+    assert!(InternationalText::try_from(&chunk).is_err());
+}
+
+#[test]
+fn test_itxt_wrong_chunk_type() {
+    let chunk = Chunk::new("tEXt".parse().unwrap(), b"Comment\0text".to_vec());
+    assert!(InternationalText::try_from(&chunk).is_err());
+}
+
+#[test]
+fn test_itxt_inflate_limit() {
+    let header = b"Comment\0\x01\0\0\0".to_vec();
+    let mut encoder = ZlibEncoder::new(header, Compression::default());
+    encoder
+        .write_all(&vec![b'a'; InternationalText::MAX_INFLATED_LENGTH + 1])
+        .unwrap();
+    let chunk = Chunk::new(ChunkType::ITXT, encoder.finish().unwrap());
+    assert!(matches!(
+        InternationalText::try_from(&chunk),
+        Err(TextError::TooLong(_))
+    ));
+}
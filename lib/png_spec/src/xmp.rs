@@ -0,0 +1,403 @@
+use crate::text::InternationalText;
+use std::{collections::HashMap, ops::Range};
+
+pub use self::error::XmpError;
+
+mod error;
+mod scanner;
+
+#[cfg(test)]
+mod tests;
+
+use scanner::{Event, Scanner, Tag};
+
+/// Resource Description Framework namespace used by every XMP packet.
+const RDF_NAMESPACE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+/// Extensible Metadata Platform packet stored in an iTXt chunk keyed `XML:com.adobe.xmp`.
+///
+/// The packet is kept verbatim; reading parses it on demand and writing only touches the bytes of
+/// the property being changed so the rest of the packet, including any padding, is preserved.
+///
+/// ['XMP Specification Part 3'](https://github.com/adobe/XMP-Toolkit-SDK/blob/main/docs/XMPSpecificationPart3.pdf)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Xmp {
+    packet: String,
+}
+
+/// A single property of an XMP packet. Array values (`rdf:Seq`, `rdf:Bag`, `rdf:Alt`) are joined
+/// with `"; "`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmpProperty {
+    pub namespace: String,
+    pub prefix: String,
+    pub name: String,
+    pub value: String,
+}
+
+/// Where a property's value lives in the packet.
+enum Location {
+    /// `prefix:name="value"` attribute on `rdf:Description`
+    Attribute(Range<usize>),
+    /// `<prefix:name>value</prefix:name>` element
+    Element(Range<usize>),
+    /// `<prefix:name/>` element without content. Holds the range of the closing `/>` and the
+    /// qualified name, so a value can be set by closing the element properly.
+    Empty(Range<usize>, String),
+    /// `<prefix:name><rdf:Seq><rdf:li>value</rdf:li>...` array element. Holds the content range of
+    /// every `rdf:li` and their full ranges.
+    Array(Vec<(Range<usize>, Range<usize>)>),
+}
+
+type OpenProperty = (XmpProperty, usize, Vec<(Range<usize>, Range<usize>)>, bool);
+
+struct Located {
+    property: XmpProperty,
+    location: Location,
+}
+
+impl Xmp {
+    /// iTXt keyword identifying the XMP packet.
+    pub const KEYWORD: &'static str = "XML:com.adobe.xmp";
+
+    /// Packet wrapper used when an image has no XMP packet yet.
+    const EMPTY_PACKET: &'static str =
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+        <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n \
+        <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n  \
+        <rdf:Description rdf:about=\"\"/>\n \
+        </rdf:RDF>\n\
+        </x:xmpmeta>\n\
+        <?xpacket end=\"w\"?>";
+
+    pub fn new() -> Xmp {
+        Xmp {
+            packet: Self::EMPTY_PACKET.to_owned(),
+        }
+    }
+
+    pub fn from_packet(packet: String) -> Xmp {
+        Xmp { packet }
+    }
+
+    pub fn packet(&self) -> &str {
+        &self.packet
+    }
+
+    /// Lists every simple and array property of every `rdf:Description`.
+    pub fn properties(&self) -> Result<Vec<XmpProperty>, XmpError> {
+        Ok(self.locate()?.into_iter().map(|l| l.property).collect())
+    }
+
+    /// Gets the value of the property `name` in `namespace`.
+    pub fn get(&self, namespace: &str, name: &str) -> Result<Option<String>, XmpError> {
+        Ok(self
+            .properties()?
+            .into_iter()
+            .find(|p| p.namespace == namespace && p.name == name)
+            .map(|p| p.value))
+    }
+
+    /// Sets the property `name` in `namespace`, leaving the rest of the packet untouched.
+    ///
+    /// Existing properties are changed in place, except that an array property (`rdf:Seq`,
+    /// `rdf:Bag` or `rdf:Alt`) keeps only its first item, set to `value`. New properties are
+    /// added as attributes of the first `rdf:Description`, declaring `prefix` for `namespace` if
+    /// it is not already declared.
+    pub fn set(
+        &mut self,
+        namespace: &str,
+        prefix: &str,
+        name: &str,
+        value: &str,
+    ) -> Result<(), XmpError> {
+        let escaped = escape(value);
+        let existing = self
+            .locate()?
+            .into_iter()
+            .find(|l| l.property.namespace == namespace && l.property.name == name);
+
+        if let Some(existing) = existing {
+            match existing.location {
+                Location::Attribute(range) | Location::Element(range) => {
+                    self.packet.replace_range(range, &escaped);
+                }
+                Location::Empty(closing, name) => {
+                    self.packet
+                        .replace_range(closing, &format!(">{escaped}</{name}>"));
+                }
+                Location::Array(items) => {
+                    // keep the first item and drop the rest, back to front so ranges stay valid
+                    for (_, full) in items.iter().skip(1).rev() {
+                        self.packet.replace_range(full.clone(), "");
+                    }
+                    self.packet.replace_range(items[0].0.clone(), &escaped);
+                }
+            }
+            return Ok(());
+        }
+
+        self.insert_attribute(namespace, prefix, name, &escaped)
+    }
+
+    pub fn to_text(&self) -> InternationalText {
+        InternationalText::new(Self::KEYWORD, &self.packet).expect("XMP keyword is valid")
+    }
+
+    fn insert_attribute(
+        &mut self,
+        namespace: &str,
+        prefix: &str,
+        name: &str,
+        escaped: &str,
+    ) -> Result<(), XmpError> {
+        let mut namespaces: HashMap<String, String> = HashMap::new();
+        let mut description: Option<Tag> = None;
+
+        for event in Scanner::new(&self.packet) {
+            if let Event::Start(tag) = event? {
+                declare(&mut namespaces, &tag);
+                if resolve(&namespaces, tag.name) == Some((RDF_NAMESPACE, "Description")) {
+                    description = Some(tag);
+                    break;
+                }
+            }
+        }
+        let description = description.ok_or(XmpError::NoDescription)?;
+
+        let declared_prefix = namespaces
+            .iter()
+            .find(|(_, uri)| uri.as_str() == namespace)
+            .map(|(p, _)| p.clone());
+
+        let mut insert = String::new();
+        let prefix = match declared_prefix {
+            Some(p) => p,
+            None => {
+                if let Some(other) = namespaces.get(prefix) {
+                    return Err(XmpError::PrefixConflict {
+                        prefix: prefix.to_owned(),
+                        namespace: other.clone(),
+                    });
+                }
+                insert.push_str(&format!(" xmlns:{prefix}=\"{}\"", escape(namespace)));
+                prefix.to_owned()
+            }
+        };
+        insert.push_str(&format!(" {prefix}:{name}=\"{escaped}\""));
+
+        self.packet.insert_str(description.attributes_end, &insert);
+        Ok(())
+    }
+
+    fn locate(&self) -> Result<Vec<Located>, XmpError> {
+        let mut located = Vec::new();
+        let mut namespaces: HashMap<String, String> = HashMap::new();
+
+        // depth of the open `rdf:Description`, if any
+        let mut description: Option<usize> = None;
+        // property element open directly below the description: (property, content start,
+        // array items, is a structure)
+        let mut property: Option<OpenProperty> = None;
+        // `rdf:li` start (content start, element start)
+        let mut item: Option<(usize, usize)> = None;
+        let mut depth = 0;
+
+        for event in Scanner::new(&self.packet) {
+            match event? {
+                Event::Start(tag) => {
+                    declare(&mut namespaces, &tag);
+                    let resolved = resolve(&namespaces, tag.name);
+
+                    if let (Some(d), Some((_, _, _, structured))) = (description, property.as_mut())
+                    {
+                        // anything other than an array below a property is a structure
+                        let array_part = matches!(
+                            resolved,
+                            Some((RDF_NAMESPACE, "Seq" | "Bag" | "Alt" | "li"))
+                        );
+                        if depth > d + 1 && !array_part {
+                            *structured = true;
+                        }
+                    }
+
+                    if description.is_some_and(|d| depth == d + 1) {
+                        let (namespace, name) = resolved.ok_or_else(|| undeclared(tag.name))?;
+                        let prop = XmpProperty {
+                            namespace: namespace.to_owned(),
+                            prefix: prefix_of(tag.name).to_owned(),
+                            name: name.to_owned(),
+                            value: String::new(),
+                        };
+                        if tag.self_closing {
+                            located.push(Located {
+                                property: prop,
+                                location: Location::Empty(
+                                    tag.attributes_end..tag.end,
+                                    tag.name.to_owned(),
+                                ),
+                            });
+                        } else {
+                            property = Some((prop, tag.end, Vec::new(), false));
+                        }
+                    } else if resolved == Some((RDF_NAMESPACE, "li")) && property.is_some() {
+                        item = Some((tag.end, tag.start));
+                    } else if description.is_none()
+                        && resolved == Some((RDF_NAMESPACE, "Description"))
+                    {
+                        for attr in &tag.attributes {
+                            if attr.name.starts_with("xmlns") {
+                                continue;
+                            }
+                            let (namespace, name) = resolve(&namespaces, attr.name)
+                                .ok_or_else(|| undeclared(attr.name))?;
+                            if namespace == RDF_NAMESPACE {
+                                continue;
+                            }
+                            located.push(Located {
+                                property: XmpProperty {
+                                    namespace: namespace.to_owned(),
+                                    prefix: prefix_of(attr.name).to_owned(),
+                                    name: name.to_owned(),
+                                    value: unescape(&self.packet[attr.value.clone()]),
+                                },
+                                location: Location::Attribute(attr.value.clone()),
+                            });
+                        }
+                        if !tag.self_closing {
+                            description = Some(depth);
+                        }
+                    }
+
+                    if !tag.self_closing {
+                        depth += 1;
+                    }
+                }
+                Event::End(tag) => {
+                    depth -= 1;
+                    if description == Some(depth) {
+                        description = None;
+                    } else if description.is_some_and(|d| depth == d + 1) {
+                        if let Some((mut prop, content_start, items, false)) = property.take() {
+                            let location = if items.is_empty() {
+                                let content = content_start..tag.start;
+                                prop.value = unescape(self.packet[content.clone()].trim());
+                                Location::Element(content)
+                            } else {
+                                prop.value = items
+                                    .iter()
+                                    .map(|(content, _)| unescape(&self.packet[content.clone()]))
+                                    .collect::<Vec<_>>()
+                                    .join("; ");
+                                Location::Array(items)
+                            };
+                            located.push(Located {
+                                property: prop,
+                                location,
+                            });
+                        }
+                    } else if let Some((content_start, start)) = item.take() {
+                        if let Some((_, _, items, _)) = property.as_mut() {
+                            items.push((content_start..tag.start, start..tag.end));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(located)
+    }
+}
+
+impl Default for Xmp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TryFrom<&InternationalText> for Xmp {
+    type Error = XmpError;
+
+    fn try_from(text: &InternationalText) -> Result<Self, Self::Error> {
+        if text.keyword() != Self::KEYWORD {
+            return Err(XmpError::Keyword(text.keyword().to_owned()));
+        }
+        Ok(Xmp::from_packet(text.text().to_owned()))
+    }
+}
+
+/// Records the `xmlns:prefix="uri"` declarations of `tag`.
+fn declare(namespaces: &mut HashMap<String, String>, tag: &Tag) {
+    for attr in &tag.attributes {
+        if let Some(prefix) = attr.name.strip_prefix("xmlns:") {
+            namespaces.insert(prefix.to_owned(), unescape(attr.raw_value));
+        }
+    }
+}
+
+/// Resolves a qualified `prefix:name` to `(namespace, name)`.
+fn resolve<'a>(
+    namespaces: &'a HashMap<String, String>,
+    qname: &'a str,
+) -> Option<(&'a str, &'a str)> {
+    let (prefix, name) = qname.split_once(':')?;
+    namespaces.get(prefix).map(|ns| (ns.as_str(), name))
+}
+
+fn prefix_of(qname: &str) -> &str {
+    qname.split_once(':').map_or("", |(p, _)| p)
+}
+
+fn undeclared(qname: &str) -> XmpError {
+    XmpError::UndeclaredPrefix(prefix_of(qname).to_owned())
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let decoded = entity.and_then(|(name, _)| match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => name
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| name.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        });
+        match (decoded, entity) {
+            (Some(c), Some((_, end))) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
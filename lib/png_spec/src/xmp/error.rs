@@ -0,0 +1,55 @@
+use crate::text::TextError;
+use std::{error, fmt};
+
+#[derive(Debug)]
+pub enum XmpError {
+    Text(TextError),
+    /// The iTXt chunk is not keyed `XML:com.adobe.xmp`.
+    Keyword(String),
+    /// The packet is not well formed XML.
+    Xml {
+        offset: usize,
+        message: &'static str,
+    },
+    UndeclaredPrefix(String),
+    /// The prefix is already bound to another namespace.
+    PrefixConflict {
+        prefix: String,
+        namespace: String,
+    },
+    /// The packet has no `rdf:Description` to add properties to.
+    NoDescription,
+}
+
+impl From<TextError> for XmpError {
+    fn from(v: TextError) -> Self {
+        Self::Text(v)
+    }
+}
+
+impl fmt::Display for XmpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XmpError::Text(e) => e.fmt(f),
+            XmpError::Keyword(k) => write!(f, "iTXt keyword '{k}' is not an XMP packet"),
+            XmpError::Xml { offset, message } => {
+                write!(f, "malformed XMP packet at byte {offset}: {message}")
+            }
+            XmpError::UndeclaredPrefix(p) => write!(f, "namespace prefix '{p}' is not declared"),
+            XmpError::PrefixConflict { prefix, namespace } => write!(
+                f,
+                "namespace prefix '{prefix}' is already bound to '{namespace}'"
+            ),
+            XmpError::NoDescription => write!(f, "XMP packet has no rdf:Description"),
+        }
+    }
+}
+
+impl error::Error for XmpError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            XmpError::Text(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Minimal XML tokenizer that keeps byte offsets so XMP properties can be edited in place.
+
+use super::XmpError;
+use std::ops::Range;
+
+pub struct Attribute<'a> {
+    pub name: &'a str,
+    pub raw_value: &'a str,
+    /// Offsets of the value, excluding quotes
+    pub value: Range<usize>,
+}
+
+pub struct Tag<'a> {
+    pub name: &'a str,
+    pub attributes: Vec<Attribute<'a>>,
+    pub start: usize,
+    /// Offset just past the closing `>`
+    pub end: usize,
+    /// Offset of the `/>` or `>` closing the start tag
+    pub attributes_end: usize,
+    pub self_closing: bool,
+}
+
+pub struct EndTag {
+    pub start: usize,
+    pub end: usize,
+}
+
+pub enum Event<'a> {
+    Start(Tag<'a>),
+    End(EndTag),
+}
+
+pub struct Scanner<'a> {
+    xml: &'a str,
+    position: usize,
+    open: Vec<&'a str>,
+    failed: bool,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(xml: &'a str) -> Scanner<'a> {
+        Scanner {
+            xml,
+            position: 0,
+            open: Vec::new(),
+            failed: false,
+        }
+    }
+
+    fn error(&mut self, offset: usize, message: &'static str) -> XmpError {
+        self.failed = true;
+        XmpError::Xml { offset, message }
+    }
+
+    /// Skips past `terminator`, returning false if it is never found.
+    fn skip_past(&mut self, terminator: &str) -> bool {
+        match self.xml[self.position..].find(terminator) {
+            Some(i) => {
+                self.position += i + terminator.len();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn end_tag(&mut self, start: usize) -> Result<Event<'a>, XmpError> {
+        let close = match self.xml[start..].find('>') {
+            Some(i) => start + i,
+            None => return Err(self.error(start, "unterminated end tag")),
+        };
+        let name = self.xml[start + 2..close].trim();
+        if self.open.pop() != Some(name) {
+            return Err(self.error(start, "mismatched end tag"));
+        }
+        self.position = close + 1;
+        Ok(Event::End(EndTag {
+            start,
+            end: close + 1,
+        }))
+    }
+
+    fn start_tag(&mut self, start: usize) -> Result<Event<'a>, XmpError> {
+        let xml = self.xml;
+        let bytes = xml.as_bytes();
+        let mut i = start + 1;
+        while i < bytes.len() && !is_delimiter(bytes[i]) {
+            i += 1;
+        }
+        let name = &xml[start + 1..i];
+        if name.is_empty() {
+            return Err(self.error(start, "missing element name"));
+        }
+
+        let mut attributes = Vec::new();
+        loop {
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            match bytes.get(i) {
+                None => return Err(self.error(start, "unterminated start tag")),
+                Some(b'>') => {
+                    self.open.push(name);
+                    self.position = i + 1;
+                    return Ok(Event::Start(Tag {
+                        name,
+                        attributes,
+                        start,
+                        end: i + 1,
+                        attributes_end: i,
+                        self_closing: false,
+                    }));
+                }
+                Some(b'/') if bytes.get(i + 1) == Some(&b'>') => {
+                    self.position = i + 2;
+                    return Ok(Event::Start(Tag {
+                        name,
+                        attributes,
+                        start,
+                        end: i + 2,
+                        attributes_end: i,
+                        self_closing: true,
+                    }));
+                }
+                Some(_) => {
+                    let name_start = i;
+                    while i < bytes.len() && !is_delimiter(bytes[i]) && bytes[i] != b'=' {
+                        i += 1;
+                    }
+                    let attr_name = &xml[name_start..i];
+                    while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                        i += 1;
+                    }
+                    if attr_name.is_empty() || bytes.get(i) != Some(&b'=') {
+                        return Err(self.error(name_start, "malformed attribute"));
+                    }
+                    i += 1;
+                    while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                        i += 1;
+                    }
+                    let quote = match bytes.get(i) {
+                        Some(q @ (b'"' | b'\'')) => *q,
+                        _ => return Err(self.error(i, "attribute value must be quoted")),
+                    };
+                    let value_start = i + 1;
+                    let value_end = match bytes[value_start..].iter().position(|&b| b == quote) {
+                        Some(len) => value_start + len,
+                        None => return Err(self.error(i, "unterminated attribute value")),
+                    };
+                    attributes.push(Attribute {
+                        name: attr_name,
+                        raw_value: &xml[value_start..value_end],
+                        value: value_start..value_end,
+                    });
+                    i = value_end + 1;
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Scanner<'a> {
+    type Item = Result<Event<'a>, XmpError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.failed {
+                return None;
+            }
+            let start = match self.xml[self.position..].find('<') {
+                Some(i) => self.position + i,
+                None => {
+                    if self.open.is_empty() {
+                        return None;
+                    }
+                    return Some(Err(self.error(self.xml.len(), "unclosed element")));
+                }
+            };
+            self.position = start;
+            let rest = &self.xml[start..];
+
+            let skipped = if rest.starts_with("<?") {
+                Some(self.skip_past("?>"))
+            } else if rest.starts_with("<!--") {
+                Some(self.skip_past("-->"))
+            } else if rest.starts_with("<![CDATA[") {
+                Some(self.skip_past("]]>"))
+            } else if rest.starts_with("<!") {
+                Some(self.skip_past(">"))
+            } else {
+                None
+            };
+
+            return match skipped {
+                Some(true) => continue,
+                Some(false) => Some(Err(self.error(start, "unterminated markup"))),
+                None if rest.starts_with("</") => Some(self.end_tag(start)),
+                None => Some(self.start_tag(start)),
+            };
+        }
+    }
+}
+
+fn is_delimiter(b: u8) -> bool {
+    b.is_ascii_whitespace() || b == b'>' || b == b'/'
+}
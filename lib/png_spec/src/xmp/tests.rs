@@ -0,0 +1,127 @@
+use super::*;
+
+const DC: &str = "http://purl.org/dc/elements/1.1/";
+const XMP: &str = "http://ns.adobe.com/xap/1.0/";
+
+fn testing_packet() -> String {
+    r#"<?xpacket begin="﻿" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmp:CreatorTool="GIMP &amp; friends">
+   <dc:format>image/png</dc:format>
+   <dc:creator>
+    <rdf:Seq>
+     <rdf:li>Alice</rdf:li>
+     <rdf:li>Bob</rdf:li>
+    </rdf:Seq>
+   </dc:creator>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#
+        .to_owned()
+}
+
+#[test]
+fn test_xmp_properties() {
+    let xmp = Xmp::from_packet(testing_packet());
+    let properties = xmp.properties().unwrap();
+
+    assert_eq!(properties.len(), 3);
+    assert_eq!(properties[0].namespace, XMP);
+    assert_eq!(properties[0].prefix, "xmp");
+    assert_eq!(properties[0].name, "CreatorTool");
+    assert_eq!(properties[0].value, "GIMP & friends");
+    assert_eq!(xmp.get(DC, "format").unwrap().unwrap(), "image/png");
+    assert_eq!(xmp.get(DC, "creator").unwrap().unwrap(), "Alice; Bob");
+    assert!(xmp.get(DC, "title").unwrap().is_none());
+}
+
+#[test]
+fn test_xmp_set_existing_keeps_packet() {
+    let mut xmp = Xmp::from_packet(testing_packet());
+    xmp.set(DC, "dc", "format", "image/x-png").unwrap();
+    xmp.set(XMP, "xmp", "CreatorTool", "pngme <1.0>").unwrap();
+
+    assert_eq!(xmp.get(DC, "format").unwrap().unwrap(), "image/x-png");
+    assert_eq!(xmp.get(XMP, "CreatorTool").unwrap().unwrap(), "pngme <1.0>");
+
+    let expected = testing_packet()
+        .replace("image/png", "image/x-png")
+        .replace("GIMP &amp; friends", "pngme &lt;1.0&gt;");
+    assert_eq!(xmp.packet(), expected);
+}
+
+#[test]
+fn test_xmp_set_array() {
+    let mut xmp = Xmp::from_packet(testing_packet());
+    xmp.set(DC, "dc", "creator", "Carol").unwrap();
+    assert_eq!(xmp.get(DC, "creator").unwrap().unwrap(), "Carol");
+}
+
+#[test]
+fn test_xmp_set_self_closing() {
+    let packet = testing_packet().replace(
+        "<dc:format>image/png</dc:format>",
+        "<dc:format/>\n   <dc:rights xml:lang=\"en\" />",
+    );
+    let mut xmp = Xmp::from_packet(packet.clone());
+    assert_eq!(xmp.get(DC, "format").unwrap().unwrap(), "");
+
+    xmp.set(DC, "dc", "format", "image/png").unwrap();
+    xmp.set(DC, "dc", "rights", "CC0").unwrap();
+    assert_eq!(xmp.get(DC, "format").unwrap().unwrap(), "image/png");
+    assert_eq!(xmp.get(DC, "rights").unwrap().unwrap(), "CC0");
+    let expected = packet
+        .replace("<dc:format/>", "<dc:format>image/png</dc:format>")
+        .replace(
+            "<dc:rights xml:lang=\"en\" />",
+            "<dc:rights xml:lang=\"en\" >CC0</dc:rights>",
+        );
+    assert_eq!(xmp.packet(), expected);
+}
+
+#[test]
+fn test_xmp_set_new_property() {
+    let mut xmp = Xmp::from_packet(testing_packet());
+    xmp.set(XMP, "xmp", "Rating", "5").unwrap();
+    xmp.set("http://example.com/ns/", "ex", "id", "42").unwrap();
+
+    assert_eq!(xmp.get(XMP, "Rating").unwrap().unwrap(), "5");
+    assert_eq!(
+        xmp.get("http://example.com/ns/", "id").unwrap().unwrap(),
+        "42"
+    );
+    assert_eq!(xmp.get(DC, "format").unwrap().unwrap(), "image/png");
+}
+
+#[test]
+fn test_xmp_set_prefix_conflict() {
+    let mut xmp = Xmp::from_packet(testing_packet());
+    assert!(xmp.set("http://example.com/ns/", "dc", "id", "42").is_err());
+}
+
+#[test]
+fn test_xmp_new_packet() {
+    let mut xmp = Xmp::new();
+    assert!(xmp.properties().unwrap().is_empty());
+    xmp.set(DC, "dc", "format", "image/png").unwrap();
+    assert_eq!(xmp.get(DC, "format").unwrap().unwrap(), "image/png");
+}
+
+#[test]
+fn test_xmp_malformed() {
+    let xmp = Xmp::from_packet("<x:xmpmeta><rdf:RDF></x:xmpmeta>".to_owned());
+    assert!(xmp.properties().is_err());
+}
+
+#[test]
+fn test_xmp_itxt_round_trip() {
+    let xmp = Xmp::from_packet(testing_packet());
+    let chunk = xmp.to_text().to_chunk().unwrap();
+    let text = InternationalText::try_from(&chunk).unwrap();
+    assert_eq!(Xmp::try_from(&text).unwrap(), xmp);
+}
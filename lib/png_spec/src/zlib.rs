@@ -0,0 +1,81 @@
+//! Pieces of the zlib format needed to assemble a stream from independently deflated blocks.
+//!
+//! ['ZLIB Compressed Data Format'](https://www.rfc-editor.org/rfc/rfc1950)
+
+use flate2::{Compress, Compression, FlushCompress, Status};
+
+#[cfg(test)]
+mod tests;
+
+/// Largest prime smaller than 65536
+const ADLER_MODULUS: u32 = 65521;
+
+/// Adler-32 checksum of `data`.
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the largest n such that 255n(n+1)/2 + (n+1)(MOD-1) fits in a u32
+    for part in data.chunks(5552) {
+        for &byte in part {
+            a += byte as u32;
+            b += a;
+        }
+        a %= ADLER_MODULUS;
+        b %= ADLER_MODULUS;
+    }
+    (b << 16) | a
+}
+
+/// Adler-32 of the concatenation of two inputs, from their checksums and the length of the
+/// second input.
+pub fn adler32_combine(first: u32, second: u32, second_length: usize) -> u32 {
+    let length = (second_length % ADLER_MODULUS as usize) as u32;
+    let (a1, b1) = (first & 0xffff, first >> 16);
+    let (a2, b2) = (second & 0xffff, second >> 16);
+
+    let a = (a1 + a2 + ADLER_MODULUS - 1) % ADLER_MODULUS;
+    let b = (b1 + b2 + ADLER_MODULUS - length) % ADLER_MODULUS;
+    let b = (b + (length as u64 * a1 as u64 % ADLER_MODULUS as u64) as u32) % ADLER_MODULUS;
+    (b << 16) | a
+}
+
+/// Two byte zlib header for deflate with a 32K window, with the level hint used by zlib.
+pub fn header(level: u32) -> [u8; 2] {
+    let cmf: u8 = 0x78;
+    let level_hint: u8 = match level {
+        0..=1 => 0,
+        2..=5 => 1,
+        6 => 2,
+        _ => 3,
+    };
+    let flg = level_hint << 6;
+    // FCHECK makes CMF * 256 + FLG a multiple of 31
+    let check = 31 - ((cmf as u16 * 256 + flg as u16) % 31) as u8;
+    [cmf, flg | (check % 31)]
+}
+
+/// Raw deflate of `data`. Blocks that are not `last` end with a sync flush, so they are byte
+/// aligned and can be concatenated with the next block's output.
+pub fn deflate_block(data: &[u8], level: u32, last: bool) -> Vec<u8> {
+    let mut compress = Compress::new(Compression::new(level), false);
+    let flush = if last {
+        FlushCompress::Finish
+    } else {
+        FlushCompress::Sync
+    };
+
+    let mut out: Vec<u8> = Vec::with_capacity(data.len() / 2 + 64);
+    loop {
+        if out.len() == out.capacity() {
+            out.reserve(out.capacity().max(64));
+        }
+        let consumed = compress.total_in() as usize;
+        let status = compress
+            .compress_vec(&data[consumed..], &mut out, flush)
+            .expect("deflate of in-memory data cannot fail");
+
+        let done = compress.total_in() as usize == data.len() && out.len() < out.capacity();
+        if status == Status::StreamEnd || (!last && done) {
+            return out;
+        }
+    }
+}
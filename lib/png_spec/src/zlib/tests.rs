@@ -0,0 +1,60 @@
+use super::*;
+use flate2::read::ZlibDecoder;
+use std::io::Read;
+
+#[test]
+fn test_adler32() {
+    assert_eq!(adler32(b""), 1);
+    assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    let long = vec![0xff; 100_000];
+    assert_eq!(adler32(&long), flate2_adler(&long));
+}
+
+fn flate2_adler(data: &[u8]) -> u32 {
+    let stream = {
+        let mut s = header(1).to_vec();
+        s.extend(deflate_block(data, 1, true));
+        s
+    };
+    let mut e = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+    std::io::Write::write_all(&mut e, data).unwrap();
+    let reference = e.finish().unwrap();
+    assert_eq!(stream[2..], reference[2..reference.len() - 4]);
+    u32::from_be_bytes(reference[reference.len() - 4..].try_into().unwrap())
+}
+
+#[test]
+fn test_adler32_combine() {
+    let data = b"The quick brown fox jumps over the lazy dog";
+    for split in [0, 1, 10, data.len()] {
+        let (first, second) = data.split_at(split);
+        let combined = adler32_combine(adler32(first), adler32(second), second.len());
+        assert_eq!(combined, adler32(data));
+    }
+}
+
+#[test]
+fn test_header_check() {
+    for level in 0..=9 {
+        let [cmf, flg] = header(level);
+        assert_eq!((cmf as u16 * 256 + flg as u16) % 31, 0);
+    }
+}
+
+#[test]
+fn test_joined_blocks_inflate() {
+    let data: Vec<u8> = (0..50_000u32).map(|i| (i * i % 251) as u8).collect();
+    let (first, second) = data.split_at(12_345);
+
+    let mut stream = header(6).to_vec();
+    stream.extend(deflate_block(first, 6, false));
+    stream.extend(deflate_block(second, 6, true));
+    let adler = adler32_combine(adler32(first), adler32(second), second.len());
+    stream.extend(adler.to_be_bytes());
+
+    let mut inflated = Vec::new();
+    ZlibDecoder::new(stream.as_slice())
+        .read_to_end(&mut inflated)
+        .unwrap();
+    assert_eq!(inflated, data);
+}